@@ -24,7 +24,7 @@ async fn main() -> Result<()> {
             match name {
                 "echo" => {
                     let message = arguments.get("message").and_then(|v| v.as_str()).unwrap_or("no message");
-                    Ok(vec![ResultContent::Text { text: format!("Echo: {}", message) }])
+                    Ok(vec![ResultContent::text(format!("Echo: {}", message))])
                 }
                 "calculator" => {
                     let op = arguments.get("operation").and_then(|v| v.as_str()).unwrap_or("add");
@@ -39,7 +39,7 @@ async fn main() -> Result<()> {
                         "sqrt" => if a >= 0.0 { a.sqrt() } else { return Err(Error::InvalidRequest("Cannot take sqrt of negative".to_string())) },
                         _ => return Err(Error::InvalidRequest(format!("Unknown operation: {}", op))),
                     };
-                    Ok(vec![ResultContent::Text { text: format!("Result: {}", result) }])
+                    Ok(vec![ResultContent::text(format!("Result: {}", result))])
                 }
                 "get_weather" => {
                     let location = arguments.get("location").and_then(|v| v.as_str()).unwrap_or("unknown");
@@ -51,28 +51,28 @@ async fn main() -> Result<()> {
                         "tokyo" => "75°F, Clear, 55% humidity",
                         _ => "72°F, Sunny, 50% humidity",
                     };
-                    Ok(vec![ResultContent::Text { text: format!("Weather in {}: {}", location, weather) }])
+                    Ok(vec![ResultContent::text(format!("Weather in {}: {}", location, weather))])
                 }
                 "search_text" => {
                     let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
                     let pattern = arguments.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
                     let count = text.matches(pattern).count();
-                    Ok(vec![ResultContent::Text { text: format!("Found {} occurrence(s) of '{}' in text", count, pattern) }])
+                    Ok(vec![ResultContent::text(format!("Found {} occurrence(s) of '{}' in text", count, pattern))])
                 }
                 "string_length" => {
                     let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                    Ok(vec![ResultContent::Text { text: format!("String length: {} characters", text.len()) }])
+                    Ok(vec![ResultContent::text(format!("String length: {} characters", text.len()))])
                 }
                 "text_reverse" => {
                     let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
                     let reversed: String = text.chars().rev().collect();
-                    Ok(vec![ResultContent::Text { text: format!("Reversed text: {}", reversed) }])
+                    Ok(vec![ResultContent::text(format!("Reversed text: {}", reversed))])
                 }
                 "json_parser" => {
                     let json_str = arguments.get("json").and_then(|v| v.as_str()).unwrap_or("{}");
                     match serde_json::from_str::<serde_json::Value>(json_str) {
-                        Ok(parsed) => Ok(vec![ResultContent::Text { text: format!("Valid JSON: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default()) }]),
-                        Err(e) => Ok(vec![ResultContent::Text { text: format!("Invalid JSON: {}", e) }]),
+                        Ok(parsed) => Ok(vec![ResultContent::text(format!("Valid JSON: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default()))]),
+                        Err(e) => Ok(vec![ResultContent::text(format!("Invalid JSON: {}", e))]),
                     }
                 }
                 "http_status" => {
@@ -88,7 +88,7 @@ async fn main() -> Result<()> {
                         503 => "Service Unavailable",
                         _ => "Unknown status code",
                     };
-                    Ok(vec![ResultContent::Text { text: format!("HTTP {}: {}", code, status) }])
+                    Ok(vec![ResultContent::text(format!("HTTP {}: {}", code, status))])
                 }
                 _ => Err(Error::ToolNotFound(name.to_string())),
             }
@@ -100,11 +100,8 @@ async fn main() -> Result<()> {
     let config = ServerConfig {
         name: "Demo Tools Server".to_string(),
         version: "1.0.0".to_string(),
-        capabilities: ServerCapabilities {
-            tools: Some(ToolsCapability { list_changed: Some(false) }),
-            resources: None,
-            prompts: None,
-        },
+        capabilities: ServerCapabilities::default().with_tools(false),
+        ..ServerConfig::default()
     };
 
     let server = Arc::new(McpServer::new(config, handler));
@@ -113,6 +110,7 @@ async fn main() -> Result<()> {
     let tools = vec![
         Tool {
             name: "echo".to_string(),
+            title: None,
             description: Some("Echo back a message".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -122,10 +120,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["message".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "calculator".to_string(),
+            title: None,
             description: Some("Perform math operations: add, subtract, multiply, divide, power, sqrt".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -137,10 +140,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["operation".to_string(), "a".to_string(), "b".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "get_weather".to_string(),
+            title: None,
             description: Some("Get current weather for a city (supports: san francisco, new york, los angeles, london, tokyo)".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -150,10 +158,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["location".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "search_text".to_string(),
+            title: None,
             description: Some("Search for pattern occurrences in text".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -164,10 +177,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["text".to_string(), "pattern".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "string_length".to_string(),
+            title: None,
             description: Some("Get the length of a string in characters".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -177,10 +195,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["text".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "text_reverse".to_string(),
+            title: None,
             description: Some("Reverse a text string".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -190,10 +213,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["text".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "json_parser".to_string(),
+            title: None,
             description: Some("Validate and parse JSON strings, returns formatted output".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -203,10 +231,15 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["json".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "http_status".to_string(),
+            title: None,
             description: Some("Look up the meaning of HTTP status codes".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
@@ -216,7 +249,11 @@ async fn main() -> Result<()> {
                     p
                 },
                 required: Some(vec!["code".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
     ];
 
@@ -247,43 +284,13 @@ async fn main() -> Result<()> {
         max_tokens: Some(2048),
     };
 
-    // Start JSON-RPC HTTP server in background task
+    // Start Streamable HTTP server in background task
     let server_clone = server.clone();
     let _server_task = tokio::spawn(async move {
-        use axum::{
-            extract::State,
-            http::StatusCode,
-            routing::post,
-            Json, Router,
-        };
-        use tokio::net::TcpListener;
-
-        #[derive(Clone)]
-        struct ServerState {
-            server: Arc<McpServer>,
-        }
-
-        // JSON-RPC endpoint handler
-        async fn handle_rpc(
-            State(state): State<ServerState>,
-            Json(request): Json<JsonRpcRequest>,
-        ) -> (StatusCode, Json<JsonRpcResponse>) {
-            let response = state.server.handle_request(request).await;
-            (StatusCode::OK, Json(response))
-        }
-
-        let state = ServerState { server: server_clone };
-        let router = Router::new()
-            .route("/", post(handle_rpc))
-            .with_state(state);
-
-        let listener = TcpListener::bind("127.0.0.1:3000")
-            .await
-            .expect("Failed to bind to 127.0.0.1:3000");
-
         println!("MCP Server listening on http://localhost:3000");
 
-        axum::serve(listener, router)
+        server_clone
+            .serve_http("127.0.0.1:3000")
             .await
             .expect("Failed to start server");
     });
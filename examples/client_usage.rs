@@ -32,7 +32,7 @@ async fn main() -> mcp_framework::Result<()> {
             println!("Tool result:");
             for content in &result.content {
                 match content {
-                    mcp_framework::protocol::ResultContent::Text { text } => println!("  {}", text),
+                    mcp_framework::protocol::ResultContent::Text { text, .. } => println!("  {}", text),
                     _ => println!("  [Binary content]"),
                 }
             }
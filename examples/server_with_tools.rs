@@ -7,13 +7,6 @@ use mcp_framework::server::{McpServer, ServerConfig, ToolHandler};
 use mcp_framework::inspector::Inspector;
 use std::sync::Arc;
 use serde_json::{json, Value};
-use axum::{
-    extract::State,
-    http::StatusCode,
-    routing::post,
-    Json, Router,
-};
-use tokio::net::TcpListener;
 
 struct ToolsHandler;
 
@@ -23,7 +16,7 @@ impl ToolHandler for ToolsHandler {
         match name {
             "echo" => {
                 let message = arguments.get("message").and_then(|v| v.as_str()).unwrap_or("no message");
-                Ok(vec![ResultContent::Text { text: format!("Echo: {}", message) }])
+                Ok(vec![ResultContent::text(format!("Echo: {}", message))])
             }
             "calculator" => {
                 let op = arguments.get("operation").and_then(|v| v.as_str()).unwrap_or("add");
@@ -38,7 +31,7 @@ impl ToolHandler for ToolsHandler {
                     "sqrt" => if a >= 0.0 { a.sqrt() } else { return Err(mcp_framework::error::Error::InvalidRequest("Cannot take sqrt of negative".to_string())) },
                     _ => return Err(mcp_framework::error::Error::InvalidRequest(format!("Unknown operation: {}", op))),
                 };
-                Ok(vec![ResultContent::Text { text: format!("Result: {}", result) }])
+                Ok(vec![ResultContent::text(format!("Result: {}", result))])
             }
             "get_weather" => {
                 let location = arguments.get("location").and_then(|v| v.as_str()).unwrap_or("unknown");
@@ -50,28 +43,28 @@ impl ToolHandler for ToolsHandler {
                     "tokyo" => "75°F, Clear, 55% humidity",
                     _ => "72°F, Sunny, 50% humidity",
                 };
-                Ok(vec![ResultContent::Text { text: format!("Weather in {}: {}", location, weather) }])
+                Ok(vec![ResultContent::text(format!("Weather in {}: {}", location, weather))])
             }
             "search_text" => {
                 let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
                 let pattern = arguments.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
                 let count = text.matches(pattern).count();
-                Ok(vec![ResultContent::Text { text: format!("Found {} occurrence(s) of '{}' in text", count, pattern) }])
+                Ok(vec![ResultContent::text(format!("Found {} occurrence(s) of '{}' in text", count, pattern))])
             }
             "string_length" => {
                 let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                Ok(vec![ResultContent::Text { text: format!("String length: {} characters", text.len()) }])
+                Ok(vec![ResultContent::text(format!("String length: {} characters", text.len()))])
             }
             "text_reverse" => {
                 let text = arguments.get("text").and_then(|v| v.as_str()).unwrap_or("");
                 let reversed: String = text.chars().rev().collect();
-                Ok(vec![ResultContent::Text { text: format!("Reversed text: {}", reversed) }])
+                Ok(vec![ResultContent::text(format!("Reversed text: {}", reversed))])
             }
             "json_parser" => {
                 let json_str = arguments.get("json").and_then(|v| v.as_str()).unwrap_or("{}");
                 match serde_json::from_str::<serde_json::Value>(json_str) {
-                    Ok(parsed) => Ok(vec![ResultContent::Text { text: format!("Valid JSON: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default()) }]),
-                    Err(e) => Ok(vec![ResultContent::Text { text: format!("Invalid JSON: {}", e) }]),
+                    Ok(parsed) => Ok(vec![ResultContent::text(format!("Valid JSON: {}", serde_json::to_string_pretty(&parsed).unwrap_or_default()))]),
+                    Err(e) => Ok(vec![ResultContent::text(format!("Invalid JSON: {}", e))]),
                 }
             }
             "http_status" => {
@@ -87,27 +80,13 @@ impl ToolHandler for ToolsHandler {
                     503 => "Service Unavailable",
                     _ => "Unknown status code",
                 };
-                Ok(vec![ResultContent::Text { text: format!("HTTP {}: {}", code, status) }])
+                Ok(vec![ResultContent::text(format!("HTTP {}: {}", code, status))])
             }
             _ => Err(mcp_framework::error::Error::ToolNotFound(name.to_string())),
         }
     }
 }
 
-#[derive(Clone)]
-struct ServerState {
-    server: Arc<McpServer>,
-}
-
-// Handler: POST / (JSON-RPC endpoint)
-async fn handle_rpc(
-    State(state): State<ServerState>,
-    Json(request): Json<JsonRpcRequest>,
-) -> (StatusCode, Json<JsonRpcResponse>) {
-    let response = state.server.handle_request(request).await;
-    (StatusCode::OK, Json(response))
-}
-
 #[tokio::main]
 async fn main() -> mcp_framework::Result<()> {
     println!("MCP Framework - 8 Tools Example with Inspector\n");
@@ -115,11 +94,8 @@ async fn main() -> mcp_framework::Result<()> {
     let config = ServerConfig {
         name: "Tools Server".to_string(),
         version: "1.0.0".to_string(),
-        capabilities: ServerCapabilities {
-            tools: Some(ToolsCapability { list_changed: Some(false) }),
-            resources: None,
-            prompts: None,
-        },
+        capabilities: ServerCapabilities::default().with_tools(false),
+        ..ServerConfig::default()
     };
 
     let tool_handler = Arc::new(ToolsHandler);
@@ -129,75 +105,115 @@ async fn main() -> mcp_framework::Result<()> {
     let tools_to_register = vec![
         Tool {
             name: "echo".to_string(),
+            title: None,
             description: Some("Echo back a message".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("message".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["message".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "calculator".to_string(),
+            title: None,
             description: Some("Math operations: add, subtract, multiply, divide, power, sqrt".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("operation".to_string(), json!({ "type": "string", "enum": ["add", "subtract", "multiply", "divide", "power", "sqrt"] })); p.insert("a".to_string(), json!({ "type": "number" })); p.insert("b".to_string(), json!({ "type": "number" })); p },
                 required: Some(vec!["operation".to_string(), "a".to_string(), "b".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "get_weather".to_string(),
+            title: None,
             description: Some("Get weather for cities worldwide".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("location".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["location".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "search_text".to_string(),
+            title: None,
             description: Some("Search for text pattern occurrences".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("text".to_string(), json!({ "type": "string" })); p.insert("pattern".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["text".to_string(), "pattern".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "string_length".to_string(),
+            title: None,
             description: Some("Get the length of a string".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("text".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["text".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "text_reverse".to_string(),
+            title: None,
             description: Some("Reverse a text string".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("text".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["text".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "json_parser".to_string(),
+            title: None,
             description: Some("Validate and parse JSON strings".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("json".to_string(), json!({ "type": "string" })); p },
                 required: Some(vec!["json".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
         Tool {
             name: "http_status".to_string(),
+            title: None,
             description: Some("Look up HTTP status codes".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: { let mut p = std::collections::HashMap::new(); p.insert("code".to_string(), json!({ "type": "integer" })); p },
                 required: Some(vec!["code".to_string()]),
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         },
     ];
 
@@ -216,22 +232,12 @@ async fn main() -> mcp_framework::Result<()> {
     inspector.set_server(server.clone());
 
     // Start MCP server on port 3000 (in background task)
-    let server_state = ServerState {
-        server: server.clone(),
-    };
-
+    let mcp_server = server.clone();
     let server_task = tokio::spawn(async move {
-        let router = Router::new()
-            .route("/", post(handle_rpc))
-            .with_state(server_state);
-
-        let listener = TcpListener::bind("127.0.0.1:3000")
-            .await
-            .expect("Failed to bind MCP server to 127.0.0.1:3000");
-
-        println!("JSON-RPC Server listening on http://localhost:3000");
+        println!("Streamable HTTP server listening on http://localhost:3000");
 
-        axum::serve(listener, router)
+        mcp_server
+            .serve_http("127.0.0.1:3000")
             .await
             .expect("Failed to start MCP server");
     });
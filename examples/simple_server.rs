@@ -16,12 +16,12 @@ impl ToolHandler for CalculatorHandler {
             "add" => {
                 let a = arguments.get("a").and_then(|v| v.as_f64()).unwrap_or(0.0);
                 let b = arguments.get("b").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                Ok(vec![ResultContent::Text { text: format!("{} + {} = {}", a, b, a + b) }])
+                Ok(vec![ResultContent::text(format!("{} + {} = {}", a, b, a + b))])
             }
             "multiply" => {
                 let a = arguments.get("a").and_then(|v| v.as_f64()).unwrap_or(0.0);
                 let b = arguments.get("b").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                Ok(vec![ResultContent::Text { text: format!("{} * {} = {}", a, b, a * b) }])
+                Ok(vec![ResultContent::text(format!("{} * {} = {}", a, b, a * b))])
             }
             _ => Err(mcp_framework::error::Error::ToolNotFound(name.to_string())),
         }
@@ -35,11 +35,8 @@ async fn main() -> mcp_framework::Result<()> {
     let config = ServerConfig {
         name: "Calculator Server".to_string(),
         version: "1.0.0".to_string(),
-        capabilities: ServerCapabilities {
-            tools: Some(ToolsCapability { list_changed: Some(false) }),
-            resources: None,
-            prompts: None,
-        },
+        capabilities: ServerCapabilities::default().with_tools(false),
+        ..ServerConfig::default()
     };
 
     let handler = Arc::new(CalculatorHandler);
@@ -48,6 +45,7 @@ async fn main() -> mcp_framework::Result<()> {
     // Register two simple tools
     server.register_tool(Tool {
         name: "add".to_string(),
+        title: None,
         description: Some("Add two numbers".to_string()),
         input_schema: Some(ToolInputSchema {
             schema_type: "object".to_string(),
@@ -58,11 +56,16 @@ async fn main() -> mcp_framework::Result<()> {
                 p
             },
             required: Some(vec!["a".to_string(), "b".to_string()]),
+            defs: None,
         }),
+        output_schema: None,
+        annotations: None,
+        meta: None,
     });
 
     server.register_tool(Tool {
         name: "multiply".to_string(),
+        title: None,
         description: Some("Multiply two numbers".to_string()),
         input_schema: Some(ToolInputSchema {
             schema_type: "object".to_string(),
@@ -73,7 +76,11 @@ async fn main() -> mcp_framework::Result<()> {
                 p
             },
             required: Some(vec!["a".to_string(), "b".to_string()]),
+            defs: None,
         }),
+        output_schema: None,
+        annotations: None,
+        meta: None,
     });
 
     println!("✅ Server ready with 2 tools: add, multiply");
@@ -0,0 +1,70 @@
+//! MCP server over stdio, for clients that launch it as a subprocess.
+//!
+//! cargo build --example stdio_server
+//! Client URL: stdio://target/debug/examples/stdio_server
+
+use mcp_framework::prelude::*;
+use mcp_framework::server::{McpServer, ServerConfig, ToolHandler};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+struct EchoHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for EchoHandler {
+    async fn execute(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> mcp_framework::Result<Vec<ResultContent>> {
+        match name {
+            "echo" => {
+                let message = arguments
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("no message");
+                tracing::info!("echo called with {:?}", message);
+                Ok(vec![ResultContent::text(format!("Echo: {}", message))])
+            }
+            _ => Err(mcp_framework::error::Error::ToolNotFound(name.to_string())),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> mcp_framework::Result<()> {
+    // Stdout carries the protocol, so logs must go to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let config = ServerConfig {
+        name: "Stdio Echo Server".to_string(),
+        version: "1.0.0".to_string(),
+        capabilities: ServerCapabilities::default().with_tools(false),
+        ..ServerConfig::default()
+    };
+
+    let server = Arc::new(McpServer::new(config, Arc::new(EchoHandler)));
+
+    server.register_tool(Tool {
+        name: "echo".to_string(),
+        title: None,
+        description: Some("Echo back a message".to_string()),
+        input_schema: Some(ToolInputSchema {
+            schema_type: "object".to_string(),
+            properties: {
+                let mut p = std::collections::HashMap::new();
+                p.insert("message".to_string(), json!({ "type": "string" }));
+                p
+            },
+            required: Some(vec!["message".to_string()]),
+            defs: None,
+        }),
+        output_schema: None,
+        annotations: None,
+        meta: None,
+    });
+
+    server.serve_stdio().await
+}
@@ -0,0 +1,67 @@
+//! MCP server with typed per-tool handlers.
+//!
+//! cargo run --example typed_tools
+
+use mcp_framework::prelude::*;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+struct AddArgs {
+    /// First operand
+    a: f64,
+    /// Second operand
+    b: f64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct ConvertArgs {
+    value: f64,
+    /// Unit to convert to
+    to: Unit,
+}
+
+/// Reverse a string
+#[mcp_tool]
+async fn reverse(text: String) -> mcp_framework::Result<String> {
+    Ok(text.chars().rev().collect())
+}
+
+#[tokio::main]
+async fn main() -> mcp_framework::Result<()> {
+    let config = ServerConfig {
+        name: "Typed Tools Server".to_string(),
+        version: "1.0.0".to_string(),
+        capabilities: ServerCapabilities::default().with_tools(false),
+        ..ServerConfig::default()
+    };
+
+    let server = Arc::new(McpServer::with_config(config));
+
+    server
+        .tool("add", "Add two numbers", |args: AddArgs| async move {
+            Ok(format!("{} + {} = {}", args.a, args.b, args.a + args.b))
+        })
+        .tool(
+            "convert_temperature",
+            "Convert a temperature",
+            |args: ConvertArgs| async move {
+                let converted = match args.to {
+                    Unit::Celsius => (args.value - 32.0) * 5.0 / 9.0,
+                    Unit::Fahrenheit => args.value * 9.0 / 5.0 + 32.0,
+                };
+                Ok(format!("{:.1}", converted))
+            },
+        )
+        .register(reverse_tool());
+
+    println!("✅ Typed tools server on http://127.0.0.1:3000");
+    server.serve_http("127.0.0.1:3000").await
+}
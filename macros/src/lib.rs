@@ -0,0 +1,613 @@
+//! Procedural macros for `mcp_framework`.
+//!
+//! - `#[mcp_tool]` on an async fn generates a `<name>_tool()` function that
+//!   returns its `ToolDefinition`. The tool name is the fn name, the
+//!   description comes from the doc comments, and the input schema is
+//!   generated from the parameter types.
+//! - `#[mcp_server]` on an impl block turns each `#[mcp_tool]` method into a
+//!   tool and adds `tool_definitions` / `register_tools` to register them all
+//!   with an `McpServer` in one call.
+//!
+//! Both accept `name = "..."` and `description = "..."` overrides on
+//! `#[mcp_tool(...)]`, a `title = "..."`, and the behavior hints
+//! `read_only`, `destructive`, `idempotent` and `open_world` (bare, or set
+//! to `true` / `false`) that become the tool's annotations. A parameter of type `RequestContext` (or
+//! `&RequestContext`) receives the request's context instead of an argument.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Expr, FnArg, Ident, ImplItem, ItemFn, ItemImpl, Lit, LitBool,
+    LitStr, Meta, Pat, Signature, Token, Type,
+};
+
+/// Generate an MCP tool definition from an async fn.
+///
+/// ```ignore
+/// /// Add two numbers
+/// #[mcp_tool]
+/// async fn add(a: f64, b: f64) -> Result<String> {
+///     Ok(format!("{}", a + b))
+/// }
+///
+/// /// Delete a file
+/// #[mcp_tool(title = "Delete File", destructive, idempotent)]
+/// async fn delete_file(path: String) -> Result<String> { ... }
+///
+/// server.register(add_tool());
+/// ```
+#[proc_macro_attribute]
+pub fn mcp_tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = ToolOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(attr with parser);
+
+    let item = parse_macro_input!(item as ItemFn);
+    expand_tool_fn(options, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Register every `#[mcp_tool]` method of an impl block as an MCP tool.
+///
+/// ```ignore
+/// #[mcp_server]
+/// impl Calculator {
+///     /// Add two numbers
+///     #[mcp_tool]
+///     async fn add(&self, a: f64, b: f64) -> Result<String> {
+///         Ok(format!("{}", a + b))
+///     }
+/// }
+///
+/// Arc::new(Calculator).register_tools(&server);
+/// ```
+#[proc_macro_attribute]
+pub fn mcp_server(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            TokenStream2::from(attr).span(),
+            "#[mcp_server] takes no arguments",
+        )
+        .into_compile_error()
+        .into();
+    }
+
+    let item = parse_macro_input!(item as ItemImpl);
+    expand_server(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ToolOptions {
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+    title: Option<LitStr>,
+    /// Annotation hints, by the `ToolAnnotations` builder method that sets them
+    hints: Vec<(Ident, LitBool)>,
+}
+
+impl ToolOptions {
+    const HINTS: [&'static str; 4] = ["read_only", "destructive", "idempotent", "open_world"];
+
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("description") {
+            self.description = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("title") {
+            self.title = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if let Some(hint) = meta
+            .path
+            .get_ident()
+            .filter(|ident| Self::HINTS.iter().any(|h| *ident == h))
+        {
+            // A bare hint means `true`
+            let value = if meta.input.peek(Token![=]) {
+                meta.value()?.parse()?
+            } else {
+                LitBool::new(true, hint.span())
+            };
+            self.hints.push((hint.clone(), value));
+            Ok(())
+        } else {
+            Err(meta.error(
+                "unsupported #[mcp_tool] option, expected `name`, `description`, `title`, \
+                 `read_only`, `destructive`, `idempotent` or `open_world`",
+            ))
+        }
+    }
+
+    fn from_attribute(attr: &Attribute) -> syn::Result<Self> {
+        let mut options = Self::default();
+        if let Meta::List(_) = attr.meta {
+            attr.parse_nested_meta(|meta| options.parse(meta))?;
+        }
+        Ok(options)
+    }
+}
+
+/// A tool parameter, which becomes a field of the generated arguments struct
+struct Param {
+    ident: Ident,
+    ty: Type,
+}
+
+/// The parts of a tool fn needed to generate its definition
+struct ToolFn {
+    name: LitStr,
+    description: LitStr,
+    title: Option<LitStr>,
+    hints: Vec<(Ident, LitBool)>,
+    has_receiver: bool,
+    params: Vec<Param>,
+    /// Expressions passed to the fn, in parameter order
+    call_args: Vec<TokenStream2>,
+    uses_context: bool,
+}
+
+impl ToolFn {
+    fn new(options: ToolOptions, attrs: &[Attribute], sig: &Signature) -> syn::Result<Self> {
+        if sig.asyncness.is_none() {
+            return Err(syn::Error::new(
+                sig.fn_token.span(),
+                "#[mcp_tool] requires an async fn",
+            ));
+        }
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                sig.generics.span(),
+                "#[mcp_tool] fns cannot be generic",
+            ));
+        }
+
+        let mut has_receiver = false;
+        let mut params = Vec::new();
+        let mut call_args = Vec::new();
+        let mut uses_context = false;
+        for input in &sig.inputs {
+            match input {
+                FnArg::Receiver(receiver) => {
+                    if receiver.reference.is_none() || receiver.mutability.is_some() {
+                        return Err(syn::Error::new(
+                            receiver.span(),
+                            "tool methods must take `&self`",
+                        ));
+                    }
+                    has_receiver = true;
+                }
+                FnArg::Typed(arg) => match (&*arg.pat, context_param(&arg.ty)) {
+                    (_, Some(by_ref)) => {
+                        if uses_context {
+                            return Err(syn::Error::new(
+                                arg.span(),
+                                "only one RequestContext parameter is allowed",
+                            ));
+                        }
+                        uses_context = true;
+                        call_args.push(if by_ref {
+                            quote! { &ctx }
+                        } else {
+                            quote! { ctx }
+                        });
+                    }
+                    (Pat::Ident(pat), None) => {
+                        let ident = pat.ident.clone();
+                        call_args.push(quote! { args.#ident });
+                        params.push(Param {
+                            ident,
+                            ty: (*arg.ty).clone(),
+                        });
+                    }
+                    (other, None) => {
+                        return Err(syn::Error::new(
+                            other.span(),
+                            "tool parameters must be plain identifiers",
+                        ));
+                    }
+                },
+            }
+        }
+
+        let name = options
+            .name
+            .unwrap_or_else(|| LitStr::new(&sig.ident.to_string(), sig.ident.span()));
+        let description = options
+            .description
+            .unwrap_or_else(|| LitStr::new(&doc_comment(attrs), sig.ident.span()));
+
+        Ok(Self {
+            name,
+            description,
+            title: options.title,
+            hints: options.hints,
+            has_receiver,
+            params,
+            call_args,
+            uses_context,
+        })
+    }
+
+    /// Expression building the `ToolDefinition`. For methods it expects a
+    /// `this: Arc<Self>` binding in scope.
+    fn definition(&self, call: TokenStream2) -> TokenStream2 {
+        let Self {
+            name, description, ..
+        } = self;
+        let mut extras = Vec::new();
+        if let Some(title) = &self.title {
+            extras.push(quote! { .with_title(#title) });
+        }
+        if !self.hints.is_empty() {
+            let (hints, values): (Vec<_>, Vec<_>) = self.hints.iter().cloned().unzip();
+            extras.push(quote! {
+                .with_annotations(::mcp_framework::protocol::ToolAnnotations::new() #( .#hints(#values) )*)
+            });
+        }
+        let idents: Vec<_> = self.params.iter().map(|p| &p.ident).collect();
+        let types: Vec<_> = self.params.iter().map(|p| &p.ty).collect();
+        let call_args = &self.call_args;
+
+        let (capture, clone) = if self.has_receiver {
+            (
+                quote! { let this = ::std::sync::Arc::clone(&this); },
+                quote! { let this = ::std::sync::Arc::clone(&this); },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+
+        let constructor = if self.uses_context {
+            quote! {
+                ::mcp_framework::server::ToolDefinition::typed_with_context(
+                    #name,
+                    #description,
+                    move |args: __McpToolArgs, ctx: ::mcp_framework::context::RequestContext| {
+                        #clone
+                        async move { #call(#( #call_args ),*).await }
+                    },
+                )
+            }
+        } else {
+            quote! {
+                ::mcp_framework::server::ToolDefinition::typed(
+                    #name,
+                    #description,
+                    move |args: __McpToolArgs| {
+                        #clone
+                        async move { #call(#( #call_args ),*).await }
+                    },
+                )
+            }
+        };
+
+        quote! {{
+            #[derive(::mcp_framework::__private::serde::Deserialize, ::mcp_framework::__private::schemars::JsonSchema)]
+            #[serde(crate = "::mcp_framework::__private::serde")]
+            #[schemars(crate = "::mcp_framework::__private::schemars")]
+            struct __McpToolArgs {
+                #( #idents: #types, )*
+            }
+
+            #capture
+            #constructor #( #extras )*
+        }}
+    }
+}
+
+fn expand_tool_fn(options: ToolOptions, item: ItemFn) -> syn::Result<TokenStream2> {
+    let tool = ToolFn::new(options, &item.attrs, &item.sig)?;
+    if tool.has_receiver {
+        return Err(syn::Error::new(
+            item.sig.ident.span(),
+            "#[mcp_tool] on a method requires #[mcp_server] on the impl block",
+        ));
+    }
+
+    let ident = &item.sig.ident;
+    let vis = &item.vis;
+    let tool_fn = format_ident!("{}_tool", ident);
+    let doc = format!(
+        "Definition of the `{}` tool, generated by `#[mcp_tool]`",
+        tool.name.value()
+    );
+    let definition = tool.definition(quote! { #ident });
+
+    Ok(quote! {
+        #item
+
+        #[doc = #doc]
+        #vis fn #tool_fn() -> ::mcp_framework::server::ToolDefinition {
+            #definition
+        }
+    })
+}
+
+fn expand_server(mut item: ItemImpl) -> syn::Result<TokenStream2> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new(
+            path.span(),
+            "#[mcp_server] must be used on an inherent impl block",
+        ));
+    }
+
+    let mut definitions = Vec::new();
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let Some(index) = method.attrs.iter().position(is_tool_attribute) else {
+            continue;
+        };
+
+        let attr = method.attrs.remove(index);
+        let tool = ToolFn::new(
+            ToolOptions::from_attribute(&attr)?,
+            &method.attrs,
+            &method.sig,
+        )?;
+        let ident = &method.sig.ident;
+        let call = if tool.has_receiver {
+            quote! { this.#ident }
+        } else {
+            quote! { Self::#ident }
+        };
+        definitions.push(tool.definition(call));
+    }
+
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let self_ty = &item.self_ty;
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #self_ty #where_clause {
+            /// Definitions of every `#[mcp_tool]` method
+            pub fn tool_definitions(self: ::std::sync::Arc<Self>) -> ::std::vec::Vec<::mcp_framework::server::ToolDefinition> {
+                #[allow(unused_variables)]
+                let this = self;
+                ::std::vec![ #( #definitions ),* ]
+            }
+
+            /// Register every `#[mcp_tool]` method with `server`
+            pub fn register_tools(self: ::std::sync::Arc<Self>, server: &::mcp_framework::server::McpServer) {
+                for definition in self.tool_definitions() {
+                    server.register(definition);
+                }
+            }
+        }
+    })
+}
+
+/// Whether a parameter type is `RequestContext`, and if so whether it is
+/// taken by reference
+fn context_param(ty: &Type) -> Option<bool> {
+    let (ty, by_ref) = match ty {
+        Type::Reference(reference) => (&*reference.elem, true),
+        ty => (ty, false),
+    };
+    match ty {
+        Type::Path(path)
+            if path
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "RequestContext") =>
+        {
+            Some(by_ref)
+        }
+        _ => None,
+    }
+}
+
+fn is_tool_attribute(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "mcp_tool")
+}
+
+/// Doc comment lines joined into a description
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(s) => Some(s.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    /// Whether `tokens` contains `expected`, ignoring token spacing
+    fn contains(tokens: &TokenStream2, expected: TokenStream2) -> bool {
+        let squash = |tokens: String| tokens.split_whitespace().collect::<String>();
+        squash(tokens.to_string()).contains(&squash(expected.to_string()))
+    }
+
+    fn tool(attr: Attribute, item: ItemFn) -> syn::Result<TokenStream2> {
+        expand_tool_fn(ToolOptions::from_attribute(&attr)?, item)
+    }
+
+    fn error<T>(result: syn::Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected a compile error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_title_and_hints() {
+        let tokens = tool(
+            parse_quote!(#[mcp_tool(title = "Remove File", read_only, destructive = false)]),
+            parse_quote! {
+                /// Remove a file
+                async fn remove(path: String) -> Result<String> { Ok(path) }
+            },
+        )
+        .unwrap();
+
+        assert!(contains(&tokens, quote! { fn remove_tool() }));
+        assert!(contains(
+            &tokens,
+            quote! { "remove", "Remove a file", move }
+        ));
+        assert!(contains(&tokens, quote! { .with_title("Remove File") }));
+        assert!(contains(
+            &tokens,
+            quote! {
+                .with_annotations(::mcp_framework::protocol::ToolAnnotations::new()
+                    .read_only(true).destructive(false))
+            }
+        ));
+
+        let tokens = tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn plain() -> Result<String> {
+                    todo!()
+                }
+            ),
+        )
+        .unwrap();
+        assert!(!contains(&tokens, quote! { with_annotations }));
+    }
+
+    #[test]
+    fn test_request_context_params() {
+        let tokens = tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn whoami(ctx: RequestContext) -> Result<String> {
+                    todo!()
+                }
+            ),
+        )
+        .unwrap();
+        assert!(contains(&tokens, quote! { typed_with_context }));
+        assert!(contains(&tokens, quote! { struct __McpToolArgs {} }));
+        assert!(contains(&tokens, quote! { whoami(ctx).await }));
+
+        let tokens = tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn count(n: u32, ctx: &RequestContext) -> Result<String> {
+                    todo!()
+                }
+            ),
+        )
+        .unwrap();
+        assert!(contains(
+            &tokens,
+            quote! { struct __McpToolArgs { n: u32, } }
+        ));
+        assert!(contains(&tokens, quote! { count(args.n, &ctx).await }));
+
+        let message = error(tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn twice(a: RequestContext, b: &RequestContext) -> Result<String> {
+                    todo!()
+                }
+            ),
+        ));
+        assert_eq!(message, "only one RequestContext parameter is allowed");
+    }
+
+    #[test]
+    fn test_server_methods() {
+        let tokens = expand_server(parse_quote! {
+            impl Greeter {
+                /// Greet someone
+                #[mcp_tool]
+                async fn greet(&self, name: String) -> Result<String> { todo!() }
+
+                #[mcp_tool(name = "server_time")]
+                async fn now() -> Result<String> { todo!() }
+
+                fn not_a_tool(&self) {}
+            }
+        })
+        .unwrap();
+
+        assert!(contains(&tokens, quote! { this.greet(args.name).await }));
+        assert!(contains(&tokens, quote! { Self::now().await }));
+        assert!(contains(&tokens, quote! { pub fn register_tools }));
+        // The tool attributes are consumed, other methods are left alone
+        assert!(!contains(&tokens, quote! { #[mcp_tool] }));
+        assert!(contains(&tokens, quote! { fn not_a_tool(&self) {} }));
+    }
+
+    #[test]
+    fn test_invalid_tools() {
+        let message = error(tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                fn add(a: f64) -> Result<String> {
+                    todo!()
+                }
+            ),
+        ));
+        assert_eq!(message, "#[mcp_tool] requires an async fn");
+
+        let message = error(tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn echo<T>(value: T) -> Result<String> {
+                    todo!()
+                }
+            ),
+        ));
+        assert_eq!(message, "#[mcp_tool] fns cannot be generic");
+
+        let message = error(expand_server(parse_quote! {
+            impl Counter {
+                #[mcp_tool]
+                async fn bump(&mut self) -> Result<String> { todo!() }
+            }
+        }));
+        assert_eq!(message, "tool methods must take `&self`");
+
+        let message = error(ToolOptions::from_attribute(
+            &parse_quote!(#[mcp_tool(hidden)]),
+        ));
+        assert!(
+            message.starts_with("unsupported #[mcp_tool] option"),
+            "{}",
+            message
+        );
+
+        let message = error(tool(
+            parse_quote!(#[mcp_tool]),
+            parse_quote!(
+                async fn greet(&self) -> Result<String> {
+                    todo!()
+                }
+            ),
+        ));
+        assert_eq!(
+            message,
+            "#[mcp_tool] on a method requires #[mcp_server] on the impl block"
+        );
+    }
+}
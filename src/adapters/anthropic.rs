@@ -1,6 +1,6 @@
 /// Anthropic Claude adapter.
 
-use crate::agent::LLMProvider;
+use crate::agent::{CallOptions, LLMProvider};
 use crate::protocol::{Message, Tool, ContentBlock, Role};
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
@@ -21,6 +21,8 @@ struct AnthropicToolInput {
     properties: std::collections::HashMap<String, Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     required: Option<Vec<String>>,
+    #[serde(rename = "$defs", skip_serializing_if = "Option::is_none")]
+    defs: Option<std::collections::HashMap<String, Value>>,
 }
 
 /// Anthropic API tool definition
@@ -41,6 +43,8 @@ struct AnthropicRequest {
     tools: Option<Vec<AnthropicTool>>,
     temperature: f32,
     system: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
 }
 
 /// Anthropic API response
@@ -115,10 +119,24 @@ impl AnthropicAdapter {
 
 #[async_trait::async_trait]
 impl LLMProvider for AnthropicAdapter {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
     async fn call(
         &self,
         messages: Vec<Message>,
         tools: Vec<Tool>,
+    ) -> Result<crate::agent::LLMResponse> {
+        self.call_with_options(messages, tools, CallOptions::default())
+            .await
+    }
+
+    async fn call_with_options(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<Tool>,
+        options: CallOptions,
     ) -> Result<crate::agent::LLMResponse> {
         // Convert MCP messages to Anthropic format
         let anthropic_messages: Vec<AnthropicMessage> = messages
@@ -133,14 +151,9 @@ impl LLMProvider for AnthropicAdapter {
                         }
                         ContentBlock::ToolResult { tool_use_id, content, is_error } => {
                             // Format tool results as text for Anthropic
-                            // Extract text from ResultContent blocks
-                            let result_strings: Vec<String> = content
-                                .iter()
-                                .filter_map(|rc| match rc {
-                                    crate::protocol::ResultContent::Text { text } => Some(text.clone()),
-                                    _ => None,
-                                })
-                                .collect();
+                            // Render ResultContent blocks as text
+                            let result_strings: Vec<String> =
+                                content.iter().filter_map(|rc| rc.to_llm_text()).collect();
 
                             let result_str = result_strings.join(" ");
                             let result_text = if is_error.unwrap_or(false) {
@@ -193,6 +206,7 @@ impl LLMProvider for AnthropicAdapter {
                                 .map(|s| s.properties.clone())
                                 .unwrap_or_default(),
                             required: tool.input_schema.as_ref().and_then(|s| s.required.clone()),
+                            defs: tool.input_schema.as_ref().and_then(|s| s.defs.clone()),
                         },
                     })
                     .collect(),
@@ -204,11 +218,14 @@ impl LLMProvider for AnthropicAdapter {
         // Create request
         let request = AnthropicRequest {
             model: self.model.clone(),
-            max_tokens: self.max_tokens,
+            max_tokens: options.max_tokens.map_or(self.max_tokens, |max| max as i32),
             messages: anthropic_messages,
             tools: anthropic_tools,
-            temperature: self.temperature,
-            system: self.system_prompt.clone(),
+            temperature: options.temperature.map_or(self.temperature, |t| t as f32),
+            system: options
+                .system_prompt
+                .unwrap_or_else(|| self.system_prompt.clone()),
+            stop_sequences: options.stop_sequences,
         };
 
         // Make API call
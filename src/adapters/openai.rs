@@ -3,7 +3,7 @@
 /// Implements internal tool execution loop - detects function calls,
 /// executes them via MCP client, and sends results back to OpenAI.
 
-use crate::agent::LLMProvider;
+use crate::agent::{CallOptions, LLMProvider};
 use crate::protocol::{Message, Tool, ContentBlock};
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
@@ -28,6 +28,8 @@ struct OpenAIToolParameters {
     properties: std::collections::HashMap<String, serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     required: Option<Vec<String>>,
+    #[serde(rename = "$defs", skip_serializing_if = "Option::is_none")]
+    defs: Option<std::collections::HashMap<String, serde_json::Value>>,
 }
 
 /// OpenAI Responses API request
@@ -37,6 +39,12 @@ struct OpenAIResponsesRequest {
     input: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAITool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
 }
 
 /// OpenAI Responses API response
@@ -179,11 +187,32 @@ impl OpenAIAdapter {
 
 #[async_trait::async_trait]
 impl LLMProvider for OpenAIAdapter {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
     async fn call(
         &self,
         messages: Vec<Message>,
         tools: Vec<Tool>,
     ) -> Result<crate::agent::LLMResponse> {
+        self.call_with_options(messages, tools, CallOptions::default())
+            .await
+    }
+
+    async fn call_with_options(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<Tool>,
+        options: CallOptions,
+    ) -> Result<crate::agent::LLMResponse> {
+        // The Responses API has no stop sequences
+        if options.stop_sequences.is_some() {
+            return Err(Error::InvalidParams(
+                "OpenAI Responses API does not support stop sequences".to_string(),
+            ));
+        }
+
         // Convert messages to a single input string
         let mut input = messages
             .iter()
@@ -256,6 +285,7 @@ impl LLMProvider for OpenAIAdapter {
                                     type_field: "object".to_string(),
                                     properties,
                                     required: if required.is_empty() { None } else { Some(required) },
+                                    defs: None,
                                 }
                             },
                             |schema| {
@@ -263,6 +293,7 @@ impl LLMProvider for OpenAIAdapter {
                                     type_field: schema.schema_type.clone(),
                                     properties: schema.properties.clone(),
                                     required: schema.required.clone(),
+                                    defs: schema.defs.clone(),
                                 }
                             }
                         );
@@ -289,6 +320,9 @@ impl LLMProvider for OpenAIAdapter {
                 model: self.model.clone(),
                 input: input.clone(),
                 tools: openai_tools.clone(),
+                instructions: options.system_prompt.clone(),
+                max_output_tokens: options.max_tokens,
+                temperature: options.temperature,
             };
             // Make API call to Responses API endpoint
             let response = self
@@ -341,10 +375,7 @@ impl LLMProvider for OpenAIAdapter {
                                 let formatted_result = tool_result
                                     .content
                                     .iter()
-                                    .filter_map(|c| match c {
-                                        crate::protocol::ResultContent::Text { text } => Some(text.clone()),
-                                        _ => None,
-                                    })
+                                    .filter_map(|c| c.to_llm_text())
                                     .collect::<Vec<_>>()
                                     .join("\n");
 
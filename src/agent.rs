@@ -1,7 +1,11 @@
 use crate::client::McpClient;
 use crate::protocol::*;
 use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use serde_json::Value;
 use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
 
 /// LLM interface trait
 #[async_trait::async_trait]
@@ -12,6 +16,39 @@ pub trait LLMProvider: Send + Sync {
         messages: Vec<Message>,
         tools: Vec<Tool>,
     ) -> Result<LLMResponse>;
+
+    /// Call the LLM with settings for this call only, e.g. those of a
+    /// `sampling/createMessage` request. Providers that can honour them
+    /// override this; the default runs [`call`](Self::call) when nothing is
+    /// set and refuses otherwise.
+    async fn call_with_options(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<Tool>,
+        options: CallOptions,
+    ) -> Result<LLMResponse> {
+        if options != CallOptions::default() {
+            return Err(Error::InvalidParams(format!(
+                "Model '{}' does not support per-call settings",
+                self.model()
+            )));
+        }
+        self.call(messages, tools).await
+    }
+
+    /// Name of the model behind the provider, e.g. for sampling results
+    fn model(&self) -> &str {
+        "unknown"
+    }
+}
+
+/// Settings for a single LLM call; `None` keeps the provider's own
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallOptions {
+    pub system_prompt: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f64>,
+    pub stop_sequences: Option<Vec<String>>,
 }
 
 /// LLM response
@@ -54,6 +91,8 @@ pub enum AgentEvent {
     ToolCallCompleted { tool_name: String, result: String },
     /// Tool execution failed
     ToolCallFailed { tool_name: String, error: String },
+    /// Tool call was not approved, so it did not run
+    ToolCallDenied { tool_name: String },
     /// Agent iteration completed
     IterationComplete { iteration: usize },
     /// Agent finished successfully
@@ -78,6 +117,10 @@ impl Default for AgentConfig {
     }
 }
 
+/// Decides whether the agent may call a tool with the given arguments,
+/// e.g. by asking the user for confirmation
+pub type ToolApprovalHandler = Arc<dyn Fn(&Tool, &Value) -> BoxFuture<'static, bool> + Send + Sync>;
+
 /// MCP-powered AI Agent
 pub struct Agent {
     client: McpClient,
@@ -89,6 +132,7 @@ pub struct Agent {
     system_prompt: Option<String>,
     /// Tools that are not allowed to be called
     disallowed_tools: Vec<String>,
+    tool_approval: Option<ToolApprovalHandler>,
 }
 
 impl Agent {
@@ -106,6 +150,7 @@ impl Agent {
             conversation: VecDeque::new(),
             system_prompt: None,
             disallowed_tools: Vec::new(),
+            tool_approval: None,
         }
     }
 
@@ -133,7 +178,7 @@ impl Agent {
             // Call LLM
             let llm_response = self
                 .llm
-                .call(messages, tools)
+                .call(messages, tools.clone())
                 .await
                 .map_err(|e| Error::LLMError(e.to_string()))?;
 
@@ -159,6 +204,12 @@ impl Agent {
                             assistant_message_added = true;
                         }
 
+                        if !self.approve_tool_call(&tools, name, input).await {
+                            self.conversation.push_back(denied_result(id, name));
+                            self.state = AgentState::Running;
+                            continue;
+                        }
+
                         // Execute tool
                         let tool_result = self.client.call_tool(name, input.clone()).await?;
 
@@ -248,6 +299,52 @@ impl Agent {
         }
     }
 
+    /// Ask `callback` before each call to a tool that is not read-only, e.g.
+    /// to have the user confirm destructive actions. Tools annotated with
+    /// `readOnlyHint` run without asking. Denied calls are reported to the
+    /// LLM as failed tool results.
+    ///
+    /// ```ignore
+    /// agent.set_tool_approval(|tool, _args| {
+    ///     let destructive = tool.is_destructive();
+    ///     async move { !destructive || confirm_with_user().await }
+    /// });
+    /// ```
+    pub fn set_tool_approval<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(&Tool, &Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.tool_approval = Some(Arc::new(
+            move |tool: &Tool, arguments: &Value| -> BoxFuture<'static, bool> {
+                Box::pin(callback(tool, arguments))
+            },
+        ));
+    }
+
+    /// Call every tool without asking for approval
+    pub fn clear_tool_approval(&mut self) {
+        self.tool_approval = None;
+    }
+
+    /// Tools the agent can use, with their titles and annotations
+    pub async fn available_tools(&self) -> Result<Vec<Tool>> {
+        self.get_available_tools().await
+    }
+
+    /// Whether a call may go ahead. Tools missing from `tools` are refused:
+    /// they are disallowed, or the server never offered them, so nothing is
+    /// known about what they do.
+    async fn approve_tool_call(&self, tools: &[Tool], name: &str, arguments: &Value) -> bool {
+        let Some(tool) = tools.iter().find(|tool| tool.name == name) else {
+            return false;
+        };
+        match &self.tool_approval {
+            Some(approve) if !tool.is_read_only() => approve(tool, arguments).await,
+            _ => true,
+        }
+    }
+
     /// Get available tools, excluding disallowed ones
     async fn get_available_tools(&self) -> Result<Vec<Tool>> {
         let all_tools = self.client.list_tools().await?;
@@ -286,7 +383,7 @@ impl Agent {
             // Call LLM
             let llm_response = self
                 .llm
-                .call(messages, tools)
+                .call(messages, tools.clone())
                 .await
                 .map_err(|e| Error::LLMError(e.to_string()))?;
 
@@ -319,16 +416,22 @@ impl Agent {
                             assistant_message_added = true;
                         }
 
+                        if !self.approve_tool_call(&tools, name, input).await {
+                            on_event(AgentEvent::ToolCallDenied {
+                                tool_name: name.clone(),
+                            });
+                            self.conversation.push_back(denied_result(id, name));
+                            self.state = AgentState::Running;
+                            continue;
+                        }
+
                         // Execute tool
                         match self.client.call_tool(name, input.clone()).await {
                             Ok(tool_result) => {
                                 let result_text = tool_result
                                     .content
                                     .iter()
-                                    .filter_map(|c| match c {
-                                        ResultContent::Text { text } => Some(text.clone()),
-                                        _ => None,
-                                    })
+                                    .filter_map(|c| c.to_llm_text())
                                     .collect::<Vec<_>>()
                                     .join("\n");
 
@@ -357,9 +460,7 @@ impl Agent {
                                     role: Role::User,
                                     content: vec![ContentBlock::ToolResult {
                                         tool_use_id: id.clone(),
-                                        content: vec![ResultContent::Text {
-                                            text: format!("Error: {}", e),
-                                        }],
+                                        content: vec![ResultContent::text(format!("Error: {}", e))],
                                         is_error: Some(true),
                                     }],
                                 });
@@ -406,6 +507,21 @@ impl Agent {
     }
 }
 
+/// Tool result telling the LLM a call was not approved
+fn denied_result(tool_use_id: &str, name: &str) -> Message {
+    Message {
+        role: Role::User,
+        content: vec![ContentBlock::ToolResult {
+            tool_use_id: tool_use_id.to_string(),
+            content: vec![ResultContent::text(format!(
+                "The call to '{}' was not approved",
+                name
+            ))],
+            is_error: Some(true),
+        }],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,6 +566,42 @@ mod tests {
         assert_eq!(agent.state, AgentState::Ready);
     }
 
+    #[tokio::test]
+    async fn test_tool_approval() {
+        let client = McpClient::new("http://localhost:8000");
+        let mut agent = Agent::new(
+            client,
+            std::sync::Arc::new(DummyLLMProvider),
+            AgentConfig::default(),
+        );
+        let tools = vec![
+            Tool::new("search", "Search").with_annotations(ToolAnnotations::new().read_only(true)),
+            Tool::new("delete", "Delete")
+                .with_annotations(ToolAnnotations::new().destructive(true)),
+            Tool::new("append", "Append")
+                .with_annotations(ToolAnnotations::new().destructive(false)),
+        ];
+        let args = serde_json::json!({});
+        assert!(agent.approve_tool_call(&tools, "delete", &args).await);
+        // Disallowed or unknown tools are never called
+        assert!(!agent.approve_tool_call(&tools, "drop_table", &args).await);
+
+        let asked = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        agent.set_tool_approval({
+            let asked = asked.clone();
+            move |tool: &Tool, _args: &Value| {
+                asked.lock().push(tool.name.clone());
+                let destructive = tool.is_destructive();
+                async move { !destructive }
+            }
+        });
+        assert!(agent.approve_tool_call(&tools, "search", &args).await);
+        assert!(!agent.approve_tool_call(&tools, "delete", &args).await);
+        assert!(agent.approve_tool_call(&tools, "append", &args).await);
+        assert!(!agent.approve_tool_call(&tools, "drop_table", &args).await);
+        assert_eq!(*asked.lock(), vec!["delete", "append"]);
+    }
+
     #[test]
     fn test_agent_config() {
         let config = AgentConfig::default();
@@ -1,22 +1,32 @@
+use crate::config::MCPServerConfig;
+use crate::connectors::base::{paginate, Connector, NotificationHandler, RequestHandler};
+use crate::connectors::http::HttpConnector;
+use crate::connectors::StdioConnector;
+use crate::error::{Error, Result};
 /// MCP Client for communicating with MCP servers.
 ///
 /// Supports multiple transports:
 /// - `http://` or `https://` - HTTP transport
 /// - `stdio://command args` - Subprocess transport
-
 use crate::protocol::*;
-use crate::error::{Error, Result};
-use crate::config::MCPServerConfig;
+use crate::sampling::SamplingHandler;
 use crate::session::Session;
-use crate::connectors::StdioConnector;
-use crate::connectors::base::Connector;
-use crate::connectors::http::HttpConnector;
-use serde_json::Value;
-use std::collections::HashMap;
+pub use crate::session::{CallToolOptions, ProgressHandler};
 use dashmap::DashMap;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Answers `elicitation/create` requests, typically by asking the user
+pub type ElicitationHandler =
+    Arc<dyn Fn(ElicitRequestParams) -> BoxFuture<'static, ElicitResult> + Send + Sync>;
+
 /// MCP Client supporting single or multiple server connections.
 #[derive(Clone)]
 pub struct McpClient {
@@ -30,6 +40,9 @@ pub struct McpClient {
 
     // Shared state
     initialized: Arc<Mutex<bool>>,
+    notification_callbacks: Arc<RwLock<Vec<NotificationHandler>>>,
+    sampling: Option<SamplingHandler>,
+    elicitation: Option<ElicitationHandler>,
 }
 
 impl McpClient {
@@ -41,6 +54,9 @@ impl McpClient {
             servers_config: HashMap::new(),
             sessions: Arc::new(DashMap::new()),
             initialized: Arc::new(Mutex::new(false)),
+            notification_callbacks: Arc::new(RwLock::new(Vec::new())),
+            sampling: None,
+            elicitation: None,
         }
     }
 
@@ -52,6 +68,9 @@ impl McpClient {
             servers_config: HashMap::new(),
             sessions: Arc::new(DashMap::new()),
             initialized: Arc::new(Mutex::new(false)),
+            notification_callbacks: Arc::new(RwLock::new(Vec::new())),
+            sampling: None,
+            elicitation: None,
         }
     }
 
@@ -64,6 +83,110 @@ impl McpClient {
         self.servers_config.keys().cloned().collect()
     }
 
+    /// Call `callback` with the method and params of every notification
+    /// the server sends. Register before `initialize` / `create_all_sessions`.
+    pub fn on_notification(&self, callback: impl Fn(&str, &Value) + Send + Sync + 'static) {
+        self.notification_callbacks.write().push(Arc::new(callback));
+    }
+
+    /// Call `callback` with the URI of each subscribed resource that changes
+    pub fn on_resource_updated(&self, callback: impl Fn(&str) + Send + Sync + 'static) {
+        self.on_notification(move |method, params| {
+            if method == "notifications/resources/updated"
+                && let Some(uri) = params.get("uri").and_then(|v| v.as_str())
+            {
+                callback(uri);
+            }
+        });
+    }
+
+    /// Call `callback` with each log message the server sends. Messages
+    /// only arrive once a level has been set with [`set_log_level`](Self::set_log_level).
+    pub fn on_log_message(&self, callback: impl Fn(LoggingMessage) + Send + Sync + 'static) {
+        self.on_notification(move |method, params| {
+            if method == "notifications/message" {
+                match serde_json::from_value::<LoggingMessage>(params.clone()) {
+                    Ok(message) => callback(message),
+                    Err(e) => tracing::debug!("Ignoring malformed log message: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Handler passed to connectors, fanning out to the registered callbacks
+    fn notification_dispatcher(&self) -> NotificationHandler {
+        let callbacks = self.notification_callbacks.clone();
+        Arc::new(move |method: &str, params: &Value| {
+            let callbacks = callbacks.read().clone();
+            for callback in callbacks {
+                callback(method, params);
+            }
+        })
+    }
+
+    /// Let servers request completions from an LLM through `handler`
+    /// (`sampling/createMessage`). Enable before `initialize` /
+    /// `create_all_sessions`, so the capability is declared.
+    pub fn enable_sampling(&mut self, handler: SamplingHandler) {
+        self.sampling = Some(handler);
+    }
+
+    /// Let servers ask the user for input mid-call (`elicitation/create`).
+    /// `callback` shows the message and form, and resolves to the user's
+    /// answer: accepted input, a decline or a cancellation. Enable before
+    /// `initialize` / `create_all_sessions`, so the capability is declared.
+    pub fn enable_elicitation<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(ElicitRequestParams) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ElicitResult> + Send + 'static,
+    {
+        self.elicitation = Some(Arc::new(move |params| Box::pin(callback(params))));
+    }
+
+    /// Capabilities declared to servers, following what has been enabled
+    fn capabilities(&self) -> ClientCapabilities {
+        ClientCapabilities {
+            sampling: self.sampling.as_ref().map(|_| json!({})),
+            elicitation: self.elicitation.as_ref().map(|_| json!({})),
+            ..ClientCapabilities::default()
+        }
+    }
+
+    /// Handler passed to connectors, answering requests from servers
+    fn request_dispatcher(&self) -> RequestHandler {
+        let sampling = self.sampling.clone();
+        let elicitation = self.elicitation.clone();
+        Arc::new(move |request: JsonRpcRequest| {
+            let sampling = sampling.clone();
+            let elicitation = elicitation.clone();
+            Box::pin(async move {
+                let params = request.params.unwrap_or_default();
+                match (request.method.as_str(), sampling, elicitation) {
+                    ("sampling/createMessage", Some(sampling), _) => {
+                        let params = serde_json::from_value(params).map_err(|e| {
+                            Error::InvalidParams(format!("Invalid sampling request: {}", e))
+                        })?;
+                        Ok(json!(sampling.create_message(params).await?))
+                    }
+                    ("elicitation/create", _, Some(elicit)) => {
+                        let params = serde_json::from_value(params).map_err(|e| {
+                            Error::InvalidParams(format!("Invalid elicitation request: {}", e))
+                        })?;
+                        Ok(json!(elicit(params).await))
+                    }
+                    _ => Err(Error::MethodNotFound(request.method)),
+                }
+            })
+        })
+    }
+
+    /// Hook a new session up to the client's callbacks and capabilities
+    fn configure_session(&self, session: &mut Session) {
+        session.set_notification_handler(self.notification_dispatcher());
+        session.set_request_handler(self.request_dispatcher());
+        session.set_capabilities(self.capabilities());
+    }
+
     fn create_connector_from_url(url: &str) -> Result<Box<dyn Connector>> {
         if url.starts_with("http://") || url.starts_with("https://") {
             // HTTP/HTTPS transport
@@ -105,10 +228,10 @@ impl McpClient {
             ));
         };
 
-        let mut connector = Self::create_connector_from_url(&url)?;
-        connector.connect().await?;
-
+        let connector = Self::create_connector_from_url(&url)?;
         let mut session = Session::new(config.name.clone(), connector);
+        self.configure_session(&mut session);
+        session.connect().await?;
         session.initialize().await?;
 
         Ok(session)
@@ -118,6 +241,7 @@ impl McpClient {
         if let Some(url) = &self.url {
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             let capabilities = session.initialize().await?;
             self.session = Some(Arc::new(Mutex::new(session)));
@@ -128,6 +252,67 @@ impl McpClient {
         }
     }
 
+    /// The persistent session, or a new one when `initialize()` has not been called
+    async fn shared_session(&self) -> Result<Arc<Mutex<Session>>> {
+        if let Some(session_arc) = &self.session {
+            return Ok(session_arc.clone());
+        }
+        let url = self
+            .url
+            .as_ref()
+            .ok_or_else(|| Error::InternalError("No server configured".to_string()))?;
+        let connector = Self::create_connector_from_url(url)?;
+        let mut session = Session::new("default", connector);
+        self.configure_session(&mut session);
+        session.connect().await?;
+        session.initialize().await?;
+        Ok(Arc::new(Mutex::new(session)))
+    }
+
+    /// Stream the server's tools page by page, without loading the whole list
+    pub async fn tools_stream(&self) -> Result<BoxStream<'static, Result<Tool>>> {
+        let session = self.shared_session().await?;
+        Ok(paginate(move |cursor| {
+            let session = session.clone();
+            async move { session.lock_owned().await.list_tools_page(cursor).await }
+        }))
+    }
+
+    /// Stream the server's resources page by page
+    pub async fn resources_stream(&self) -> Result<BoxStream<'static, Result<Resource>>> {
+        let session = self.shared_session().await?;
+        Ok(paginate(move |cursor| {
+            let session = session.clone();
+            async move { session.lock_owned().await.list_resources_page(cursor).await }
+        }))
+    }
+
+    /// Stream the server's resource templates page by page
+    pub async fn resource_templates_stream(
+        &self,
+    ) -> Result<BoxStream<'static, Result<ResourceTemplate>>> {
+        let session = self.shared_session().await?;
+        Ok(paginate(move |cursor| {
+            let session = session.clone();
+            async move {
+                session
+                    .lock_owned()
+                    .await
+                    .list_resource_templates_page(cursor)
+                    .await
+            }
+        }))
+    }
+
+    /// Stream the server's prompts page by page
+    pub async fn prompts_stream(&self) -> Result<BoxStream<'static, Result<Prompt>>> {
+        let session = self.shared_session().await?;
+        Ok(paginate(move |cursor| {
+            let session = session.clone();
+            async move { session.lock_owned().await.list_prompts_page(cursor).await }
+        }))
+    }
+
     pub async fn list_tools(&self) -> Result<Vec<Tool>> {
         if let Some(session_arc) = &self.session {
             let mut session = session_arc.lock().await;
@@ -137,6 +322,7 @@ impl McpClient {
             // Create session on-demand if not yet initialized
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             session.initialize().await?;
             session.refresh_tools().await?;
@@ -154,6 +340,7 @@ impl McpClient {
             // Create session on-demand
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             session.initialize().await?;
             session.call_tool(tool_name, arguments).await
@@ -162,6 +349,45 @@ impl McpClient {
         }
     }
 
+    /// Call a tool and deserialize its structured content into `T`.
+    ///
+    /// A result with `isError` set fails with [`Error::ToolError`]. Servers
+    /// that return no structured content fall back to JSON in the first
+    /// text block.
+    pub async fn call_tool_typed<T: DeserializeOwned>(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+    ) -> Result<T> {
+        let result = self.call_tool(tool_name, arguments).await?;
+        if result.is_error == Some(true) {
+            return Err(Error::ToolError(result.content));
+        }
+        result.structured()
+    }
+
+    /// Call a tool with a progress callback and/or a cancellation token
+    pub async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        options: CallToolOptions,
+    ) -> Result<ToolResult> {
+        if let Some(session_arc) = &self.session {
+            let session = session_arc.lock().await;
+            session.call_tool_with(tool_name, arguments, options).await
+        } else if let Some(url) = &self.url {
+            let connector = Self::create_connector_from_url(url)?;
+            let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
+            session.connect().await?;
+            session.initialize().await?;
+            session.call_tool_with(tool_name, arguments, options).await
+        } else {
+            Err(Error::InternalError("No server configured".to_string()))
+        }
+    }
+
     pub async fn list_resources(&self) -> Result<Vec<Resource>> {
         if let Some(session_arc) = &self.session {
             let mut session = session_arc.lock().await;
@@ -170,6 +396,7 @@ impl McpClient {
         } else if let Some(url) = &self.url {
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             session.initialize().await?;
             session.refresh_resources().await?;
@@ -179,13 +406,32 @@ impl McpClient {
         }
     }
 
-    pub async fn read_resource(&self, uri: &str) -> Result<String> {
+    /// List the server's resource templates
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        if let Some(session_arc) = &self.session {
+            let session = session_arc.lock().await;
+            session.list_resource_templates().await
+        } else if let Some(url) = &self.url {
+            let connector = Self::create_connector_from_url(url)?;
+            let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
+            session.connect().await?;
+            session.initialize().await?;
+            session.list_resource_templates().await
+        } else {
+            Err(Error::InternalError("No server configured".to_string()))
+        }
+    }
+
+    /// Read a resource, returning every content entry the server sent
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>> {
         if let Some(session_arc) = &self.session {
             let session = session_arc.lock().await;
             session.read_resource(uri).await
         } else if let Some(url) = &self.url {
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             session.initialize().await?;
             session.read_resource(uri).await
@@ -194,6 +440,37 @@ impl McpClient {
         }
     }
 
+    /// Subscribe to updates of a resource; changes are delivered to
+    /// `on_resource_updated` callbacks. Requires `initialize()`.
+    pub async fn subscribe_resource(&self, uri: &str) -> Result<()> {
+        match &self.session {
+            Some(session_arc) => session_arc.lock().await.subscribe_resource(uri).await,
+            None => Err(Error::InvalidRequest(
+                "Subscriptions need a persistent session, call initialize() first".to_string(),
+            )),
+        }
+    }
+
+    /// Stop receiving updates for a resource
+    pub async fn unsubscribe_resource(&self, uri: &str) -> Result<()> {
+        match &self.session {
+            Some(session_arc) => session_arc.lock().await.unsubscribe_resource(uri).await,
+            None => Err(Error::InvalidRequest(
+                "Subscriptions need a persistent session, call initialize() first".to_string(),
+            )),
+        }
+    }
+
+    /// Ask the server for log messages at `level` and above
+    pub async fn set_log_level(&self, level: LoggingLevel) -> Result<()> {
+        match &self.session {
+            Some(session_arc) => session_arc.lock().await.set_log_level(level).await,
+            None => Err(Error::InvalidRequest(
+                "Log levels need a persistent session, call initialize() first".to_string(),
+            )),
+        }
+    }
+
     pub async fn list_prompts(&self) -> Result<Vec<Prompt>> {
         if let Some(session_arc) = &self.session {
             let mut session = session_arc.lock().await;
@@ -202,6 +479,7 @@ impl McpClient {
         } else if let Some(url) = &self.url {
             let connector = Self::create_connector_from_url(url)?;
             let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
             session.connect().await?;
             session.initialize().await?;
             session.refresh_prompts().await?;
@@ -211,6 +489,43 @@ impl McpClient {
         }
     }
 
+    /// Render a prompt with the given arguments, as messages ready to pass to an agent
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<Vec<Message>> {
+        let arguments = Some(serde_json::json!(arguments));
+        let result = if let Some(session_arc) = &self.session {
+            let session = session_arc.lock().await;
+            session.get_prompt(name, arguments).await?
+        } else if let Some(url) = &self.url {
+            let connector = Self::create_connector_from_url(url)?;
+            let mut session = Session::new("default", connector);
+            self.configure_session(&mut session);
+            session.connect().await?;
+            session.initialize().await?;
+            session.get_prompt(name, arguments).await?
+        } else {
+            return Err(Error::InternalError("No server configured".to_string()));
+        };
+
+        Ok(result.messages.into_iter().map(Message::from).collect())
+    }
+
+    /// Ask the server for suggestions for a prompt argument or resource
+    /// template variable, e.g. to autocomplete it as the user types:
+    ///
+    /// ```ignore
+    /// let params = CompleteParams::new(CompletionReference::prompt("code_review"), "language", "py");
+    /// let suggestions = client.complete(params).await?.values;
+    /// ```
+    pub async fn complete(&self, params: CompleteParams) -> Result<Completion> {
+        let session = self.shared_session().await?;
+        let session = session.lock().await;
+        session.complete(params).await
+    }
+
     pub async fn create_all_sessions(&self) -> Result<()> {
         let server_names = self.server_names();
         let mut errors = Vec::new();
@@ -331,6 +646,75 @@ mod tests {
         assert_eq!(client.server_names().len(), 1);
     }
 
+    #[test]
+    fn test_resource_updated_callback() {
+        let client = McpClient::new("http://localhost:3000");
+        let updated = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = updated.clone();
+        client.on_resource_updated(move |uri| sink.lock().push(uri.to_string()));
+
+        let dispatch = client.notification_dispatcher();
+        dispatch(
+            "notifications/resources/updated",
+            &serde_json::json!({"uri": "file:///a"}),
+        );
+        dispatch("notifications/tools/list_changed", &serde_json::json!({}));
+        assert_eq!(*updated.lock(), vec!["file:///a".to_string()]);
+    }
+
+    #[test]
+    fn test_log_message_callback() {
+        let client = McpClient::new("http://localhost:3000");
+        let messages = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        client.on_log_message(move |message| sink.lock().push((message.level, message.data)));
+
+        let dispatch = client.notification_dispatcher();
+        dispatch(
+            "notifications/message",
+            &serde_json::json!({ "level": "warning", "data": "disk almost full" }),
+        );
+        dispatch(
+            "notifications/message",
+            &serde_json::json!({ "level": "unknown" }),
+        );
+        assert_eq!(
+            *messages.lock(),
+            vec![(LoggingLevel::Warning, serde_json::json!("disk almost full"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_elicitation_callback() {
+        let mut client = McpClient::new("http://localhost:3000");
+        assert!(client.capabilities().elicitation.is_none());
+        client.enable_elicitation(|params: ElicitRequestParams| async move {
+            match params.message.as_str() {
+                "Which branch?" => ElicitResult::accept(serde_json::json!({ "branch": "main" })),
+                _ => ElicitResult::decline(),
+            }
+        });
+        assert!(client.capabilities().elicitation.is_some());
+        assert!(client.capabilities().sampling.is_none());
+
+        let dispatch = client.request_dispatcher();
+        let params =
+            ElicitRequestParams::new("Which branch?", serde_json::json!({ "type": "object" }));
+        let request = JsonRpcRequest::new("elicitation/create", Some(serde_json::json!(params)));
+        let result = dispatch(request).await.unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({ "action": "accept", "content": { "branch": "main" } })
+        );
+
+        // Sampling was not enabled
+        let request = JsonRpcRequest::new("sampling/createMessage", Some(serde_json::json!({})));
+        assert!(matches!(
+            dispatch(request).await,
+            Err(Error::MethodNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_connector_url_detection_http() {
         let result = McpClient::create_connector_from_url("http://localhost:3000");
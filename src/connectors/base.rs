@@ -1,7 +1,17 @@
-/// Base connector trait for MCP connections
-use crate::protocol::{JsonRpcRequest, JsonRpcResponse, Tool, ToolResult, Resource, Prompt};
 use crate::error::{Error, Result};
+/// Base connector trait for MCP connections
+use crate::protocol::{
+    ClientCapabilities, CompleteParams, CompleteResult, Completion, GetPromptResult,
+    Implementation, InitializeParams, JsonRpcError, JsonRpcNotification, JsonRpcRequest,
+    JsonRpcResponse, LoggingLevel, Page, Prompt, ProtocolVersion, ReadResourceResult, Resource,
+    ResourceContents, ResourceTemplate, Tool, ToolResult, MCP_VERSION,
+};
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
 
 /// Configuration for connector
 #[derive(Debug, Clone)]
@@ -21,6 +31,109 @@ impl Default for ConnectorConfig {
     }
 }
 
+/// Receives the method and params of each notification the server sends.
+///
+/// Called from the connector's reader task, so it should return quickly.
+pub type NotificationHandler = Arc<dyn Fn(&str, &Value) + Send + Sync>;
+
+/// Pass a notification from the server to `handler`, if there is one
+pub(crate) fn dispatch_notification(
+    handler: Option<&NotificationHandler>,
+    notification: &JsonRpcNotification,
+) {
+    if let Some(handler) = handler {
+        let params = notification.params.clone().unwrap_or(Value::Null);
+        handler(&notification.method, &params);
+    }
+}
+
+/// Answers requests the server sends to the client, such as
+/// `sampling/createMessage`, with the result to send back.
+///
+/// Each request is handled on its own task, so it may take as long as it
+/// needs (e.g. to ask the user for approval).
+pub type RequestHandler =
+    Arc<dyn Fn(JsonRpcRequest) -> BoxFuture<'static, Result<Value>> + Send + Sync>;
+
+/// Answer a request from the server with `handler`. `ping` is answered
+/// here; other methods fail with "method not found" if there is no handler.
+pub(crate) async fn dispatch_request(
+    handler: Option<RequestHandler>,
+    request: JsonRpcRequest,
+) -> JsonRpcResponse {
+    let id = request.id.clone();
+    let result = match (request.method.as_str(), handler) {
+        ("ping", _) => Ok(serde_json::json!({})),
+        (_, Some(handler)) => handler(request).await,
+        (_, None) => Err(Error::MethodNotFound(request.method)),
+    };
+
+    match result {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err(e) => JsonRpcResponse::failure(Some(id), JsonRpcError::from(&e)),
+    }
+}
+
+/// Parse the response to a `tools/call` request
+pub(crate) fn tool_result(response: JsonRpcResponse) -> Result<ToolResult> {
+    if let Some(result) = response.result {
+        serde_json::from_value::<ToolResult>(result)
+            .map_err(|e| Error::InvalidRequest(format!("Invalid tool result: {}", e)))
+    } else if let Some(error) = response.error {
+        Err(Error::ServerError(error.message))
+    } else {
+        Err(Error::InternalError("No result in response".to_string()))
+    }
+}
+
+/// Build a list request, continuing after `cursor` if given
+fn list_request(method: &str, cursor: Option<String>) -> JsonRpcRequest {
+    JsonRpcRequest::new(
+        method,
+        cursor.map(|cursor| serde_json::json!({ "cursor": cursor })),
+    )
+}
+
+/// Parse one page of a list response whose items are under `key`
+fn list_page<T: DeserializeOwned>(response: JsonRpcResponse, key: &str) -> Result<Page<T>> {
+    if let Some(mut result) = response.result {
+        let items = result
+            .get_mut(key)
+            .map(Value::take)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .ok_or_else(|| Error::InvalidRequest(format!("Invalid {} response", key)))?;
+        let next_cursor = result
+            .get("nextCursor")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Ok(Page { items, next_cursor })
+    } else if let Some(error) = response.error {
+        Err(Error::ServerError(error.message))
+    } else {
+        Err(Error::InternalError("No result in response".to_string()))
+    }
+}
+
+/// Stream every item of a paginated list. `fetch` is called with the cursor
+/// of each page once the previous page has been consumed.
+pub fn paginate<'a, T, F, Fut>(fetch: F) -> BoxStream<'a, Result<T>>
+where
+    T: Send + 'a,
+    F: Fn(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Page<T>>> + Send + 'a,
+{
+    futures::stream::try_unfold((fetch, Some(None)), |(fetch, cursor)| async move {
+        let Some(cursor) = cursor else {
+            return Ok(None);
+        };
+        let page = fetch(cursor).await?;
+        let items = futures::stream::iter(page.items.into_iter().map(Ok));
+        Ok::<_, Error>(Some((items, (fetch, page.next_cursor.map(Some)))))
+    })
+    .try_flatten()
+    .boxed()
+}
+
 /// Trait for different connection transports (HTTP, Stdio, SSE, WebSocket).
 #[async_trait::async_trait]
 pub trait Connector: Send + Sync {
@@ -29,26 +142,63 @@ pub trait Connector: Send + Sync {
     async fn connect(&mut self) -> Result<()>;
     async fn disconnect(&mut self) -> Result<()>;
     fn is_connected(&self) -> bool;
+
+    /// Set the handler for notifications from the server. Set it before
+    /// connecting; transports that cannot receive notifications ignore it.
+    fn set_notification_handler(&mut self, _handler: NotificationHandler) {}
+
+    /// Set the handler for requests from the server. Set it before
+    /// connecting; transports that cannot receive requests ignore it.
+    fn set_request_handler(&mut self, _handler: RequestHandler) {}
+
+    /// Send a notification to the server, e.g. `notifications/cancelled`
+    async fn send_notification(&self, notification: JsonRpcNotification) -> Result<()> {
+        Err(Error::ConnectionError(format!(
+            "Cannot send '{}': transport does not support notifications",
+            notification.method
+        )))
+    }
     // These can be overridden by specific transports for optimization
 
+    /// Initialize the MCP connection without declaring any client capabilities
+    async fn initialize(&self) -> Result<Value> {
+        self.initialize_with(ClientCapabilities::default()).await
+    }
+
     /// Initialize the MCP connection
     ///
-    /// Sends the initialize request and returns server capabilities
-    async fn initialize(&self) -> Result<Value> {
-        let params = serde_json::json!({
-            "protocolVersion": "2025-11-05",
-            "capabilities": {
-                "sampling": {}
+    /// Offers the latest protocol version and `capabilities`, checks the one
+    /// the server chose is supported, then sends `notifications/initialized`.
+    /// Returns the initialize result with the server capabilities.
+    async fn initialize_with(&self, capabilities: ClientCapabilities) -> Result<Value> {
+        let params = InitializeParams {
+            protocol_version: MCP_VERSION.to_string(),
+            capabilities,
+            client_info: Implementation {
+                name: "mcp-framework".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                title: None,
             },
-            "clientInfo": {
-                "name": "mcp-framework",
-                "version": "0.1.0"
-            }
-        });
-        let request = JsonRpcRequest::new("initialize", Some(params));
+        };
+        let request = JsonRpcRequest::new("initialize", Some(serde_json::json!(params)));
         let response = self.send_request(request).await?;
 
         if let Some(result) = response.result {
+            let version = result
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if ProtocolVersion::parse(version).is_none() {
+                return Err(Error::ConnectionError(format!(
+                    "Server chose unsupported protocol version '{}'",
+                    version
+                )));
+            }
+
+            let initialized = JsonRpcNotification::new("notifications/initialized", None);
+            if let Err(e) = self.send_notification(initialized).await {
+                tracing::debug!("Could not send initialized notification: {}", e);
+            }
             Ok(result)
         } else if let Some(error) = response.error {
             Err(Error::ServerError(error.message))
@@ -57,22 +207,20 @@ pub trait Connector: Send + Sync {
         }
     }
 
-    /// List all available tools from the server
-    async fn list_tools(&self) -> Result<Vec<Tool>> {
-        let request = JsonRpcRequest::new("tools/list", None);
-        let response = self.send_request(request).await?;
+    /// Fetch one page of tools, continuing after `cursor`
+    async fn list_tools_page(&self, cursor: Option<String>) -> Result<Page<Tool>> {
+        list_page(
+            self.send_request(list_request("tools/list", cursor))
+                .await?,
+            "tools",
+        )
+    }
 
-        if let Some(result) = response.result {
-            let tools = result
-                .get("tools")
-                .and_then(|v| serde_json::from_value::<Vec<Tool>>(v.clone()).ok())
-                .ok_or_else(|| Error::InvalidRequest("Invalid tools response".to_string()))?;
-            Ok(tools)
-        } else if let Some(error) = response.error {
-            Err(Error::ServerError(error.message))
-        } else {
-            Err(Error::InternalError("No result in response".to_string()))
-        }
+    /// List all available tools from the server, across every page
+    async fn list_tools(&self) -> Result<Vec<Tool>> {
+        paginate(|cursor| self.list_tools_page(cursor))
+            .try_collect()
+            .await
     }
 
     /// Call a tool on the server
@@ -82,38 +230,43 @@ pub trait Connector: Send + Sync {
             "arguments": arguments,
         });
         let request = JsonRpcRequest::new("tools/call", Some(params));
-        let response = self.send_request(request).await?;
+        tool_result(self.send_request(request).await?)
+    }
 
-        if let Some(result) = response.result {
-            serde_json::from_value::<ToolResult>(result)
-                .map_err(|e| Error::InvalidRequest(format!("Invalid tool result: {}", e)))
-        } else if let Some(error) = response.error {
-            Err(Error::ServerError(error.message))
-        } else {
-            Err(Error::InternalError("No result in response".to_string()))
-        }
+    /// Fetch one page of resources, continuing after `cursor`
+    async fn list_resources_page(&self, cursor: Option<String>) -> Result<Page<Resource>> {
+        list_page(
+            self.send_request(list_request("resources/list", cursor))
+                .await?,
+            "resources",
+        )
     }
 
-    /// List all available resources from the server
+    /// List all available resources from the server, across every page
     async fn list_resources(&self) -> Result<Vec<Resource>> {
-        let request = JsonRpcRequest::new("resources/list", None);
-        let response = self.send_request(request).await?;
+        paginate(|cursor| self.list_resources_page(cursor))
+            .try_collect()
+            .await
+    }
 
-        if let Some(result) = response.result {
-            let resources = result
-                .get("resources")
-                .and_then(|v| serde_json::from_value::<Vec<Resource>>(v.clone()).ok())
-                .ok_or_else(|| Error::InvalidRequest("Invalid resources response".to_string()))?;
-            Ok(resources)
-        } else if let Some(error) = response.error {
-            Err(Error::ServerError(error.message))
-        } else {
-            Err(Error::InternalError("No result in response".to_string()))
-        }
+    /// Fetch one page of resource templates, continuing after `cursor`
+    async fn list_resource_templates_page(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<Page<ResourceTemplate>> {
+        let request = list_request("resources/templates/list", cursor);
+        list_page(self.send_request(request).await?, "resourceTemplates")
+    }
+
+    /// List the resource templates the server exposes, across every page
+    async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        paginate(|cursor| self.list_resource_templates_page(cursor))
+            .try_collect()
+            .await
     }
 
     /// Read a specific resource from the server
-    async fn read_resource(&self, uri: &str) -> Result<String> {
+    async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>> {
         let params = serde_json::json!({
             "uri": uri,
         });
@@ -121,14 +274,9 @@ pub trait Connector: Send + Sync {
         let response = self.send_request(request).await?;
 
         if let Some(result) = response.result {
-            let content = result
-                .get("contents")
-                .and_then(|v| v.as_array())
-                .and_then(|arr| arr.first())
-                .and_then(|item| item.get("text"))
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| Error::InvalidRequest("Invalid resource content".to_string()))?;
-            Ok(content.to_string())
+            let result = serde_json::from_value::<ReadResourceResult>(result)
+                .map_err(|e| Error::InvalidRequest(format!("Invalid resource content: {}", e)))?;
+            Ok(result.contents)
         } else if let Some(error) = response.error {
             Err(Error::ServerError(error.message))
         } else {
@@ -136,26 +284,66 @@ pub trait Connector: Send + Sync {
         }
     }
 
-    /// List all available prompts from the server
-    async fn list_prompts(&self) -> Result<Vec<Prompt>> {
-        let request = JsonRpcRequest::new("prompts/list", None);
+    /// Ask the server for `notifications/resources/updated` when `uri` changes
+    async fn subscribe_resource(&self, uri: &str) -> Result<()> {
+        let request = JsonRpcRequest::new(
+            "resources/subscribe",
+            Some(serde_json::json!({ "uri": uri })),
+        );
         let response = self.send_request(request).await?;
 
-        if let Some(result) = response.result {
-            let prompts = result
-                .get("prompts")
-                .and_then(|v| serde_json::from_value::<Vec<Prompt>>(v.clone()).ok())
-                .ok_or_else(|| Error::InvalidRequest("Invalid prompts response".to_string()))?;
-            Ok(prompts)
-        } else if let Some(error) = response.error {
-            Err(Error::ServerError(error.message))
-        } else {
-            Err(Error::InternalError("No result in response".to_string()))
+        match response.error {
+            Some(error) => Err(Error::ServerError(error.message)),
+            None => Ok(()),
         }
     }
 
+    /// Stop receiving updates for `uri`
+    async fn unsubscribe_resource(&self, uri: &str) -> Result<()> {
+        let request = JsonRpcRequest::new(
+            "resources/unsubscribe",
+            Some(serde_json::json!({ "uri": uri })),
+        );
+        let response = self.send_request(request).await?;
+
+        match response.error {
+            Some(error) => Err(Error::ServerError(error.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Ask the server for log messages at `level` and above
+    async fn set_log_level(&self, level: LoggingLevel) -> Result<()> {
+        let request = JsonRpcRequest::new(
+            "logging/setLevel",
+            Some(serde_json::json!({ "level": level })),
+        );
+        let response = self.send_request(request).await?;
+
+        match response.error {
+            Some(error) => Err(Error::ServerError(error.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fetch one page of prompts, continuing after `cursor`
+    async fn list_prompts_page(&self, cursor: Option<String>) -> Result<Page<Prompt>> {
+        list_page(
+            self.send_request(list_request("prompts/list", cursor))
+                .await?,
+            "prompts",
+        )
+    }
+
+    /// List all available prompts from the server, across every page
+    async fn list_prompts(&self) -> Result<Vec<Prompt>> {
+        paginate(|cursor| self.list_prompts_page(cursor))
+            .try_collect()
+            .await
+    }
+
     /// Get a specific prompt from the server
-    async fn get_prompt(&self, name: &str, arguments: Option<Value>) -> Result<Value> {
+    async fn get_prompt(&self, name: &str, arguments: Option<Value>) -> Result<GetPromptResult> {
         let params = serde_json::json!({
             "name": name,
             "arguments": arguments.unwrap_or(serde_json::json!({})),
@@ -164,7 +352,25 @@ pub trait Connector: Send + Sync {
         let response = self.send_request(request).await?;
 
         if let Some(result) = response.result {
-            Ok(result)
+            serde_json::from_value::<GetPromptResult>(result)
+                .map_err(|e| Error::InvalidRequest(format!("Invalid prompt result: {}", e)))
+        } else if let Some(error) = response.error {
+            Err(Error::ServerError(error.message))
+        } else {
+            Err(Error::InternalError("No result in response".to_string()))
+        }
+    }
+
+    /// Ask the server for suggestions for a prompt argument or resource
+    /// template variable
+    async fn complete(&self, params: CompleteParams) -> Result<Completion> {
+        let request = JsonRpcRequest::new("completion/complete", Some(serde_json::json!(params)));
+        let response = self.send_request(request).await?;
+
+        if let Some(result) = response.result {
+            let result = serde_json::from_value::<CompleteResult>(result)
+                .map_err(|e| Error::InvalidRequest(format!("Invalid completion result: {}", e)))?;
+            Ok(result.completion)
         } else if let Some(error) = response.error {
             Err(Error::ServerError(error.message))
         } else {
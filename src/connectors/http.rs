@@ -1,16 +1,37 @@
 /// HTTP connector for MCP
-use super::base::{Connector, ConnectorConfig};
-use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
-use crate::error::{Result, Error};
+use super::base::{
+    dispatch_notification, dispatch_request, Connector, ConnectorConfig, NotificationHandler,
+    RequestHandler,
+};
+use crate::error::{Error, Result};
+use crate::jsonrpc;
+use crate::protocol::{
+    JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, RequestId,
+};
+use crate::transport::{PROTOCOL_VERSION_HEADER, SESSION_ID_HEADER};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
 use reqwest::Client;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Background stream of server notifications, with the session it belongs to
+type Listener = Arc<Mutex<Option<(String, JoinHandle<()>)>>>;
 
 /// HTTP-based MCP connector
 pub struct HttpConnector {
     config: ConnectorConfig,
     client: Client,
     connected: Arc<Mutex<bool>>,
+    /// Session ID assigned by the server at initialization
+    session_id: Arc<Mutex<Option<String>>>,
+    /// Protocol version negotiated at initialization, sent on every later request
+    protocol_version: Arc<Mutex<Option<String>>>,
+    notification_handler: Option<NotificationHandler>,
+    request_handler: Option<RequestHandler>,
+    /// GET stream carrying notifications outside of requests, with the
+    /// session it was opened for
+    listener: Listener,
 }
 
 impl HttpConnector {
@@ -20,6 +41,11 @@ impl HttpConnector {
             config,
             client: Client::new(),
             connected: Arc::new(Mutex::new(false)),
+            session_id: Arc::new(Mutex::new(None)),
+            protocol_version: Arc::new(Mutex::new(None)),
+            notification_handler: None,
+            request_handler: None,
+            listener: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -27,6 +53,217 @@ impl HttpConnector {
     pub fn default() -> Self {
         Self::new(ConnectorConfig::default())
     }
+
+    /// Add the session and protocol version headers, once they are known
+    async fn with_session_headers(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
+        session_headers(builder, &self.session_id, &self.protocol_version).await
+    }
+
+    /// Answers server requests on the current session
+    fn responder(&self) -> Responder {
+        Responder {
+            client: self.client.clone(),
+            url: self.config.url.clone(),
+            session_id: self.session_id.clone(),
+            protocol_version: self.protocol_version.clone(),
+            handler: self.request_handler.clone(),
+        }
+    }
+
+    /// Read an SSE response body, dispatching notifications as they arrive,
+    /// until the response to `id` shows up
+    async fn response_from_event_stream(
+        &self,
+        mut response: reqwest::Response,
+        id: &RequestId,
+    ) -> Result<JsonRpcResponse> {
+        let mut parser = EventStreamParser::default();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::ConnectionError(e.to_string()))?
+        {
+            for data in parser.push(&chunk) {
+                if let Some(response) = self.handle_event(&data, id) {
+                    return Ok(response);
+                }
+            }
+        }
+
+        Err(Error::ConnectionError(
+            "Event stream ended without a response".to_string(),
+        ))
+    }
+
+    /// Dispatch notifications and server requests, and return the response
+    /// to `id` if the event carries it
+    fn handle_event(&self, data: &str, id: &RequestId) -> Option<JsonRpcResponse> {
+        let mut found = None;
+        for message in jsonrpc::parse(data.as_bytes()).ok()?.into_messages() {
+            match message {
+                Ok(JsonRpcMessage::Notification(notification)) => {
+                    dispatch_notification(self.notification_handler.as_ref(), &notification);
+                }
+                Ok(JsonRpcMessage::Request(request)) => self.responder().respond(request),
+                Ok(JsonRpcMessage::Response(response)) if response.id.as_ref() == Some(id) => {
+                    found = Some(response)
+                }
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// Open the session's GET stream if notifications or server requests
+    /// are wanted and it is not already open
+    async fn ensure_listener(&self) {
+        if self.notification_handler.is_none() && self.request_handler.is_none() {
+            return;
+        }
+        let Some(session_id) = self.session_id.lock().await.clone() else {
+            return;
+        };
+
+        let mut listener = self.listener.lock().await;
+        if matches!(&*listener, Some((id, _)) if *id == session_id) {
+            return;
+        }
+        if let Some((_, task)) = listener.take() {
+            task.abort();
+        }
+
+        let request = self
+            .with_session_headers(self.client.get(&self.config.url))
+            .await;
+        let task = tokio::spawn(listen(
+            request,
+            self.notification_handler.clone(),
+            self.responder(),
+        ));
+        *listener = Some((session_id, task));
+    }
+}
+
+/// Add the session and protocol version headers, once they are known
+async fn session_headers(
+    mut builder: reqwest::RequestBuilder,
+    session_id: &Mutex<Option<String>>,
+    protocol_version: &Mutex<Option<String>>,
+) -> reqwest::RequestBuilder {
+    if let Some(id) = session_id.lock().await.clone() {
+        builder = builder.header(SESSION_ID_HEADER, id);
+    }
+    if let Some(version) = protocol_version.lock().await.clone() {
+        builder = builder.header(PROTOCOL_VERSION_HEADER, version);
+    }
+    builder
+}
+
+/// Answers requests from the server by POSTing the response back on the session
+#[derive(Clone)]
+struct Responder {
+    client: Client,
+    url: String,
+    session_id: Arc<Mutex<Option<String>>>,
+    protocol_version: Arc<Mutex<Option<String>>>,
+    handler: Option<RequestHandler>,
+}
+
+impl Responder {
+    /// Handle `request` on its own task, so the stream it came on keeps flowing
+    fn respond(&self, request: JsonRpcRequest) {
+        let responder = self.clone();
+        tokio::spawn(async move {
+            let response = dispatch_request(responder.handler.clone(), request).await;
+            let builder = responder
+                .client
+                .post(&responder.url)
+                .header(ACCEPT, "application/json, text/event-stream")
+                .json(&response);
+            let sent = session_headers(builder, &responder.session_id, &responder.protocol_version)
+                .await
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = sent {
+                tracing::debug!("Could not answer server request: {}", e);
+            }
+        });
+    }
+}
+
+/// Receive notifications and requests on the session's GET stream until it closes
+async fn listen(
+    request: reqwest::RequestBuilder,
+    handler: Option<NotificationHandler>,
+    responder: Responder,
+) {
+    let response = request.header(ACCEPT, "text/event-stream").send().await;
+
+    let mut response = match response {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            tracing::debug!(
+                "Server offers no notification stream ({})",
+                response.status()
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::debug!("Failed to open notification stream: {}", e);
+            return;
+        }
+    };
+
+    let mut parser = EventStreamParser::default();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        for data in parser.push(&chunk) {
+            let Ok(incoming) = jsonrpc::parse(data.as_bytes()) else {
+                continue;
+            };
+            for message in incoming.into_messages() {
+                match message {
+                    Ok(JsonRpcMessage::Notification(notification)) => {
+                        dispatch_notification(handler.as_ref(), &notification);
+                    }
+                    Ok(JsonRpcMessage::Request(request)) => responder.respond(request),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Incremental parser for `text/event-stream` bodies
+#[derive(Default)]
+struct EventStreamParser {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamParser {
+    /// Feed a chunk of the body, returning the data of every event it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let data = String::from_utf8_lossy(&event)
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            if !data.is_empty() {
+                events.push(data);
+            }
+        }
+        events
+    }
 }
 
 #[async_trait::async_trait]
@@ -36,19 +273,62 @@ impl Connector for HttpConnector {
             return Err(Error::ConnectionError("Not connected".to_string()));
         }
 
-        let response = self
+        let builder = self
             .client
             .post(&self.config.url)
+            .header(ACCEPT, "application/json, text/event-stream")
             .json(&request)
-            .timeout(std::time::Duration::from_secs(self.config.timeout_secs))
+            .timeout(std::time::Duration::from_secs(self.config.timeout_secs));
+
+        let session_id = self.session_id.lock().await.clone();
+        let response = self
+            .with_session_headers(builder)
+            .await
             .send()
             .await
             .map_err(|e| Error::ConnectionError(e.to_string()))?;
 
-        response
-            .json::<JsonRpcResponse>()
-            .await
-            .map_err(|e| Error::ConnectionError(e.to_string()))
+        if response.status() == reqwest::StatusCode::NOT_FOUND && session_id.is_some() {
+            *self.session_id.lock().await = None;
+            return Err(Error::ConnectionError("Session expired".to_string()));
+        }
+
+        if let Some(id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(id.to_string());
+        }
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.starts_with("text/event-stream"))
+            .unwrap_or(false);
+
+        self.ensure_listener().await;
+
+        let response = if is_event_stream {
+            self.response_from_event_stream(response, &request.id)
+                .await?
+        } else {
+            response
+                .json::<JsonRpcResponse>()
+                .await
+                .map_err(|e| Error::ConnectionError(e.to_string()))?
+        };
+
+        if request.method == "initialize" {
+            let version = response
+                .result
+                .as_ref()
+                .and_then(|result| result.get("protocolVersion"))
+                .and_then(|version| version.as_str());
+            *self.protocol_version.lock().await = version.map(str::to_string);
+        }
+        Ok(response)
     }
 
     async fn connect(&mut self) -> Result<()> {
@@ -57,6 +337,20 @@ impl Connector for HttpConnector {
     }
 
     async fn disconnect(&mut self) -> Result<()> {
+        if let Some((_, task)) = self.listener.lock().await.take() {
+            task.abort();
+        }
+
+        // Let the server release the session; it expires on its own otherwise
+        if self.session_id.lock().await.is_some() {
+            let _ = self
+                .with_session_headers(self.client.delete(&self.config.url))
+                .await
+                .send()
+                .await;
+        }
+        *self.session_id.lock().await = None;
+        *self.protocol_version.lock().await = None;
         *self.connected.lock().await = false;
         Ok(())
     }
@@ -64,11 +358,48 @@ impl Connector for HttpConnector {
     fn is_connected(&self) -> bool {
         futures::executor::block_on(async { *self.connected.lock().await })
     }
+
+    fn set_notification_handler(&mut self, handler: NotificationHandler) {
+        self.notification_handler = Some(handler);
+    }
+
+    fn set_request_handler(&mut self, handler: RequestHandler) {
+        self.request_handler = Some(handler);
+    }
+
+    async fn send_notification(&self, notification: JsonRpcNotification) -> Result<()> {
+        if !*self.connected.lock().await {
+            return Err(Error::ConnectionError("Not connected".to_string()));
+        }
+
+        let builder = self
+            .client
+            .post(&self.config.url)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(&notification)
+            .timeout(std::time::Duration::from_secs(self.config.timeout_secs));
+
+        let response = self
+            .with_session_headers(builder)
+            .await
+            .send()
+            .await
+            .map_err(|e| Error::ConnectionError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(Error::ConnectionError(format!(
+                "Server rejected '{}': {}",
+                notification.method,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::Value;
 
     #[test]
     fn test_http_connector_creation() {
@@ -82,4 +413,32 @@ mod tests {
         assert!(connector.connect().await.is_ok());
         assert!(connector.is_connected());
     }
+
+    #[test]
+    fn test_response_from_event_stream() {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut connector = HttpConnector::default();
+        let sink = received.clone();
+        connector.set_notification_handler(Arc::new(move |method: &str, _params: &Value| {
+            sink.lock().unwrap().push(method.to_string());
+        }));
+
+        let mut parser = EventStreamParser::default();
+        let mut events = parser.push(
+            b"data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{}}\n\n",
+        );
+        // Events may be split across chunks
+        events.extend(parser.push(b"data: {\"jsonrpc\":\"2.0\",\"id\":\"42\","));
+        events.extend(parser.push(b"\"result\":{\"ok\":true}}\r\n\r\n"));
+        assert_eq!(events.len(), 2);
+
+        let id = RequestId::from("42");
+        assert!(connector.handle_event(&events[0], &id).is_none());
+        let response = connector.handle_event(&events[1], &id).unwrap();
+        assert_eq!(response.result.unwrap()["ok"], true);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["notifications/progress".to_string()]
+        );
+    }
 }
@@ -9,6 +9,6 @@ pub mod base;
 pub mod http;
 pub mod stdio;
 
-pub use base::{Connector, ConnectorConfig};
+pub use base::{Connector, ConnectorConfig, NotificationHandler, RequestHandler};
 pub use http::HttpConnector;
 pub use stdio::StdioConnector;
@@ -1,19 +1,44 @@
 /// Stdio connector for MCP - Standard input/output based connections
-use super::base::Connector;
-use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
-use crate::error::{Result, Error};
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
-use std::sync::Arc;
+use super::base::{
+    dispatch_notification, dispatch_request, Connector, NotificationHandler, RequestHandler,
+};
+use crate::error::{Error, Result};
+use crate::jsonrpc;
+use crate::protocol::{
+    JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, RequestId,
+};
+use dashmap::DashMap;
 use std::collections::HashMap;
-use tokio::sync::Mutex;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+/// Requests waiting for their response, by request ID
+type PendingRequests = Arc<DashMap<RequestId, oneshot::Sender<JsonRpcResponse>>>;
+
+/// The server process, shared with the reader task so it can answer
+/// requests from the server
+type SharedProcess = Arc<Mutex<Option<Process>>>;
+
+/// A running server process
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    reader: JoinHandle<()>,
+}
 
 /// Stdio-based MCP connector for spawning and communicating with processes
 pub struct StdioConnector {
     command: String,
     args: Vec<String>,
     env_vars: HashMap<String, String>,
-    child: Arc<Mutex<Option<Child>>>,
+    process: SharedProcess,
+    pending: PendingRequests,
+    notification_handler: Option<NotificationHandler>,
+    request_handler: Option<RequestHandler>,
     connected: Arc<Mutex<bool>>,
 }
 
@@ -24,7 +49,10 @@ impl StdioConnector {
             command,
             args,
             env_vars: HashMap::new(),
-            child: Arc::new(Mutex::new(None)),
+            process: Arc::new(Mutex::new(None)),
+            pending: Arc::new(DashMap::new()),
+            notification_handler: None,
+            request_handler: None,
             connected: Arc::new(Mutex::new(false)),
         }
     }
@@ -46,6 +74,112 @@ impl StdioConnector {
     }
 }
 
+/// Removes a request from the pending map if its caller stops waiting
+struct PendingGuard<'a> {
+    pending: &'a PendingRequests,
+    id: RequestId,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.remove(&self.id);
+    }
+}
+
+/// Write one message line to the server's stdin
+async fn write_message(
+    process: &Mutex<Option<Process>>,
+    message: &impl serde::Serialize,
+) -> Result<()> {
+    let mut line =
+        serde_json::to_string(message).map_err(|e| Error::ConnectionError(e.to_string()))?;
+    line.push('\n');
+
+    let mut process = process.lock().await;
+    let process = process
+        .as_mut()
+        .ok_or_else(|| Error::ConnectionError("No process running".to_string()))?;
+    process
+        .stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| Error::ConnectionError(e.to_string()))?;
+    process
+        .stdin
+        .flush()
+        .await
+        .map_err(|e| Error::ConnectionError(e.to_string()))
+}
+
+/// Handlers for what the server sends on its own initiative
+#[derive(Clone)]
+struct Handlers {
+    notifications: Option<NotificationHandler>,
+    requests: Option<RequestHandler>,
+}
+
+/// Route each line the server writes to the request waiting for it, to the
+/// notification handler, or to the request handler, whose answer is
+/// written back to the server
+async fn read_messages(
+    stdout: ChildStdout,
+    process: SharedProcess,
+    pending: PendingRequests,
+    handlers: Handlers,
+) {
+    let mut lines = BufReader::new(stdout).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let incoming = match jsonrpc::parse(line.as_bytes()) {
+            Ok(incoming) => incoming,
+            Err(_) => {
+                tracing::warn!("Ignoring non-JSON output from server");
+                continue;
+            }
+        };
+
+        for message in incoming.into_messages() {
+            match message {
+                Ok(JsonRpcMessage::Notification(notification)) => {
+                    dispatch_notification(handlers.notifications.as_ref(), &notification);
+                }
+                Ok(JsonRpcMessage::Request(request)) => {
+                    let handler = handlers.requests.clone();
+                    let process = process.clone();
+                    tokio::spawn(async move {
+                        let response = dispatch_request(handler, request).await;
+                        if let Err(e) = write_message(&process, &response).await {
+                            tracing::debug!("Could not answer server request: {}", e);
+                        }
+                    });
+                }
+                Ok(JsonRpcMessage::Response(response)) => {
+                    match response.id.as_ref().and_then(|id| pending.remove(id)) {
+                        Some((_, waiter)) => {
+                            let _ = waiter.send(response);
+                        }
+                        None => tracing::debug!(
+                            "Ignoring response to unknown request {:?}",
+                            response.id
+                        ),
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!("Ignoring malformed message from server: {:?}", error.error)
+                }
+            }
+        }
+    }
+
+    // The process is gone; fail everything still waiting
+    pending.clear();
+}
+
 #[async_trait::async_trait]
 impl Connector for StdioConnector {
     async fn send_request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
@@ -53,61 +187,71 @@ impl Connector for StdioConnector {
             return Err(Error::ConnectionError("Not connected".to_string()));
         }
 
-        let mut child_lock = self.child.lock().await;
-        let child = child_lock
-            .as_mut()
-            .ok_or_else(|| Error::ConnectionError("No process running".to_string()))?;
-
-        // Send request as JSON line
-        let json_str = serde_json::to_string(&request)
-            .map_err(|e| Error::ConnectionError(e.to_string()))?;
+        // Register before writing so a fast response is not missed
+        let (waiter, response) = oneshot::channel();
+        self.pending.insert(request.id.clone(), waiter);
+        let _guard = PendingGuard {
+            pending: &self.pending,
+            id: request.id.clone(),
+        };
 
-        if let Some(stdin) = child.stdin.as_mut() {
-            writeln!(stdin, "{}", json_str)
-                .map_err(|e| Error::ConnectionError(e.to_string()))?;
-        } else {
-            return Err(Error::ConnectionError("No stdin available".to_string()));
-        }
+        write_message(&self.process, &request).await?;
 
-        // Read response from stdout
-        if let Some(stdout) = child.stdout.as_mut() {
-            let mut reader = BufReader::new(stdout);
-            let mut response_line = String::new();
-            reader
-                .read_line(&mut response_line)
-                .map_err(|e| Error::ConnectionError(e.to_string()))?;
-
-            serde_json::from_str(&response_line)
-                .map_err(|e| Error::ConnectionError(e.to_string()))
-        } else {
-            Err(Error::ConnectionError("No stdout available".to_string()))
-        }
+        response.await.map_err(|_| {
+            Error::ConnectionError("Server process exited before responding".to_string())
+        })
     }
 
     async fn connect(&mut self) -> Result<()> {
         let mut cmd = Command::new(&self.command);
         cmd.args(&self.args)
             .stdin(Stdio::piped())
-            .stdout(Stdio::piped());
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
 
         // Set environment variables
         for (key, value) in &self.env_vars {
             cmd.env(key, value);
         }
 
-        let child = cmd.spawn()
+        let mut child = cmd
+            .spawn()
             .map_err(|e| Error::ConnectionError(format!("Failed to spawn process: {}", e)))?;
 
-        *self.child.lock().await = Some(child);
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| Error::ConnectionError("No stdin available".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| Error::ConnectionError("No stdout available".to_string()))?;
+        let handlers = Handlers {
+            notifications: self.notification_handler.clone(),
+            requests: self.request_handler.clone(),
+        };
+        let reader = tokio::spawn(read_messages(
+            stdout,
+            self.process.clone(),
+            self.pending.clone(),
+            handlers,
+        ));
+
+        *self.process.lock().await = Some(Process {
+            child,
+            stdin,
+            reader,
+        });
         *self.connected.lock().await = true;
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<()> {
-        if let Some(mut child) = self.child.lock().await.take() {
-            let _ = child.kill();
-            let _ = child.wait();
+        if let Some(mut process) = self.process.lock().await.take() {
+            process.reader.abort();
+            let _ = process.child.kill().await;
         }
+        self.pending.clear();
         *self.connected.lock().await = false;
         Ok(())
     }
@@ -115,15 +259,98 @@ impl Connector for StdioConnector {
     fn is_connected(&self) -> bool {
         futures::executor::block_on(async { *self.connected.lock().await })
     }
+
+    fn set_notification_handler(&mut self, handler: NotificationHandler) {
+        self.notification_handler = Some(handler);
+    }
+
+    fn set_request_handler(&mut self, handler: RequestHandler) {
+        self.request_handler = Some(handler);
+    }
+
+    async fn send_notification(&self, notification: JsonRpcNotification) -> Result<()> {
+        if !*self.connected.lock().await {
+            return Err(Error::ConnectionError("Not connected".to_string()));
+        }
+        write_message(&self.process, &notification).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::Value;
 
     #[test]
     fn test_stdio_connector_creation() {
         let connector = StdioConnector::from_command("echo".to_string());
         assert!(!connector.is_connected());
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_connector_routes_messages() {
+        // Replies to one request with a notification followed by the response
+        let script = r#"read line
+echo '{"jsonrpc":"2.0","method":"notifications/resources/updated","params":{"uri":"file:///a"}}'
+echo '{"jsonrpc":"2.0","id":"1","result":{"ok":true}}'"#;
+        let mut connector =
+            StdioConnector::new("sh".to_string(), vec!["-c".to_string(), script.to_string()]);
+
+        let (tx, rx) = oneshot::channel();
+        let tx = std::sync::Mutex::new(Some(tx));
+        connector.set_notification_handler(Arc::new(move |method: &str, params: &Value| {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send((method.to_string(), params.clone()));
+            }
+        }));
+        connector.connect().await.unwrap();
+
+        let mut request = JsonRpcRequest::new("ping", None);
+        request.id = "1".into();
+        let response = connector.send_request(request).await.unwrap();
+        assert_eq!(response.result.unwrap()["ok"], true);
+
+        let (method, params) = rx.await.unwrap();
+        assert_eq!(method, "notifications/resources/updated");
+        assert_eq!(params["uri"], "file:///a");
+
+        // The script has exited, so further requests fail instead of hanging
+        assert!(connector
+            .send_request(JsonRpcRequest::new("ping", None))
+            .await
+            .is_err());
+        connector.disconnect().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_connector_answers_server_requests() {
+        // Sends a request of its own and echoes the client's answer back
+        // as the result of the request it was sent
+        let script = r#"read line
+echo '{"jsonrpc":"2.0","id":"s1","method":"sampling/createMessage","params":{"n":2}}'
+read answer
+echo "{\"jsonrpc\":\"2.0\",\"id\":\"1\",\"result\":$answer}""#;
+        let mut connector =
+            StdioConnector::new("sh".to_string(), vec!["-c".to_string(), script.to_string()]);
+        connector.set_request_handler(Arc::new(|request: JsonRpcRequest| {
+            Box::pin(async move {
+                let n = request.params.unwrap()["n"].as_i64().unwrap();
+                Ok(serde_json::json!({ "method": request.method, "doubled": n * 2 }))
+            })
+        }));
+        connector.connect().await.unwrap();
+
+        let mut request = JsonRpcRequest::new("tools/call", None);
+        request.id = "1".into();
+        let response = connector.send_request(request).await.unwrap();
+        let answer = response.result.unwrap();
+        assert_eq!(answer["id"], "s1");
+        assert_eq!(
+            answer["result"],
+            serde_json::json!({ "method": "sampling/createMessage", "doubled": 4 })
+        );
+        connector.disconnect().await.unwrap();
+    }
 }
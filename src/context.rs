@@ -0,0 +1,521 @@
+//! Per-request context handed from a transport to the server.
+
+use crate::error::{Error, Result};
+use crate::protocol::{
+    ClientCapabilities, CreateMessageParams, CreateMessageResult, ElicitAction,
+    ElicitRequestParams, ElicitResult, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+    LoggingLevel, LoggingMessage, ProgressToken, ProtocolVersion, RequestId,
+};
+use crate::schema;
+use crate::session_manager::SessionInfo;
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Channel carrying JSON-RPC messages from the server to a client
+pub type OutboundSender = mpsc::UnboundedSender<Value>;
+
+/// A request the server sent to a client, waiting for its response
+#[derive(Debug)]
+pub(crate) struct PendingRequest {
+    /// Session the request was sent on; only that session may answer it
+    pub(crate) session_id: Option<String>,
+    pub(crate) reply: oneshot::Sender<JsonRpcResponse>,
+}
+
+/// Requests sent to clients that have not been answered yet, by request ID
+pub(crate) type PendingRequests = Arc<DashMap<RequestId, PendingRequest>>;
+
+/// Build a JSON-RPC notification message
+pub(crate) fn notification(method: &str, params: Value) -> Value {
+    json!(JsonRpcNotification::new(method, Some(params)))
+}
+
+/// Handle to the client session a request arrived on.
+///
+/// Messages sent through it are not tied to any request, e.g. they travel
+/// on the standalone SSE stream of a Streamable HTTP session.
+#[derive(Debug, Clone)]
+pub struct SessionHandle {
+    id: String,
+    outbound: OutboundSender,
+}
+
+impl SessionHandle {
+    /// Create a handle for a session with the given outbound channel
+    pub fn new(id: impl Into<String>, outbound: OutboundSender) -> Self {
+        Self {
+            id: id.into(),
+            outbound,
+        }
+    }
+
+    /// Session ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Send a message to the client outside of any request.
+    ///
+    /// Returns `false` if the session has been closed.
+    pub fn send(&self, message: Value) -> bool {
+        self.outbound.send(message).is_ok()
+    }
+}
+
+/// State that travels with a single request while the server handles it.
+///
+/// Transports that can deliver messages alongside a response (such as the
+/// SSE stream of a Streamable HTTP POST) attach an outbound channel, so
+/// anything the server sends for this request reaches the right client.
+///
+/// Long-running handlers use it to report progress and to stop early once
+/// the client cancels the request.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    request_id: RequestId,
+    session: Option<SessionHandle>,
+    session_info: Option<SessionInfo>,
+    outbound: Option<OutboundSender>,
+    progress_token: Option<ProgressToken>,
+    protocol_version: Option<ProtocolVersion>,
+    client_capabilities: Option<ClientCapabilities>,
+    log_level: Option<LoggingLevel>,
+    pending: Option<PendingRequests>,
+    cancellation: CancellationToken,
+}
+
+impl RequestContext {
+    /// Create a context for a request that has no session or outbound channel
+    pub fn new(request_id: impl Into<RequestId>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            session: None,
+            session_info: None,
+            outbound: None,
+            progress_token: None,
+            protocol_version: None,
+            client_capabilities: None,
+            log_level: None,
+            pending: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Attach the session the request belongs to
+    pub fn with_session(mut self, session: SessionHandle) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Attach what the server knows about the request's session
+    pub fn with_session_info(mut self, info: SessionInfo) -> Self {
+        self.session_info = Some(info);
+        self
+    }
+
+    /// Attach a channel for messages related to this request
+    pub fn with_outbound(mut self, outbound: OutboundSender) -> Self {
+        self.outbound = Some(outbound);
+        self
+    }
+
+    /// Attach the `progressToken` the client sent in the request's `_meta`
+    pub fn with_progress_token(mut self, token: ProgressToken) -> Self {
+        self.progress_token = Some(token);
+        self
+    }
+
+    /// Attach the protocol version in effect for the request
+    pub fn with_protocol_version(mut self, version: ProtocolVersion) -> Self {
+        self.protocol_version = Some(version);
+        self
+    }
+
+    /// Attach the capabilities the client declared in `initialize`
+    pub fn with_client_capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.client_capabilities = Some(capabilities);
+        self
+    }
+
+    /// Attach the minimum level the client wants log messages at
+    pub fn with_log_level(mut self, level: LoggingLevel) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Attach the table that routes client responses back to [`send_request`](Self::send_request)
+    pub(crate) fn with_pending(mut self, pending: PendingRequests) -> Self {
+        self.pending = Some(pending);
+        self
+    }
+
+    /// ID of the request being handled
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+
+    /// Session the request arrived on, if the transport has one
+    pub fn session(&self) -> Option<&SessionHandle> {
+        self.session.as_ref()
+    }
+
+    /// ID of the session the request arrived on
+    pub fn session_id(&self) -> Option<&str> {
+        self.session.as_ref().map(|s| s.id())
+    }
+
+    /// The session the request arrived on, if the server has it open: its
+    /// transport, client and per-session state
+    pub fn session_info(&self) -> Option<&SessionInfo> {
+        self.session_info.as_ref()
+    }
+
+    /// Protocol version negotiated with the client, if known. Handlers can
+    /// use it to gate behavior that differs between MCP revisions.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.protocol_version
+    }
+
+    /// Capabilities the client declared in `initialize`, if it has a session
+    pub fn client_capabilities(&self) -> Option<&ClientCapabilities> {
+        self.client_capabilities.as_ref()
+    }
+
+    /// Send a notification related to this request to the client.
+    ///
+    /// Uses the request's outbound channel, falling back to the session.
+    /// Returns `false` when the transport cannot deliver it (neither is
+    /// available, or the client has gone away).
+    pub fn notify(&self, method: &str, params: Value) -> bool {
+        self.send(notification(method, params))
+    }
+
+    /// Minimum level the client asked for with `logging/setLevel`, if any
+    pub fn log_level(&self) -> Option<LoggingLevel> {
+        self.log_level
+    }
+
+    /// Send a log message to the client with `notifications/message`.
+    ///
+    /// Only sent if the client asked for messages at `level` or above;
+    /// returns whether it was sent.
+    pub fn log(&self, level: LoggingLevel, logger: Option<&str>, data: Value) -> bool {
+        if !matches!(self.log_level, Some(min) if level >= min) {
+            return false;
+        }
+        let message = LoggingMessage {
+            level,
+            logger: logger.map(str::to_string),
+            data,
+        };
+        self.notify("notifications/message", json!(message))
+    }
+
+    /// Send a request to the client and wait for its result.
+    ///
+    /// Fails if the transport cannot deliver it, if the client answers with
+    /// an error, or if this request is cancelled first, in which case the
+    /// client is told to stop with `notifications/cancelled`.
+    pub async fn send_request(&self, method: &str, params: Value) -> Result<Value> {
+        let pending = self.pending.as_ref().ok_or_else(|| {
+            Error::ConnectionError("Requests to the client are not supported here".to_string())
+        })?;
+
+        let request = JsonRpcRequest::new(method, Some(params));
+        let id = request.id.clone();
+        let (reply, response) = oneshot::channel();
+        pending.insert(
+            id.clone(),
+            PendingRequest {
+                session_id: self.session_id().map(str::to_string),
+                reply,
+            },
+        );
+        let _pending = PendingGuard { pending, id: &id };
+
+        if !self.send(json!(request)) {
+            return Err(Error::ConnectionError(
+                "Client is not connected".to_string(),
+            ));
+        }
+
+        let response = tokio::select! {
+            biased;
+            _ = self.cancellation.cancelled() => {
+                self.notify("notifications/cancelled", json!({ "requestId": id, "reason": "Request cancelled" }));
+                return Err(Error::Cancelled(format!("{} request {} was abandoned", method, id)));
+            }
+            response = response => response
+                .map_err(|_| Error::ConnectionError("Client disconnected before answering".to_string()))?,
+        };
+
+        match (response.result, response.error) {
+            // The user declined, as opposed to the client failing
+            (_, Some(error)) if error.code == -1 => Err(Error::Rejected(error.message)),
+            (_, Some(error)) => Err(Error::RequestError(format!(
+                "{} ({})",
+                error.message, error.code
+            ))),
+            (result, None) => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Ask the client's LLM for a completion with `sampling/createMessage`.
+    ///
+    /// The client must have declared the `sampling` capability; it may ask
+    /// its user to approve the request and can refuse it.
+    pub async fn create_message(&self, params: CreateMessageParams) -> Result<CreateMessageResult> {
+        if self
+            .client_capabilities
+            .as_ref()
+            .and_then(|c| c.sampling.as_ref())
+            .is_none()
+        {
+            return Err(Error::InvalidRequest(
+                "Client does not support sampling".to_string(),
+            ));
+        }
+        let result = self
+            .send_request("sampling/createMessage", json!(params))
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Ask the user for structured input with `elicitation/create`, e.g. a
+    /// missing parameter or a confirmation, and wait for their answer.
+    ///
+    /// The client must have declared the `elicitation` capability. Accepted
+    /// input is checked against `requested_schema` before it is returned.
+    pub async fn elicit(&self, params: ElicitRequestParams) -> Result<ElicitResult> {
+        if self
+            .client_capabilities
+            .as_ref()
+            .and_then(|c| c.elicitation.as_ref())
+            .is_none()
+        {
+            return Err(Error::InvalidRequest(
+                "Client does not support elicitation".to_string(),
+            ));
+        }
+        check_elicitation_schema(&params.requested_schema)?;
+
+        let result = self
+            .send_request("elicitation/create", json!(params))
+            .await?;
+        let result: ElicitResult = serde_json::from_value(result)?;
+        if result.action == ElicitAction::Accept {
+            let content = result.content.clone().unwrap_or(Value::Null);
+            let violations = schema::validate(&params.requested_schema, &content);
+            if !violations.is_empty() {
+                return Err(Error::RequestError(format!(
+                    "Elicited input does not match the requested schema: {}",
+                    schema::summarize(&violations)
+                )));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Send a message to the client on the request's outbound channel,
+    /// falling back to the session
+    fn send(&self, message: Value) -> bool {
+        match (&self.outbound, &self.session) {
+            (Some(outbound), _) => outbound.send(message).is_ok(),
+            (None, Some(session)) => session.send(message),
+            (None, None) => false,
+        }
+    }
+
+    /// Token the client asked progress to be reported under, if any
+    pub fn progress_token(&self) -> Option<&ProgressToken> {
+        self.progress_token.as_ref()
+    }
+
+    /// Report progress on this request with `notifications/progress`.
+    ///
+    /// `progress` should increase with every call. Does nothing and returns
+    /// `false` if the client did not ask for progress.
+    pub fn report_progress(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<&str>,
+    ) -> bool {
+        let Some(token) = &self.progress_token else {
+            return false;
+        };
+
+        let mut params = json!({ "progressToken": token, "progress": progress });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = json!(message);
+        }
+        self.notify("notifications/progress", params)
+    }
+
+    /// Token cancelled when the client sends `notifications/cancelled` for
+    /// this request. Await `cancelled()` on it to stop work early.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Whether the client has cancelled this request
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// Check a requested schema is a flat object of primitive properties, the
+/// only kind clients are required to render as a form
+fn check_elicitation_schema(schema: &Value) -> Result<()> {
+    let invalid = |reason: String| {
+        Err(Error::InvalidParams(format!(
+            "Invalid elicitation schema: {}",
+            reason
+        )))
+    };
+
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        return invalid("must be of type 'object'".to_string());
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, property) in properties.into_iter().flatten() {
+        match property.get("type").and_then(Value::as_str) {
+            Some("string" | "number" | "integer" | "boolean") => {}
+            _ => {
+                return invalid(format!(
+                    "property '{}' must be a string, number, integer or boolean",
+                    name
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes a pending request once its sender stops waiting
+struct PendingGuard<'a> {
+    pending: &'a PendingRequests,
+    id: &'a RequestId,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_without_outbound() {
+        let ctx = RequestContext::new("1".to_string());
+        assert!(!ctx.notify("notifications/progress", json!({})));
+        assert!(ctx.session_id().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_notify_with_outbound() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = RequestContext::new("1".to_string()).with_outbound(tx);
+        assert!(ctx.notify("notifications/message", json!({ "level": "info" })));
+
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"]["level"], "info");
+    }
+
+    #[tokio::test]
+    async fn test_log_respects_client_level() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = RequestContext::new("1".to_string()).with_outbound(tx);
+        assert!(!ctx.log(LoggingLevel::Emergency, None, json!("nobody asked")));
+
+        let ctx = ctx.with_log_level(LoggingLevel::Warning);
+        assert!(!ctx.log(LoggingLevel::Info, None, json!("too quiet")));
+        assert!(ctx.log(LoggingLevel::Error, Some("db"), json!("connection lost")));
+
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(
+            message["params"],
+            json!({ "level": "error", "logger": "db", "data": "connection lost" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_progress() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = RequestContext::new("1".to_string()).with_outbound(tx);
+        assert!(!ctx.report_progress(1.0, None, None));
+
+        let ctx = ctx.with_progress_token("tok".into());
+        assert!(ctx.report_progress(2.0, Some(10.0), Some("crawling")));
+
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(
+            message["params"],
+            json!({ "progressToken": "tok", "progress": 2.0, "total": 10.0, "message": "crawling" })
+        );
+    }
+
+    #[test]
+    fn test_elicitation_schema_is_flat() {
+        let flat = json!({
+            "type": "object",
+            "properties": {
+                "branch": { "type": "string", "enum": ["main", "dev"] },
+                "force": { "type": "boolean" }
+            }
+        });
+        assert!(check_elicitation_schema(&flat).is_ok());
+
+        let nested = json!({ "type": "object", "properties": { "target": { "type": "object" } } });
+        assert!(matches!(
+            check_elicitation_schema(&nested),
+            Err(Error::InvalidParams(_))
+        ));
+        assert!(check_elicitation_schema(&json!({ "type": "string" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_rejection_is_distinct() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let pending: PendingRequests = Arc::new(DashMap::new());
+        let ctx = RequestContext::new("1".to_string())
+            .with_outbound(tx)
+            .with_pending(pending.clone());
+        tokio::spawn(async move {
+            let request = rx.recv().await.unwrap();
+            let id: RequestId = serde_json::from_value(request["id"].clone()).unwrap();
+            let (_, waiting) = pending.remove(&id).unwrap();
+            let error = Error::Rejected("User rejected sampling request".to_string());
+            let _ = waiting
+                .reply
+                .send(JsonRpcResponse::failure(Some(id), (&error).into()));
+        });
+
+        let result = ctx.send_request("sampling/createMessage", json!({})).await;
+        assert!(
+            matches!(result, Err(Error::Rejected(message)) if message.contains("User rejected sampling request"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_handle_send() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = RequestContext::new("1".to_string()).with_session(SessionHandle::new("abc", tx));
+        assert_eq!(ctx.session_id(), Some("abc"));
+        assert!(ctx.session().unwrap().send(json!({ "ok": true })));
+        assert_eq!(rx.recv().await.unwrap()["ok"], true);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::protocol::{JsonRpcError, ResultContent};
+use crate::schema::{self, SchemaViolation};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -10,6 +12,10 @@ pub enum Error {
     #[error("Invalid params: {0}")]
     InvalidParams(String),
 
+    /// Arguments that failed validation against a tool's input schema
+    #[error("Invalid arguments: {}", schema::summarize(.0))]
+    InvalidArguments(Vec<SchemaViolation>),
+
     #[error("Method not found: {0}")]
     MethodNotFound(String),
 
@@ -22,18 +28,34 @@ pub enum Error {
     #[error("Tool not found: {0}")]
     ToolNotFound(String),
 
+    /// A tool failed while executing. The content is returned to the model
+    /// as an `isError` tool result rather than as a protocol error.
+    #[error("Tool error: {}", tool_error_text(.0))]
+    ToolError(Vec<ResultContent>),
+
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
 
+    #[error("Prompt not found: {0}")]
+    PromptNotFound(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
     #[error("Request error: {0}")]
     RequestError(String),
 
+    /// The user declined a request, such as a server's `sampling/createMessage`
+    #[error("Request rejected: {0}")]
+    Rejected(String),
+
     #[error("Timeout")]
     Timeout,
 
+    /// The request was cancelled before it completed
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+
     #[error("Connection error: {0}")]
     ConnectionError(String),
 
@@ -44,22 +66,61 @@ pub enum Error {
     Unknown(String),
 }
 
+/// The text blocks of a tool error, for display
+fn tool_error_text(content: &[ResultContent]) -> String {
+    content
+        .iter()
+        .filter_map(|c| match c {
+            ResultContent::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 impl Error {
+    /// A tool execution error with a text message for the model
+    pub fn tool(message: impl Into<String>) -> Self {
+        Error::ToolError(vec![ResultContent::text(message)])
+    }
+
     pub fn error_code(&self) -> i64 {
         match self {
             Error::InvalidRequest(_) => -32600,
             Error::InvalidParams(_) => -32602,
+            Error::InvalidArguments(_) => -32602,
             Error::MethodNotFound(_) => -32601,
             Error::InternalError(_) => -32603,
             Error::ServerError(_) => -32000,
             Error::ToolNotFound(_) => -32001,
+            Error::ToolError(_) => -32603,
             Error::ResourceNotFound(_) => -32002,
+            Error::PromptNotFound(_) => -32602,
             Error::SerializationError(_) => -32603,
             Error::RequestError(_) => -32603,
+            Error::Rejected(_) => -1,
             Error::Timeout => -32604,
+            Error::Cancelled(_) => -32800,
             Error::ConnectionError(_) => -32605,
             Error::LLMError(_) => -32606,
             Error::Unknown(_) => -32603,
         }
     }
 }
+
+impl From<&Error> for JsonRpcError {
+    fn from(error: &Error) -> Self {
+        let data = match error {
+            Error::InvalidArguments(violations) => {
+                Some(serde_json::json!({ "errors": violations }))
+            }
+            _ => None,
+        };
+
+        JsonRpcError {
+            code: error.error_code(),
+            message: error.to_string(),
+            data,
+        }
+    }
+}
@@ -292,6 +292,20 @@ async fn handle_index() -> Html<&'static str> {
             margin-bottom: 12px;
         }
 
+        .tool-hint {
+            display: inline-block;
+            font-size: 11px;
+            color: #555;
+            background: #eee;
+            padding: 2px 6px;
+            margin: 0 4px 8px 0;
+        }
+
+        .tool-hint.destructive {
+            color: #fff;
+            background: #c53030;
+        }
+
         .tool-form {
             background: #fafafa;
             padding: 15px;
@@ -600,9 +614,10 @@ async fn handle_index() -> Html<&'static str> {
 
                 document.getElementById('tools-list').innerHTML = tools.map(tool => `
                     <div class="tool-card">
-                        <h3>${tool.name}</h3>
-                        <p>${tool.description || 'No description'}</p>
-                        <button class="btn-primary" onclick="editTool('${tool.name}')">Test Tool</button>
+                        <h3>${escapeHtml(toolTitle(tool))}</h3>
+                        ${toolHints(tool)}
+                        <p>${escapeHtml(tool.description || 'No description')}</p>
+                        <button class="btn-primary" data-tool="${escapeHtml(tool.name)}" onclick="editTool(this.dataset.tool)">Test Tool</button>
                     </div>
                 `).join('');
             } catch(e) {
@@ -610,6 +625,84 @@ async fn handle_index() -> Html<&'static str> {
             }
         }
 
+        function toolTitle(tool) {
+            return tool.title || (tool.annotations && tool.annotations.title) || tool.name;
+        }
+
+        // Badges for the tool's behavior hints, with the spec defaults applied
+        function toolHints(tool) {
+            const a = tool.annotations || {};
+            const hints = [];
+            if (a.readOnlyHint) {
+                hints.push('<span class="tool-hint">read-only</span>');
+            } else {
+                if (a.destructiveHint !== false) hints.push('<span class="tool-hint destructive">destructive</span>');
+                if (a.idempotentHint) hints.push('<span class="tool-hint">idempotent</span>');
+            }
+            if (a.openWorldHint === false) hints.push('<span class="tool-hint">closed world</span>');
+            return hints.join('');
+        }
+
+        // Safe in element content and in quoted attribute values
+        function escapeHtml(value) {
+            return String(value)
+                .replace(/&/g, '&amp;')
+                .replace(/</g, '&lt;')
+                .replace(/>/g, '&gt;')
+                .replace(/"/g, '&quot;')
+                .replace(/'/g, '&#39;');
+        }
+
+        function renderToolResult(result) {
+            if (result.error) {
+                return `<div class="result-content">${escapeHtml(result.error)}</div>`;
+            }
+            let html = (result.content || []).map(renderContent).join('');
+            if (result.structuredContent) {
+                html += `<div class="result-content"><pre>${escapeHtml(JSON.stringify(result.structuredContent, null, 2))}</pre></div>`;
+            }
+            return html;
+        }
+
+        // One tool result content block, with its annotations underneath
+        function renderContent(block) {
+            let body;
+            switch (block.type) {
+                case 'text':
+                    body = `<pre>${escapeHtml(block.text)}</pre>`;
+                    break;
+                case 'image':
+                    body = `<img src="data:${escapeHtml(block.mimeType)};base64,${escapeHtml(block.data)}" style="max-width: 100%;">`;
+                    break;
+                case 'audio':
+                    body = `<audio controls src="data:${escapeHtml(block.mimeType)};base64,${escapeHtml(block.data)}"></audio>`;
+                    break;
+                case 'resource_link':
+                    body = `<div><strong>${escapeHtml(block.title || block.name)}</strong> <code>${escapeHtml(block.uri)}</code></div>`
+                        + (block.description ? `<div>${escapeHtml(block.description)}</div>` : '');
+                    break;
+                case 'resource': {
+                    const r = block.resource;
+                    body = `<div><code>${escapeHtml(r.uri)}</code></div>`
+                        + (r.text !== undefined
+                            ? `<pre>${escapeHtml(r.text)}</pre>`
+                            : `<div>[binary, ${escapeHtml(r.mimeType || 'application/octet-stream')}]</div>`);
+                    break;
+                }
+                default:
+                    body = `<pre>${escapeHtml(JSON.stringify(block, null, 2))}</pre>`;
+            }
+            const a = block.annotations;
+            const notes = [];
+            if (a) {
+                if (a.audience) notes.push(`audience: ${a.audience.join(', ')}`);
+                if (a.priority !== undefined) notes.push(`priority: ${a.priority}`);
+                if (a.lastModified) notes.push(`modified: ${a.lastModified}`);
+            }
+            const meta = notes.length ? `<div class="result-title">${escapeHtml(notes.join(' · '))}</div>` : '';
+            return `<div class="result-content">${body}${meta}</div>`;
+        }
+
         async function loadResources() {
             document.getElementById('resources-list').innerHTML = `<div class="empty-state"><h3>Resources</h3><p>Resource support coming soon</p></div>`;
         }
@@ -653,19 +746,20 @@ async fn handle_index() -> Html<&'static str> {
             }
 
             let formHTML = `<div style="padding: 20px; max-width: 500px;">
-                <h3>${tool.name}</h3>
-                <p style="color: #a0aec0; margin-bottom: 20px;">${tool.description || 'No description'}</p>
+                <h3>${escapeHtml(toolTitle(tool))}</h3>
+                ${toolHints(tool)}
+                <p style="color: #a0aec0; margin-bottom: 20px;">${escapeHtml(tool.description || 'No description')}</p>
                 <form id="tool-form" style="display: flex; flex-direction: column; gap: 15px;">`;
 
             // Generate form fields based on tool schema
-            if (tool.input_schema && tool.input_schema.properties) {
-                const props = tool.input_schema.properties;
+            if (tool.inputSchema && tool.inputSchema.properties) {
+                const props = tool.inputSchema.properties;
                 for (const [key, schema] of Object.entries(props)) {
-                    const isRequired = tool.input_schema.required && tool.input_schema.required.includes(key);
+                    const isRequired = tool.inputSchema.required && tool.inputSchema.required.includes(key);
                     formHTML += `
                         <div>
-                            <label style="display: block; margin-bottom: 5px; color: #e2e8f0;">${key}${isRequired ? ' *' : ''}</label>
-                            <input type="text" name="${key}" placeholder="${key}" style="width: 100%; padding: 8px; background: #2d3748; border: 1px solid #4a5568; color: #e2e8f0; border-radius: 4px;" ${isRequired ? 'required' : ''}>
+                            <label style="display: block; margin-bottom: 5px; color: #e2e8f0;">${escapeHtml(key)}${isRequired ? ' *' : ''}</label>
+                            <input type="text" name="${escapeHtml(key)}" placeholder="${escapeHtml(key)}" style="width: 100%; padding: 8px; background: #2d3748; border: 1px solid #4a5568; color: #e2e8f0; border-radius: 4px;" ${isRequired ? 'required' : ''}>
                         </div>`;
                 }
             }
@@ -695,8 +789,13 @@ async fn handle_index() -> Html<&'static str> {
                         body: JSON.stringify({ tool_name: toolName, arguments: args })
                     });
                     const result = await res.json();
-                    alert(`Tool Result:\n${JSON.stringify(result, null, 2)}`);
-                    modal.remove();
+                    modal.firstElementChild.innerHTML = `<div style="padding: 20px; max-width: 500px;">
+                        <h3>${escapeHtml(toolTitle(tool))}</h3>
+                        <div class="result show${result.isError || result.error ? ' error' : ''}">
+                            <div class="result-title">Result</div>
+                            ${renderToolResult(result)}
+                        </div>
+                    </div>`;
                 } catch(err) {
                     alert(`Error executing tool: ${err.message}`);
                 }
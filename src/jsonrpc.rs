@@ -0,0 +1,341 @@
+//! JSON-RPC 2.0 messages, shared by the server transports and the client
+//! connectors.
+//!
+//! Incoming text is parsed with [`parse`], which accepts a single message or
+//! a batch array and classifies each entry as a request, notification or
+//! response. Entries that are not valid JSON-RPC become [`InvalidMessage`]s,
+//! to be answered with an error response per the spec.
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+
+/// Custom serializer for params that outputs empty object instead of null
+fn serialize_params<S>(params: &Option<Value>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match params {
+        Some(value) => value.serialize(serializer),
+        None => serde_json::json!({}).serialize(serializer),
+    }
+}
+
+/// Identifier of a request, chosen by its sender: a string or an integer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i64),
+    String(String),
+}
+
+impl RequestId {
+    /// A new random string ID
+    pub fn random() -> Self {
+        RequestId::String(Uuid::new_v4().to_string())
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(id) => write!(f, "{}", id),
+            RequestId::String(id) => f.write_str(id),
+        }
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(id: String) -> Self {
+        RequestId::String(id)
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(id: &str) -> Self {
+        RequestId::String(id.to_string())
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(id: i64) -> Self {
+        RequestId::Number(id)
+    }
+}
+
+/// JSON-RPC request, which expects a response with the same ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub id: RequestId,
+    pub method: String,
+    #[serde(default, serialize_with = "serialize_params")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcRequest {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: RequestId::random(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// JSON-RPC notification: a request without an ID, which is never answered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// JSON-RPC response. The ID is `None` only for errors about messages
+/// whose ID could not be read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: Option<RequestId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl JsonRpcResponse {
+    /// Successful response to the request `id`
+    pub fn success(id: RequestId, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Error response, to the request `id` if it is known
+    pub fn failure(id: Option<RequestId>, error: JsonRpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// JSON-RPC Error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// A message that could not be processed, with the ID to answer it under
+#[derive(Debug, Clone)]
+pub struct InvalidMessage {
+    pub id: Option<RequestId>,
+    pub error: JsonRpcError,
+}
+
+impl InvalidMessage {
+    pub(crate) fn new(id: Option<RequestId>, code: i64, message: String) -> Self {
+        Self {
+            id,
+            error: JsonRpcError::new(code, message),
+        }
+    }
+}
+
+impl From<InvalidMessage> for JsonRpcResponse {
+    fn from(invalid: InvalidMessage) -> Self {
+        JsonRpcResponse::failure(invalid.id, invalid.error)
+    }
+}
+
+/// A single JSON-RPC message of any kind
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum JsonRpcMessage {
+    Request(JsonRpcRequest),
+    Notification(JsonRpcNotification),
+    Response(JsonRpcResponse),
+}
+
+impl JsonRpcMessage {
+    /// Classify a JSON value, failing if it is not a valid JSON-RPC 2.0 message
+    pub fn from_value(value: Value) -> Result<Self, InvalidMessage> {
+        let id = value.get("id").cloned();
+        let invalid = |reason: String| {
+            // Echo the ID if it is usable, so the sender can match the error
+            let id = id.clone().and_then(|id| serde_json::from_value(id).ok());
+            InvalidMessage::new(id, INVALID_REQUEST, format!("Invalid request: {}", reason))
+        };
+
+        if !value.is_object() {
+            return Err(invalid("expected an object".to_string()));
+        }
+        if value.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Err(invalid("'jsonrpc' must be \"2.0\"".to_string()));
+        }
+        if matches!(value.get("params"), Some(params) if !params.is_object() && !params.is_array() && !params.is_null())
+        {
+            return Err(invalid("'params' must be an object or array".to_string()));
+        }
+
+        let message = match (value.get("method"), &id) {
+            (Some(_), None) => serde_json::from_value(value).map(JsonRpcMessage::Notification),
+            (Some(_), Some(Value::Null)) => {
+                return Err(invalid("request 'id' must not be null".to_string()))
+            }
+            (Some(_), Some(_)) => serde_json::from_value(value).map(JsonRpcMessage::Request),
+            (None, Some(_)) if value.get("result").is_some() || value.get("error").is_some() => {
+                serde_json::from_value(value).map(JsonRpcMessage::Response)
+            }
+            (None, _) => return Err(invalid("missing 'method'".to_string())),
+        };
+        message.map_err(|e| invalid(e.to_string()))
+    }
+}
+
+/// A message or batch as read from the wire. Entries that failed validation
+/// hold the error to send back.
+#[derive(Debug)]
+pub enum Incoming {
+    Single(Result<JsonRpcMessage, InvalidMessage>),
+    Batch(Vec<Result<JsonRpcMessage, InvalidMessage>>),
+}
+
+impl Incoming {
+    pub fn is_batch(&self) -> bool {
+        matches!(self, Incoming::Batch(_))
+    }
+
+    /// The entries, in order
+    pub fn into_messages(self) -> Vec<Result<JsonRpcMessage, InvalidMessage>> {
+        match self {
+            Incoming::Single(message) => vec![message],
+            Incoming::Batch(messages) => messages,
+        }
+    }
+}
+
+/// Parse a message or batch. Fails with `-32700` (invalid JSON) or `-32600`
+/// (empty batch) if nothing can be processed.
+pub fn parse(text: &[u8]) -> Result<Incoming, InvalidMessage> {
+    let value: Value = serde_json::from_slice(text)
+        .map_err(|e| InvalidMessage::new(None, PARSE_ERROR, format!("Parse error: {}", e)))?;
+
+    match value {
+        Value::Array(values) if values.is_empty() => Err(InvalidMessage::new(
+            None,
+            INVALID_REQUEST,
+            "Invalid request: empty batch".to_string(),
+        )),
+        Value::Array(values) => Ok(Incoming::Batch(
+            values.into_iter().map(JsonRpcMessage::from_value).collect(),
+        )),
+        value => Ok(Incoming::Single(JsonRpcMessage::from_value(value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_id_forms() {
+        let request: JsonRpcRequest =
+            serde_json::from_value(json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" })).unwrap();
+        assert_eq!(request.id, RequestId::Number(7));
+        assert_eq!(serde_json::to_value(&request.id).unwrap(), json!(7));
+
+        let id = RequestId::from("abc");
+        assert_eq!(serde_json::to_value(&id).unwrap(), json!("abc"));
+        assert_eq!(id.to_string(), "abc");
+
+        let error = JsonRpcResponse::failure(None, JsonRpcError::new(PARSE_ERROR, "bad"));
+        assert_eq!(serde_json::to_value(error).unwrap()["id"], Value::Null);
+    }
+
+    #[test]
+    fn test_classify_messages() {
+        let parse_one = |value: Value| JsonRpcMessage::from_value(value);
+
+        assert!(matches!(
+            parse_one(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })),
+            Ok(JsonRpcMessage::Request(_))
+        ));
+        assert!(matches!(
+            parse_one(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })),
+            Ok(JsonRpcMessage::Notification(_))
+        ));
+        assert!(matches!(
+            parse_one(json!({ "jsonrpc": "2.0", "id": "a", "result": {} })),
+            Ok(JsonRpcMessage::Response(_))
+        ));
+
+        for invalid in [
+            json!(1),
+            json!({ "id": 1, "method": "ping" }),
+            json!({ "jsonrpc": "2.0", "id": null, "method": "ping" }),
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "ping", "params": "x" }),
+            json!({ "jsonrpc": "2.0", "id": 1 }),
+        ] {
+            assert_eq!(parse_one(invalid).unwrap_err().error.code, INVALID_REQUEST);
+        }
+
+        // The ID of an invalid request is echoed when readable
+        let error = parse_one(json!({ "jsonrpc": "1.0", "id": 3, "method": "ping" })).unwrap_err();
+        assert_eq!(error.id, Some(RequestId::Number(3)));
+    }
+
+    #[test]
+    fn test_parse_batches() {
+        assert_eq!(parse(b"{not json").unwrap_err().error.code, PARSE_ERROR);
+        assert_eq!(parse(b"[]").unwrap_err().error.code, INVALID_REQUEST);
+
+        let incoming = parse(
+            br#"[{"jsonrpc":"2.0","id":1,"method":"ping"},{"jsonrpc":"2.0","method":"x"},2]"#,
+        )
+        .unwrap();
+        assert!(incoming.is_batch());
+        let messages = incoming.into_messages();
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], Ok(JsonRpcMessage::Request(_))));
+        assert!(matches!(messages[1], Ok(JsonRpcMessage::Notification(_))));
+        assert!(messages[2].is_err());
+    }
+}
@@ -5,13 +5,16 @@
 //! ## Features
 //! - Protocol types and structures
 //! - MCP Server implementation
+//! - Streamable HTTP server transport
+//! - Request middleware (timing, tracing, custom hooks)
 //! - MCP Client with multiple connection types
 //! - AI Agent with LLM integration
+//! - Sampling: servers request completions from the client's LLM
 //! - Web-based Inspector for debugging
 //! - Authentication (Bearer, OAuth)
 //! - Configuration management
-//! - Session handling
-//! - Logging support
+//! - Session handling, on the client and per connected client on the server
+//! - Logging, locally and to clients (`notifications/message`)
 //! - .env file support for configuration
 
 /// Load environment variables from .env file
@@ -20,24 +23,52 @@ pub fn load_env() {
     dotenv::dotenv().ok();
 }
 
-pub mod protocol;
-pub mod server;
-pub mod client;
-pub mod agent;
-pub mod inspector;
-pub mod error;
 pub mod adapters;
+pub mod agent;
+pub mod client;
+pub mod config;
 pub mod connectors;
+pub mod context;
+pub mod error;
+pub mod inspector;
+pub mod jsonrpc;
+pub mod logging;
+pub mod middleware;
+pub mod protocol;
+pub mod sampling;
+pub mod schema;
+pub mod server;
 pub mod session;
-pub mod config;
+pub mod session_manager;
+pub mod transport;
+pub mod uri_template;
 
 pub use error::{Error, Result};
 
+/// Re-exported so tool argument types can derive `JsonSchema`
+pub use schemars;
+
+pub use mcp_framework_macros::{mcp_server, mcp_tool};
+
+// Lets macro output refer to `::mcp_framework` from inside this crate too
+extern crate self as mcp_framework;
+
+/// Dependencies used by macro-generated code
+#[doc(hidden)]
+pub mod __private {
+    pub use schemars;
+    pub use serde;
+}
+
 pub mod prelude {
-    pub use crate::protocol::*;
-    pub use crate::server::*;
-    pub use crate::client::*;
+    pub use crate::adapters::{AnthropicAdapter, OpenAIAdapter};
     pub use crate::agent::*;
-    pub use crate::adapters::{OpenAIAdapter, AnthropicAdapter};
+    pub use crate::client::*;
     pub use crate::error::{Error, Result};
+    pub use crate::protocol::*;
+    pub use crate::sampling::SamplingHandler;
+    pub use crate::server::*;
+    pub use crate::session_manager::{SessionInfo, SessionState, TransportKind};
+    pub use crate::{mcp_server, mcp_tool};
+    pub use schemars::JsonSchema;
 }
@@ -1,5 +1,37 @@
-/// Logging setup for MCP applications
+//! Logging for MCP applications.
+//!
+//! Local logging is set up with [`init_logging`]. Servers can also send log
+//! messages to their clients (`notifications/message`): either directly
+//! with [`RequestContext::log`], or by adding the layer from
+//! [`McpServer::logging_layer`](crate::server::McpServer::logging_layer) to
+//! the `tracing` subscriber, which forwards the events a tool handler logs
+//! to the client that called it.
+//!
+//! ```ignore
+//! use tracing_subscriber::prelude::*;
+//!
+//! let server = McpServer::with_config(config);
+//! tracing_subscriber::registry()
+//!     .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+//!     .with(server.logging_layer())
+//!     .init();
+//! ```
+//!
+//! Clients only receive messages at or above the level they asked for with
+//! `logging/setLevel`, and nothing before they ask.
+
+use crate::context::RequestContext;
+use crate::protocol::LoggingLevel;
+use dashmap::DashMap;
 use log::LevelFilter;
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::span::Id;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
 
 /// Initialize logging for MCP applications
 pub fn init_logging(level: LogLevel) {
@@ -81,6 +113,135 @@ impl Logger {
     }
 }
 
+/// Requests whose tracing events are forwarded to their client, by the ID
+/// of the span their handler runs in
+pub(crate) type LogSinks = Arc<DashMap<u64, RequestContext>>;
+
+/// Forwarding of a span's events, removed when the request finishes
+pub(crate) struct LogForwarding {
+    sinks: LogSinks,
+    span: u64,
+}
+
+impl LogForwarding {
+    pub(crate) fn register(sinks: &LogSinks, span: Id, ctx: RequestContext) -> Self {
+        let span = span.into_u64();
+        sinks.insert(span, ctx);
+        Self {
+            sinks: sinks.clone(),
+            span,
+        }
+    }
+}
+
+impl Drop for LogForwarding {
+    fn drop(&mut self) {
+        self.sinks.remove(&self.span);
+    }
+}
+
+/// `tracing` layer that sends events logged while handling a tool call to
+/// the client that made it, as `notifications/message`.
+///
+/// The event's target becomes the `logger`, and its fields the `data`.
+/// Needs a subscriber that tracks spans, such as `tracing_subscriber::registry()`,
+/// and which has `mcp_tool` spans enabled.
+pub struct ClientLogLayer {
+    sinks: LogSinks,
+}
+
+impl ClientLogLayer {
+    pub(crate) fn new(sinks: LogSinks) -> Self {
+        Self { sinks }
+    }
+}
+
+impl<S> Layer<S> for ClientLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        let Some(request) = scope
+            .into_iter()
+            .find_map(|span| self.sinks.get(&span.id().into_u64()).map(|ctx| ctx.clone()))
+        else {
+            return;
+        };
+
+        let metadata = event.metadata();
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+        request.log(
+            level_of(metadata.level()),
+            Some(metadata.target()),
+            fields.into_data(),
+        );
+    }
+}
+
+/// The MCP level for a `tracing` level
+fn level_of(level: &Level) -> LoggingLevel {
+    match *level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        _ => LoggingLevel::Debug,
+    }
+}
+
+/// Collects an event's fields as JSON
+#[derive(Default)]
+struct FieldVisitor {
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    /// The message alone if it is the only field, otherwise every field
+    fn into_data(mut self) -> Value {
+        match (self.fields.len(), self.fields.remove("message")) {
+            (1, Some(message)) => message,
+            (_, Some(message)) => {
+                self.fields.insert("message".to_string(), message);
+                Value::Object(self.fields)
+            }
+            (_, None) => Value::Object(self.fields),
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,4 +258,65 @@ mod tests {
     fn test_logger_creation() {
         let _ = Logger;
     }
+
+    #[tokio::test]
+    async fn test_layer_forwards_tool_events() {
+        use crate::context::SessionHandle;
+        use crate::protocol::{JsonRpcNotification, JsonRpcRequest, MCP_VERSION};
+        use crate::server::{McpServer, ServerConfig};
+        use tracing_subscriber::prelude::*;
+
+        let server = McpServer::with_config(ServerConfig::default());
+        let _subscriber = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(server.logging_layer()),
+        );
+        server.tool_with_context(
+            "work",
+            "Logs while working",
+            |_: Value, _ctx: RequestContext| async move {
+                tracing::info!(step = 1, "Working");
+                tracing::debug!("Too detailed");
+                Ok("done")
+            },
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        let send = |method: &str, params: Value| {
+            let request = JsonRpcRequest::new(method, Some(params));
+            let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+            server.handle_request_with_context(request, ctx)
+        };
+        let initialize = json!({
+            "protocolVersion": MCP_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1.0" }
+        });
+        assert!(send("initialize", initialize).await.error.is_none());
+        server.handle_notification(
+            JsonRpcNotification::new("notifications/initialized", None),
+            Some("s1"),
+        );
+
+        // Nothing is forwarded until the client sets a level
+        send("tools/call", json!({ "name": "work" })).await;
+        assert!(rx.try_recv().is_err());
+
+        assert!(send("logging/setLevel", json!({ "level": "info" }))
+            .await
+            .error
+            .is_none());
+        send("tools/call", json!({ "name": "work" })).await;
+        let message = rx.try_recv().unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"]["level"], "info");
+        assert_eq!(
+            message["params"]["data"],
+            json!({ "message": "Working", "step": 1 })
+        );
+        assert!(rx.try_recv().is_err());
+
+        let response = send("logging/setLevel", json!({ "level": "loud" })).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
 }
@@ -0,0 +1,266 @@
+//! Request middleware for [`McpServer`](crate::server::McpServer).
+//!
+//! A middleware sees every JSON-RPC request with its [`RequestContext`] and
+//! decides what happens next: pass it on (possibly rewritten) with
+//! [`Next::run`], answer it directly, or inspect the response on the way
+//! back. Middlewares are added with [`McpServer::layer`](crate::server::McpServer::layer);
+//! the first one added is the outermost.
+//!
+//! ```ignore
+//! struct RequireSession;
+//!
+//! #[async_trait::async_trait]
+//! impl Middleware for RequireSession {
+//!     async fn handle(&self, request: JsonRpcRequest, ctx: RequestContext, next: Next<'_>) -> JsonRpcResponse {
+//!         if ctx.session_id().is_none() {
+//!             let error = JsonRpcError::new(-32600, "A session is required");
+//!             return JsonRpcResponse::failure(Some(request.id), error);
+//!         }
+//!         next.run(request, ctx).await
+//!     }
+//! }
+//!
+//! server.layer(TracingMiddleware).layer(RequireSession);
+//! ```
+
+use crate::context::RequestContext;
+use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
+use crate::server::McpServer;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Wraps the handling of every request
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+        next: Next<'_>,
+    ) -> JsonRpcResponse;
+}
+
+/// The rest of the chain: the middlewares after this one, then the server
+pub struct Next<'a> {
+    server: &'a McpServer,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(server: &'a McpServer, middlewares: &'a [Arc<dyn Middleware>]) -> Self {
+        Self {
+            server,
+            middlewares,
+        }
+    }
+
+    /// Pass the request on and wait for its response
+    pub async fn run(self, request: JsonRpcRequest, ctx: RequestContext) -> JsonRpcResponse {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next::new(self.server, rest);
+                middleware.handle(request, ctx, next).await
+            }
+            None => self.server.process_request(request, ctx).await,
+        }
+    }
+}
+
+/// Receives the method, duration and outcome (`true` on error) of each request
+pub type TimingCallback = Arc<dyn Fn(&str, Duration, bool) + Send + Sync>;
+
+/// Measures how long each request takes. Durations are logged at debug
+/// level, or passed to a callback to feed metrics.
+#[derive(Clone, Default)]
+pub struct TimingMiddleware {
+    callback: Option<TimingCallback>,
+}
+
+impl TimingMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass each measurement to `callback` instead of logging it
+    pub fn with_callback(callback: impl Fn(&str, Duration, bool) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Some(Arc::new(callback)),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for TimingMiddleware {
+    async fn handle(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+        next: Next<'_>,
+    ) -> JsonRpcResponse {
+        let method = request.method.clone();
+        let started = Instant::now();
+        let response = next.run(request, ctx).await;
+        let elapsed = started.elapsed();
+
+        match &self.callback {
+            Some(callback) => callback(&method, elapsed, response.error.is_some()),
+            None => tracing::debug!(
+                method,
+                elapsed_ms = elapsed.as_secs_f64() * 1000.0,
+                "Request finished"
+            ),
+        }
+        response
+    }
+}
+
+/// Runs each request inside an `mcp_request` tracing span carrying its
+/// method, ID and session, so everything a handler logs is attributed to it
+#[derive(Clone, Copy, Default)]
+pub struct TracingMiddleware;
+
+#[async_trait]
+impl Middleware for TracingMiddleware {
+    async fn handle(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+        next: Next<'_>,
+    ) -> JsonRpcResponse {
+        let span = tracing::info_span!(
+            "mcp_request",
+            method = %request.method,
+            id = %request.id,
+            session = ctx.session_id().unwrap_or_default(),
+            error_code = tracing::field::Empty,
+        );
+
+        let response = next.run(request, ctx).instrument(span.clone()).await;
+        if let Some(error) = &response.error {
+            span.record("error_code", error.code);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::JsonRpcError;
+    use crate::server::ServerConfig;
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    /// Records the order middlewares run in
+    struct Record {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn handle(
+            &self,
+            request: JsonRpcRequest,
+            ctx: RequestContext,
+            next: Next<'_>,
+        ) -> JsonRpcResponse {
+            self.log.lock().push(format!("{} in", self.name));
+            let response = next.run(request, ctx).await;
+            self.log.lock().push(format!("{} out", self.name));
+            response
+        }
+    }
+
+    /// Rejects `tools/call` and rewrites the cursor of `tools/list`
+    struct Guard;
+
+    #[async_trait]
+    impl Middleware for Guard {
+        async fn handle(
+            &self,
+            mut request: JsonRpcRequest,
+            ctx: RequestContext,
+            next: Next<'_>,
+        ) -> JsonRpcResponse {
+            match request.method.as_str() {
+                "tools/call" => JsonRpcResponse::failure(
+                    Some(request.id),
+                    JsonRpcError::new(-32001, "Forbidden"),
+                ),
+                _ => {
+                    request.params = Some(json!({ "cursor": "!" }));
+                    next.run(request, ctx).await
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut server = McpServer::with_config(ServerConfig::default());
+        server
+            .layer(Record {
+                name: "outer",
+                log: log.clone(),
+            })
+            .layer(Record {
+                name: "inner",
+                log: log.clone(),
+            });
+
+        let response = server
+            .handle_request(JsonRpcRequest::new("ping", None))
+            .await;
+        assert!(response.error.is_none());
+        assert_eq!(
+            *log.lock(),
+            vec!["outer in", "inner in", "inner out", "outer out"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware_short_circuits_and_rewrites() {
+        let mut server = McpServer::with_config(ServerConfig::default());
+        server.layer(Guard);
+
+        let call = JsonRpcRequest::new("tools/call", Some(json!({ "name": "anything" })));
+        assert_eq!(
+            server.handle_request(call).await.error.unwrap().code,
+            -32001
+        );
+
+        // The rewritten cursor reaches the server and is rejected there
+        let list = JsonRpcRequest::new("tools/list", None);
+        assert_eq!(
+            server.handle_request(list).await.error.unwrap().code,
+            -32602
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timing_middleware() {
+        let timings = Arc::new(Mutex::new(Vec::new()));
+        let mut server = McpServer::with_config(ServerConfig::default());
+        server
+            .layer(TracingMiddleware)
+            .layer(TimingMiddleware::with_callback({
+                let timings = timings.clone();
+                move |method, _elapsed, failed| timings.lock().push((method.to_string(), failed))
+            }));
+
+        server
+            .handle_request(JsonRpcRequest::new("ping", None))
+            .await;
+        server
+            .handle_request(JsonRpcRequest::new("missing", None))
+            .await;
+        assert_eq!(
+            *timings.lock(),
+            vec![("ping".to_string(), false), ("missing".to_string(), true)]
+        );
+    }
+}
@@ -1,89 +1,242 @@
 /// Protocol types and compatibility layer over rmcp SDK.
-
-use serde::{Deserialize, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use uuid::Uuid;
-
-/// Custom serializer for params that outputs empty object instead of null
-fn serialize_params<S>(params: &Option<Value>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    match params {
-        Some(value) => value.serialize(serializer),
-        None => serde_json::json!({}).serialize(serializer),
-    }
-}
 
 // Re-export types from official SDK
 pub use rmcp::model::{
+    EmbeddedResource, ImageContent, Prompt as RmcpPrompt, RawResourceTemplate,
+    Resource as RmcpResource, ResourceTemplate as RmcpResourceTemplate, TextContent,
     Tool as RmcpTool,
-    Prompt as RmcpPrompt,
-    Resource as RmcpResource,
-    TextContent,
-    ImageContent,
-    EmbeddedResource,
 };
 
-/// MCP Protocol version
-pub const MCP_VERSION: &str = "2025-11-05";
+/// Latest MCP protocol version, offered by clients and preferred by servers
+pub const MCP_VERSION: &str = ProtocolVersion::LATEST.as_str();
 
-/// Unique identifier for requests
-pub type RequestId = String;
-
-/// JSON-RPC Request - compatibility type
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcRequest {
-    pub jsonrpc: String,
-    pub id: RequestId,
-    pub method: String,
-    #[serde(serialize_with = "serialize_params")]
-    pub params: Option<Value>,
+/// Published revisions of the MCP specification, ordered oldest to newest
+/// so features can be gated with comparisons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    V2024_11_05,
+    V2025_03_26,
+    V2025_06_18,
+    V2025_11_25,
 }
 
-impl JsonRpcRequest {
-    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
-        Self {
-            jsonrpc: "2.0".to_string(),
-            id: Uuid::new_v4().to_string(),
-            method: method.into(),
-            params,
+impl ProtocolVersion {
+    pub const LATEST: Self = ProtocolVersion::V2025_11_25;
+
+    /// Every supported version, newest first
+    pub const ALL: [Self; 4] = [
+        ProtocolVersion::V2025_11_25,
+        ProtocolVersion::V2025_06_18,
+        ProtocolVersion::V2025_03_26,
+        ProtocolVersion::V2024_11_05,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+            ProtocolVersion::V2025_11_25 => "2025-11-25",
         }
     }
-}
 
-/// JSON-RPC Response - compatibility type
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcResponse {
-    pub jsonrpc: String,
-    pub id: RequestId,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub result: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<JsonRpcError>,
+    /// Parse a version string, `None` if it is not a supported revision
+    pub fn parse(version: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.as_str() == version)
+    }
+
+    /// Version a server answers with: the client's if supported, else the latest
+    pub fn negotiate(requested: &str) -> Self {
+        Self::parse(requested).unwrap_or(Self::LATEST)
+    }
+
+    /// JSON-RPC batches were dropped in 2025-06-18
+    pub fn supports_batching(self) -> bool {
+        self < ProtocolVersion::V2025_06_18
+    }
 }
 
-/// JSON-RPC Error
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcError {
-    pub code: i64,
-    pub message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
+pub use crate::jsonrpc::{
+    JsonRpcError, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, RequestId,
+};
+
+/// Token a client attaches to a request to receive progress notifications
+pub type ProgressToken = RequestId;
+
 pub use rmcp::model::Tool as RmcpToolType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
-    pub description: Option<String>,
+    /// Human-readable name for display, e.g. "Delete File"
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(
+        rename = "inputSchema",
+        alias = "input_schema",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub input_schema: Option<ToolInputSchema>,
+    /// Schema of the tool's `structuredContent`. When set, every successful
+    /// result carries structured content that conforms to it.
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<ToolInputSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+    /// Implementation-specific metadata
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
-/// Tool input schema
+impl Tool {
+    /// A tool without an input schema, title or annotations
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            title: None,
+            description: Some(description.into()),
+            input_schema: None,
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        }
+    }
+
+    pub fn with_input_schema(mut self, input_schema: ToolInputSchema) -> Self {
+        self.input_schema = Some(input_schema);
+        self
+    }
+
+    pub fn with_output_schema(mut self, output_schema: ToolInputSchema) -> Self {
+        self.output_schema = Some(output_schema);
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    pub fn with_meta(mut self, meta: Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Name to show users: the title, then the annotations' title, then the name
+    pub fn display_name(&self) -> &str {
+        self.title
+            .as_deref()
+            .or_else(|| self.annotations.as_ref().and_then(|a| a.title.as_deref()))
+            .unwrap_or(&self.name)
+    }
+
+    /// Whether the tool declares it does not modify its environment
+    pub fn is_read_only(&self) -> bool {
+        self.annotations
+            .as_ref()
+            .is_some_and(ToolAnnotations::is_read_only)
+    }
+
+    /// Whether the tool may perform destructive updates
+    pub fn is_destructive(&self) -> bool {
+        self.annotations
+            .as_ref()
+            .is_none_or(ToolAnnotations::is_destructive)
+    }
+}
+
+/// Hints about a tool's behavior.
+///
+/// They come from the server and are not guaranteed to be accurate; clients
+/// should not rely on them for tools from servers they do not trust.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolAnnotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The tool does not modify its environment. Defaults to `false`.
+    #[serde(rename = "readOnlyHint", skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    /// The tool may delete or overwrite data, rather than only add to it.
+    /// Only meaningful when not read-only. Defaults to `true`.
+    #[serde(rename = "destructiveHint", skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    /// Repeating a call with the same arguments has no further effect.
+    /// Only meaningful when not read-only. Defaults to `false`.
+    #[serde(rename = "idempotentHint", skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    /// The tool interacts with external entities, e.g. the web, rather than
+    /// a closed domain such as a memory store. Defaults to `true`.
+    #[serde(rename = "openWorldHint", skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl ToolAnnotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only_hint = Some(read_only);
+        self
+    }
+
+    pub fn destructive(mut self, destructive: bool) -> Self {
+        self.destructive_hint = Some(destructive);
+        self
+    }
+
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent_hint = Some(idempotent);
+        self
+    }
+
+    pub fn open_world(mut self, open_world: bool) -> Self {
+        self.open_world_hint = Some(open_world);
+        self
+    }
+
+    /// `readOnlyHint`, with its default applied
+    pub fn is_read_only(&self) -> bool {
+        self.read_only_hint.unwrap_or(false)
+    }
+
+    /// `destructiveHint` with its default applied; read-only tools are never destructive
+    pub fn is_destructive(&self) -> bool {
+        !self.is_read_only() && self.destructive_hint.unwrap_or(true)
+    }
+
+    /// `idempotentHint`, with its default applied
+    pub fn is_idempotent(&self) -> bool {
+        self.is_read_only() || self.idempotent_hint.unwrap_or(false)
+    }
+
+    /// `openWorldHint`, with its default applied
+    pub fn is_open_world(&self) -> bool {
+        self.open_world_hint.unwrap_or(true)
+    }
+}
+
+/// Tool input schema, also used for output schemas
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolInputSchema {
     #[serde(rename = "type")]
@@ -91,11 +244,150 @@ pub struct ToolInputSchema {
     pub properties: HashMap<String, Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<Vec<String>>,
+    /// Subschemas that `$ref`s point into, e.g. `#/$defs/Node`
+    #[serde(rename = "$defs", skip_serializing_if = "Option::is_none")]
+    pub defs: Option<HashMap<String, Value>>,
+}
+
+impl ToolInputSchema {
+    /// Generate the schema for an arguments or output type.
+    ///
+    /// Nested types are inlined, except recursive ones, which stay in
+    /// `$defs` and are referenced from where they are used.
+    pub fn from_type<T: schemars::JsonSchema>() -> Self {
+        let generator = schemars::generate::SchemaSettings::draft2020_12()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator();
+        let schema = generator.into_root_schema_for::<T>();
+
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let required = schema
+            .get("required")
+            .and_then(|required| serde_json::from_value::<Vec<String>>(required.clone()).ok())
+            .filter(|required| !required.is_empty());
+        let defs = schema
+            .get("$defs")
+            .and_then(Value::as_object)
+            .filter(|defs| !defs.is_empty())
+            .map(|defs| defs.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
+
+        Self {
+            schema_type: "object".to_string(),
+            properties,
+            required,
+            defs,
+        }
+    }
 }
 
 /// Resource definition - compatibility wrapper
 pub type Resource = RmcpResource;
 
+/// Resource template definition, advertised by `resources/templates/list`
+pub type ResourceTemplate = RmcpResourceTemplate;
+
+/// Contents of a resource, as returned by `resources/read`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResourceContents {
+    /// Text contents
+    Text {
+        uri: String,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "mimeType")]
+        mime_type: Option<String>,
+        text: String,
+    },
+    /// Binary contents, base64-encoded
+    Blob {
+        uri: String,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "mimeType")]
+        mime_type: Option<String>,
+        blob: String,
+    },
+}
+
+impl ResourceContents {
+    /// Text contents without a MIME type
+    pub fn text(uri: impl Into<String>, text: impl Into<String>) -> Self {
+        ResourceContents::Text {
+            uri: uri.into(),
+            mime_type: None,
+            text: text.into(),
+        }
+    }
+
+    /// Binary contents from an already base64-encoded string
+    pub fn blob(
+        uri: impl Into<String>,
+        blob: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> Self {
+        ResourceContents::Blob {
+            uri: uri.into(),
+            mime_type: Some(mime_type.into()),
+            blob: blob.into(),
+        }
+    }
+
+    /// Binary contents from raw bytes
+    pub fn from_bytes(uri: impl Into<String>, bytes: &[u8], mime_type: impl Into<String>) -> Self {
+        use base64::Engine;
+        Self::blob(
+            uri,
+            base64::engine::general_purpose::STANDARD.encode(bytes),
+            mime_type,
+        )
+    }
+
+    /// Set the MIME type
+    pub fn with_mime_type(mut self, mime: impl Into<String>) -> Self {
+        match &mut self {
+            ResourceContents::Text { mime_type, .. } | ResourceContents::Blob { mime_type, .. } => {
+                *mime_type = Some(mime.into());
+            }
+        }
+        self
+    }
+
+    /// URI these contents belong to
+    pub fn uri(&self) -> &str {
+        match self {
+            ResourceContents::Text { uri, .. } | ResourceContents::Blob { uri, .. } => uri,
+        }
+    }
+
+    /// Text, if these are text contents
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            ResourceContents::Text { text, .. } => Some(text),
+            ResourceContents::Blob { .. } => None,
+        }
+    }
+}
+
+/// One page of a list result. `next_cursor` is set when more items follow;
+/// pass it back as the `cursor` param to fetch them.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Result of `resources/read`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceResult {
+    pub contents: Vec<ResourceContents>,
+}
+
 /// Prompt definition - compatibility wrapper
 pub type Prompt = RmcpPrompt;
 
@@ -108,6 +400,72 @@ pub struct PromptArgument {
     pub required: Option<bool>,
 }
 
+/// Message in a rendered prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: Role,
+    pub content: PromptContent,
+}
+
+impl PromptMessage {
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: PromptContent::Text { text: text.into() },
+        }
+    }
+
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: PromptContent::Text { text: text.into() },
+        }
+    }
+
+    /// Base64-encoded image
+    pub fn image(role: Role, data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: PromptContent::Image {
+                data: data.into(),
+                mime_type: mime_type.into(),
+            },
+        }
+    }
+
+    /// Resource embedded in the prompt
+    pub fn resource(role: Role, resource: ResourceContents) -> Self {
+        Self {
+            role,
+            content: PromptContent::Resource { resource },
+        }
+    }
+}
+
+/// Content of a prompt message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PromptContent {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image")]
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    #[serde(rename = "resource")]
+    Resource { resource: ResourceContents },
+}
+
+/// Result of `prompts/get`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub messages: Vec<PromptMessage>,
+}
+
 /// Tool call
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
@@ -122,22 +480,241 @@ pub struct ToolResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     pub content: Vec<ResultContent>,
+    /// Result as a JSON object, conforming to the tool's output schema
+    #[serde(skip_serializing_if = "Option::is_none", rename = "structuredContent")]
+    pub structured_content: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "isError")]
     pub is_error: Option<bool>,
 }
 
+impl ToolResult {
+    /// Deserialize the structured content. Results from servers that only
+    /// return text fall back to parsing the first text block as JSON.
+    pub fn structured<T: serde::de::DeserializeOwned>(&self) -> crate::Result<T> {
+        if let Some(structured) = &self.structured_content {
+            return Ok(serde_json::from_value(structured.clone())?);
+        }
+        let text = self.content.iter().find_map(|content| match content {
+            ResultContent::Text { text, .. } => Some(text),
+            _ => None,
+        });
+        match text {
+            Some(text) => Ok(serde_json::from_str(text)?),
+            None => Err(crate::Error::InvalidRequest(
+                "Tool result has no structured content".to_string(),
+            )),
+        }
+    }
+}
+
+/// Params of a `notifications/progress` message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Token from the `_meta.progressToken` of the request being reported on
+    #[serde(rename = "progressToken")]
+    pub progress_token: ProgressToken,
+    /// Work done so far; increases with every notification
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Hints on how a content block is meant to be used
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Annotations {
+    /// Who the content is intended for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// Importance from 0.0 (optional) to 1.0 (required)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+    /// ISO 8601 timestamp of the last modification
+    #[serde(skip_serializing_if = "Option::is_none", rename = "lastModified")]
+    pub last_modified: Option<String>,
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn audience(mut self, audience: Vec<Role>) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
+    pub fn priority(mut self, priority: f64) -> Self {
+        self.priority = Some(priority.clamp(0.0, 1.0));
+        self
+    }
+
+    pub fn last_modified(mut self, last_modified: impl Into<String>) -> Self {
+        self.last_modified = Some(last_modified.into());
+        self
+    }
+
+    /// Whether the content is meant for `role`; no audience means everyone
+    pub fn is_for(&self, role: Role) -> bool {
+        self.audience
+            .as_ref()
+            .is_none_or(|audience| audience.contains(&role))
+    }
+}
+
 /// Result content
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ResultContent {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Base64-encoded image
     #[serde(rename = "image")]
     Image {
         data: String,
         #[serde(rename = "mimeType")]
         mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Base64-encoded audio
+    #[serde(rename = "audio")]
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
+    /// Link to a resource the client can read with `resources/read`
+    #[serde(rename = "resource_link")]
+    ResourceLink {
+        uri: String,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "mimeType")]
+        mime_type: Option<String>,
+        /// Size of the raw contents in bytes
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Resource contents included inline
+    #[serde(rename = "resource")]
+    EmbeddedResource {
+        resource: ResourceContents,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+}
+
+impl ResultContent {
+    pub fn text(text: impl Into<String>) -> Self {
+        ResultContent::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+
+    pub fn image(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        ResultContent::Image {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        }
+    }
+
+    pub fn audio(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        ResultContent::Audio {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+        }
+    }
+
+    pub fn resource_link(uri: impl Into<String>, name: impl Into<String>) -> Self {
+        ResultContent::ResourceLink {
+            uri: uri.into(),
+            name: name.into(),
+            title: None,
+            description: None,
+            mime_type: None,
+            size: None,
+            annotations: None,
+        }
+    }
+
+    pub fn embedded(resource: ResourceContents) -> Self {
+        ResultContent::EmbeddedResource {
+            resource,
+            annotations: None,
+        }
+    }
+
+    pub fn with_annotations(mut self, value: Annotations) -> Self {
+        match &mut self {
+            ResultContent::Text { annotations, .. }
+            | ResultContent::Image { annotations, .. }
+            | ResultContent::Audio { annotations, .. }
+            | ResultContent::ResourceLink { annotations, .. }
+            | ResultContent::EmbeddedResource { annotations, .. } => *annotations = Some(value),
+        }
+        self
+    }
+
+    pub fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            ResultContent::Text { annotations, .. }
+            | ResultContent::Image { annotations, .. }
+            | ResultContent::Audio { annotations, .. }
+            | ResultContent::ResourceLink { annotations, .. }
+            | ResultContent::EmbeddedResource { annotations, .. } => annotations.as_ref(),
+        }
+    }
+
+    /// Text to hand an LLM for this block. Media the model can't take
+    /// inline is described by a placeholder, and blocks whose audience
+    /// leaves out the assistant are dropped.
+    pub fn to_llm_text(&self) -> Option<String> {
+        if self
+            .annotations()
+            .is_some_and(|annotations| !annotations.is_for(Role::Assistant))
+        {
+            return None;
+        }
+        let text = match self {
+            ResultContent::Text { text, .. } => text.clone(),
+            ResultContent::Image { mime_type, .. } => format!("[Image ({})]", mime_type),
+            ResultContent::Audio { mime_type, .. } => format!("[Audio ({})]", mime_type),
+            ResultContent::ResourceLink {
+                uri,
+                name,
+                description,
+                ..
+            } => match description {
+                Some(description) => format!("[Resource {} <{}>: {}]", name, uri, description),
+                None => format!("[Resource {} <{}>]", name, uri),
+            },
+            ResultContent::EmbeddedResource { resource, .. } => match resource {
+                ResourceContents::Text { text, .. } => text.clone(),
+                ResourceContents::Blob { uri, mime_type, .. } => format!(
+                    "[Binary resource {} ({})]",
+                    uri,
+                    mime_type.as_deref().unwrap_or("application/octet-stream")
+                ),
+            },
+        };
+        Some(text)
+    }
 }
 
 /// Server capabilities
@@ -149,6 +726,37 @@ pub struct ServerCapabilities {
     pub resources: Option<ResourcesCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompts: Option<PromptsCapability>,
+    /// Present when the server accepts `logging/setLevel` and sends
+    /// `notifications/message`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<Value>,
+    /// Present when the server answers `completion/complete`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<Value>,
+}
+
+impl ServerCapabilities {
+    pub fn with_tools(mut self, list_changed: bool) -> Self {
+        self.tools = Some(ToolsCapability {
+            list_changed: Some(list_changed),
+        });
+        self
+    }
+
+    pub fn with_resources(mut self, subscribe: bool, list_changed: bool) -> Self {
+        self.resources = Some(ResourcesCapability {
+            subscribe: Some(subscribe),
+            list_changed: Some(list_changed),
+        });
+        self
+    }
+
+    pub fn with_prompts(mut self, list_changed: bool) -> Self {
+        self.prompts = Some(PromptsCapability {
+            list_changed: Some(list_changed),
+        });
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,6 +779,358 @@ pub struct PromptsCapability {
     pub list_changed: Option<bool>,
 }
 
+/// Capabilities a client declares at initialization. Each present entry
+/// means the feature is supported; its value holds feature options.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ClientCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<Value>,
+}
+
+/// Name and version of a client or server implementation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Implementation {
+    pub name: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Params of the `initialize` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitializeParams {
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: ClientCapabilities,
+    #[serde(rename = "clientInfo")]
+    pub client_info: Implementation,
+}
+
+/// Result of the `initialize` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitializeResult {
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: String,
+    pub capabilities: ServerCapabilities,
+    #[serde(rename = "serverInfo")]
+    pub server_info: Implementation,
+}
+
+/// Content of a sampling message, which is limited to text, images and
+/// audio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SamplingContent {
+    #[serde(rename = "text")]
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Base64-encoded image
+    #[serde(rename = "image")]
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Base64-encoded audio
+    #[serde(rename = "audio")]
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+}
+
+impl SamplingContent {
+    pub fn text(text: impl Into<String>) -> Self {
+        SamplingContent::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+}
+
+/// A message in a sampling request or result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: Role,
+    pub content: SamplingContent,
+}
+
+impl SamplingMessage {
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: SamplingContent::text(text),
+        }
+    }
+}
+
+/// Params of `sampling/createMessage`: a completion the server asks the
+/// client's LLM for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageParams {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u32,
+    #[serde(rename = "systemPrompt", skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(rename = "stopSequences", skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// Hints and priorities for the client's model choice
+    #[serde(rename = "modelPreferences", skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<Value>,
+    /// `"none"`, `"thisServer"` or `"allServers"`
+    #[serde(rename = "includeContext", skip_serializing_if = "Option::is_none")]
+    pub include_context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+impl CreateMessageParams {
+    pub fn new(messages: Vec<SamplingMessage>, max_tokens: u32) -> Self {
+        Self {
+            messages,
+            max_tokens,
+            system_prompt: None,
+            temperature: None,
+            stop_sequences: None,
+            model_preferences: None,
+            include_context: None,
+            metadata: None,
+        }
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+}
+
+/// Result of `sampling/createMessage`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageResult {
+    pub role: Role,
+    pub content: SamplingContent,
+    /// Model that generated the message
+    pub model: String,
+    /// e.g. `"endTurn"`, `"stopSequence"` or `"maxTokens"`
+    #[serde(rename = "stopReason", skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// Params of `elicitation/create`: structured input the server asks the
+/// user for.
+///
+/// `requested_schema` is restricted to a flat object whose properties are
+/// strings, numbers, integers, booleans or enums of strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElicitRequestParams {
+    /// Shown to the user, e.g. "Which branch should be deployed?"
+    pub message: String,
+    #[serde(rename = "requestedSchema")]
+    pub requested_schema: Value,
+}
+
+impl ElicitRequestParams {
+    pub fn new(message: impl Into<String>, requested_schema: Value) -> Self {
+        Self {
+            message: message.into(),
+            requested_schema,
+        }
+    }
+}
+
+/// How the user answered an elicitation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitAction {
+    /// Submitted the requested input
+    Accept,
+    /// Explicitly refused to provide it
+    Decline,
+    /// Dismissed the request without choosing
+    Cancel,
+}
+
+/// Result of `elicitation/create`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElicitResult {
+    pub action: ElicitAction,
+    /// The submitted input, present only when accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Value>,
+}
+
+impl ElicitResult {
+    pub fn accept(content: Value) -> Self {
+        Self {
+            action: ElicitAction::Accept,
+            content: Some(content),
+        }
+    }
+
+    pub fn decline() -> Self {
+        Self {
+            action: ElicitAction::Decline,
+            content: None,
+        }
+    }
+
+    pub fn cancel() -> Self {
+        Self {
+            action: ElicitAction::Cancel,
+            content: None,
+        }
+    }
+}
+
+/// Severity of a log message sent to a client, from least to most severe
+/// (the syslog levels of RFC 5424)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Params of `notifications/message`: a log message from the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingMessage {
+    pub level: LoggingLevel,
+    /// Name of the component that logged it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    /// The message: a string or any JSON value
+    pub data: Value,
+}
+
+/// What a `completion/complete` request completes an argument of
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompletionReference {
+    /// An argument of a prompt
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    /// A variable of a resource template, identified by its URI template
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+impl CompletionReference {
+    pub fn prompt(name: impl Into<String>) -> Self {
+        CompletionReference::Prompt { name: name.into() }
+    }
+
+    pub fn resource(uri_template: impl Into<String>) -> Self {
+        CompletionReference::Resource {
+            uri: uri_template.into(),
+        }
+    }
+}
+
+/// The argument being completed and what the user has typed so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionArgument {
+    pub name: String,
+    pub value: String,
+}
+
+/// Arguments the user has already filled in
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompletionContext {
+    #[serde(default)]
+    pub arguments: HashMap<String, String>,
+}
+
+/// Params of `completion/complete`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteParams {
+    #[serde(rename = "ref")]
+    pub reference: CompletionReference,
+    pub argument: CompletionArgument,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<CompletionContext>,
+}
+
+impl CompleteParams {
+    pub fn new(
+        reference: CompletionReference,
+        argument: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        Self {
+            reference,
+            argument: CompletionArgument {
+                name: argument.into(),
+                value: value.into(),
+            },
+            context: None,
+        }
+    }
+
+    /// Pass the arguments already filled in, for completions that depend on them
+    pub fn with_context(mut self, arguments: HashMap<String, String>) -> Self {
+        self.context = Some(CompletionContext { arguments });
+        self
+    }
+}
+
+/// Suggestions for an argument value, best first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    /// At most [`Completion::MAX_VALUES`] values
+    pub values: Vec<String>,
+    /// Number of suggestions available, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    #[serde(rename = "hasMore", skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+}
+
+impl Completion {
+    /// Most values a single completion result may carry
+    pub const MAX_VALUES: usize = 100;
+
+    /// A completion from every suggestion, truncated to [`MAX_VALUES`](Self::MAX_VALUES)
+    pub fn from_values(mut values: Vec<String>) -> Self {
+        let total = values.len();
+        values.truncate(Self::MAX_VALUES);
+        Self {
+            has_more: Some(total > values.len()),
+            total: Some(total),
+            values,
+        }
+    }
+}
+
+/// Result of `completion/complete`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResult {
+    pub completion: Completion,
+}
+
 /// Message role
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum Role {
@@ -242,6 +1202,45 @@ impl Message {
     }
 }
 
+impl From<PromptMessage> for Message {
+    fn from(message: PromptMessage) -> Self {
+        let block = match message.content {
+            PromptContent::Text { text } => ContentBlock::Text { text },
+            PromptContent::Image { data, mime_type } => ContentBlock::Image {
+                source: ImageSource::Base64 {
+                    data,
+                    media_type: mime_type,
+                },
+            },
+            PromptContent::Resource { resource } => match resource {
+                ResourceContents::Text { text, .. } => ContentBlock::Text { text },
+                ResourceContents::Blob {
+                    mime_type: Some(mime_type),
+                    blob,
+                    ..
+                } if mime_type.starts_with("image/") => ContentBlock::Image {
+                    source: ImageSource::Base64 {
+                        data: blob,
+                        media_type: mime_type,
+                    },
+                },
+                ResourceContents::Blob { uri, mime_type, .. } => ContentBlock::Text {
+                    text: format!(
+                        "[Binary resource {} ({})]",
+                        uri,
+                        mime_type.as_deref().unwrap_or("application/octet-stream")
+                    ),
+                },
+            },
+        };
+
+        Self {
+            role: message.role,
+            content: vec![block],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,6 +1268,251 @@ mod tests {
         assert!(json.contains("\"params\":{}"), "Expected params: {{}}, got: {}", json);
     }
 
+    #[test]
+    fn test_resource_contents_serialization() {
+        let text = ResourceContents::text("file:///a.txt", "hello").with_mime_type("text/plain");
+        let json = serde_json::to_value(&text).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "uri": "file:///a.txt", "mimeType": "text/plain", "text": "hello" })
+        );
+
+        let blob: ResourceContents = serde_json::from_value(serde_json::json!({
+            "uri": "file:///a.png", "mimeType": "image/png", "blob": "aGk="
+        }))
+        .unwrap();
+        assert_eq!(
+            blob,
+            ResourceContents::from_bytes("file:///a.png", b"hi", "image/png")
+        );
+        assert!(blob.as_text().is_none());
+    }
+
+    #[test]
+    fn test_prompt_message_to_agent_message() {
+        let result: GetPromptResult = serde_json::from_value(serde_json::json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "Review this" } },
+                { "role": "user", "content": { "type": "image", "data": "aGk=", "mimeType": "image/png" } },
+                { "role": "assistant", "content": { "type": "resource", "resource": { "uri": "file:///a.rs", "text": "fn main() {}" } } }
+            ]
+        }))
+        .unwrap();
+        assert!(result.description.is_none());
+
+        let messages: Vec<Message> = result.messages.into_iter().map(Message::from).collect();
+        assert!(
+            matches!(&messages[0].content[0], ContentBlock::Text { text } if text == "Review this")
+        );
+        assert!(
+            matches!(&messages[1].content[0], ContentBlock::Image { source: ImageSource::Base64 { media_type, .. } } if media_type == "image/png")
+        );
+        assert_eq!(messages[2].role, Role::Assistant);
+        assert!(
+            matches!(&messages[2].content[0], ContentBlock::Text { text } if text == "fn main() {}")
+        );
+    }
+
+    #[test]
+    fn test_tool_input_schema_from_type() {
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct Args {
+            /// Label to draw
+            label: String,
+            origin: Point,
+            scale: Option<f64>,
+        }
+
+        let schema = ToolInputSchema::from_type::<Args>();
+        assert_eq!(schema.schema_type, "object");
+        assert_eq!(
+            schema.required,
+            Some(vec!["label".to_string(), "origin".to_string()])
+        );
+        assert_eq!(schema.properties["label"]["description"], "Label to draw");
+        assert_eq!(
+            schema.properties["origin"]["properties"]["x"]["type"],
+            "number"
+        );
+        assert!(schema.defs.is_none());
+    }
+
+    #[test]
+    fn test_tool_input_schema_keeps_recursive_defs() {
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct Node {
+            name: String,
+            children: Vec<Node>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct Args {
+            root: Node,
+        }
+
+        let schema = serde_json::to_value(ToolInputSchema::from_type::<Args>()).unwrap();
+        assert!(schema["$defs"]["Node"].is_object());
+
+        let tree = serde_json::json!({ "root": { "name": "a", "children": [{ "name": "b", "children": [] }] } });
+        assert!(crate::schema::validate(&schema, &tree).is_empty());
+        let tree = serde_json::json!({ "root": { "name": "a", "children": [{ "name": 1, "children": [] }] } });
+        let violations = crate::schema::validate(&schema, &tree);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].path, "/root/children/0/name");
+    }
+
+    #[test]
+    fn test_tool_annotations_serialization() {
+        let tool = Tool::new("delete_file", "Delete a file")
+            .with_title("Delete File")
+            .with_annotations(ToolAnnotations::new().destructive(true).idempotent(true))
+            .with_meta(serde_json::json!({ "vendor/owner": "files" }));
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(json["title"], "Delete File");
+        assert_eq!(
+            json["annotations"],
+            serde_json::json!({ "destructiveHint": true, "idempotentHint": true })
+        );
+        assert_eq!(json["_meta"]["vendor/owner"], "files");
+
+        let listed: Tool = serde_json::from_value(serde_json::json!({
+            "name": "search",
+            "description": "Search the web",
+            "inputSchema": { "type": "object", "properties": { "query": { "type": "string" } }, "required": ["query"] },
+            "annotations": { "title": "Web Search", "readOnlyHint": true }
+        }))
+        .unwrap();
+        assert_eq!(
+            listed.input_schema.as_ref().unwrap().required,
+            Some(vec!["query".to_string()])
+        );
+        let json = serde_json::to_value(&listed).unwrap();
+        assert!(json.get("input_schema").is_none());
+        assert_eq!(json["inputSchema"]["type"], "object");
+        assert_eq!(listed.display_name(), "Web Search");
+        assert!(listed.is_read_only());
+        assert!(!listed.is_destructive());
+        assert!(listed.annotations.unwrap().is_open_world());
+
+        // Without annotations a tool is assumed to be destructive
+        let bare: Tool =
+            serde_json::from_value(serde_json::json!({ "name": "run", "description": null }))
+                .unwrap();
+        assert_eq!(bare.display_name(), "run");
+        assert!(bare.is_destructive());
+    }
+
+    #[test]
+    fn test_tool_result_structured() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Weather {
+            temperature: f64,
+        }
+
+        let result: ToolResult = serde_json::from_value(serde_json::json!({
+            "content": [{ "type": "text", "text": "{\"temperature\": 22.5}" }],
+            "structuredContent": { "temperature": 22.5 }
+        }))
+        .unwrap();
+        assert_eq!(
+            result.structured::<Weather>().unwrap(),
+            Weather { temperature: 22.5 }
+        );
+
+        let text_only = ToolResult {
+            structured_content: None,
+            ..result
+        };
+        assert_eq!(
+            text_only.structured::<Weather>().unwrap(),
+            Weather { temperature: 22.5 }
+        );
+        assert!(text_only.structured::<Vec<String>>().is_err());
+    }
+
+    #[test]
+    fn test_result_content_types() {
+        let audio = ResultContent::audio("UklGRg==", "audio/wav").with_annotations(
+            Annotations::new()
+                .audience(vec![Role::User])
+                .priority(0.8)
+                .last_modified("2025-01-12T15:00:58Z"),
+        );
+        assert_eq!(
+            serde_json::to_value(&audio).unwrap(),
+            serde_json::json!({
+                "type": "audio",
+                "data": "UklGRg==",
+                "mimeType": "audio/wav",
+                "annotations": {
+                    "audience": ["user"],
+                    "priority": 0.8,
+                    "lastModified": "2025-01-12T15:00:58Z"
+                }
+            })
+        );
+        // Meant for the user only, so it never reaches the model
+        assert_eq!(audio.to_llm_text(), None);
+
+        let text = ResultContent::text("Done").with_annotations(Annotations::new().priority(0.2));
+        assert_eq!(
+            serde_json::to_value(&text).unwrap(),
+            serde_json::json!({ "type": "text", "text": "Done", "annotations": { "priority": 0.2 } })
+        );
+        assert_eq!(text.to_llm_text().as_deref(), Some("Done"));
+
+        let link: ResultContent = serde_json::from_value(serde_json::json!({
+            "type": "resource_link",
+            "uri": "file:///main.rs",
+            "name": "main.rs",
+            "mimeType": "text/x-rust"
+        }))
+        .unwrap();
+        assert!(
+            matches!(&link, ResultContent::ResourceLink { mime_type: Some(m), annotations: None, .. } if m == "text/x-rust")
+        );
+        assert_eq!(
+            link.to_llm_text().unwrap(),
+            "[Resource main.rs <file:///main.rs>]"
+        );
+
+        let embedded = ResultContent::embedded(ResourceContents::text("file:///a.txt", "hello"));
+        let json = serde_json::to_value(&embedded).unwrap();
+        assert_eq!(json["type"], "resource");
+        assert_eq!(json["resource"]["text"], "hello");
+        assert!(json.get("annotations").is_none());
+        assert_eq!(embedded.to_llm_text().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_sampling_content_types() {
+        let message: SamplingMessage = serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": { "type": "audio", "data": "UklGRg==", "mimeType": "audio/wav" }
+        }))
+        .unwrap();
+        assert!(
+            matches!(message.content, SamplingContent::Audio { ref mime_type, .. } if mime_type == "audio/wav")
+        );
+
+        // Resources have no place in a sampling message
+        let link = serde_json::json!({
+            "role": "user",
+            "content": { "type": "resource_link", "uri": "file:///a.rs", "name": "a.rs" }
+        });
+        assert!(serde_json::from_value::<SamplingMessage>(link).is_err());
+    }
+
     #[test]
     fn test_message_creation() {
         let msg = Message::user("Hello");
@@ -0,0 +1,222 @@
+//! Client-side answers to `sampling/createMessage`.
+//!
+//! A server asks for a completion from the client's LLM; the client decides
+//! whether to allow it (typically by asking its user) and runs it on one of
+//! its own [`LLMProvider`]s. Enable it on a client with
+//! [`McpClient::enable_sampling`](crate::client::McpClient::enable_sampling):
+//!
+//! ```ignore
+//! let llm = AnthropicAdapter::from_env("claude-sonnet-4-5-20250929".to_string())?;
+//! let sampling = SamplingHandler::new(Arc::new(llm))
+//!     .with_approval(|params| params.max_tokens <= 1000);
+//! client.enable_sampling(sampling);
+//! ```
+//!
+//! Servers send the request from a handler with
+//! [`RequestContext::create_message`](crate::context::RequestContext::create_message).
+
+use crate::agent::{CallOptions, LLMProvider, StopReason};
+use crate::error::{Error, Result};
+use crate::protocol::{
+    ContentBlock, CreateMessageParams, CreateMessageResult, ImageSource, Message, Role,
+    SamplingContent, SamplingMessage,
+};
+use std::sync::Arc;
+
+/// Decides whether a sampling request may run
+pub type ApprovalCallback = Arc<dyn Fn(&CreateMessageParams) -> bool + Send + Sync>;
+
+/// Runs sampling requests from servers on an LLM provider
+#[derive(Clone)]
+pub struct SamplingHandler {
+    provider: Arc<dyn LLMProvider>,
+    approve: Option<ApprovalCallback>,
+}
+
+impl SamplingHandler {
+    /// Run every request on `provider` without asking
+    pub fn new(provider: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            provider,
+            approve: None,
+        }
+    }
+
+    /// Ask `approve` before running each request; requests it declines are
+    /// answered with [`Error::Rejected`], code -1
+    pub fn with_approval(
+        mut self,
+        approve: impl Fn(&CreateMessageParams) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.approve = Some(Arc::new(approve));
+        self
+    }
+
+    /// Answer a `sampling/createMessage` request.
+    ///
+    /// The system prompt, `maxTokens`, `temperature` and `stopSequences` go
+    /// to the provider through [`LLMProvider::call_with_options`]; a provider
+    /// that cannot honour them fails the request.
+    pub async fn create_message(&self, params: CreateMessageParams) -> Result<CreateMessageResult> {
+        if let Some(approve) = &self.approve
+            && !approve(&params)
+        {
+            return Err(Error::Rejected(
+                "User rejected sampling request".to_string(),
+            ));
+        }
+
+        let options = CallOptions {
+            system_prompt: params.system_prompt,
+            max_tokens: Some(params.max_tokens),
+            temperature: params.temperature,
+            stop_sequences: params.stop_sequences,
+        };
+        let messages = params.messages.into_iter().map(to_message).collect();
+
+        let response = self
+            .provider
+            .call_with_options(messages, Vec::new(), options)
+            .await?;
+        let text = response
+            .content
+            .into_iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let stop_reason = match response.stop_reason {
+            StopReason::EndTurn => "endTurn",
+            StopReason::MaxTokens => "maxTokens",
+            StopReason::ToolUse => "toolUse",
+        };
+        Ok(CreateMessageResult {
+            role: Role::Assistant,
+            content: SamplingContent::text(text),
+            model: self.provider.model().to_string(),
+            stop_reason: Some(stop_reason.to_string()),
+        })
+    }
+}
+
+/// Convert a sampling message to the form LLM providers take
+fn to_message(message: SamplingMessage) -> Message {
+    let content = match message.content {
+        SamplingContent::Text { text, .. } => ContentBlock::Text { text },
+        SamplingContent::Image {
+            data, mime_type, ..
+        } => ContentBlock::Image {
+            source: ImageSource::Base64 {
+                data,
+                media_type: mime_type,
+            },
+        },
+        // Providers take no audio; the model is told it was there
+        SamplingContent::Audio { mime_type, .. } => ContentBlock::Text {
+            text: format!("[Audio ({})]", mime_type),
+        },
+    };
+    Message {
+        role: message.role,
+        content: vec![content],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::LLMResponse;
+    use crate::protocol::Tool;
+    use parking_lot::Mutex;
+
+    /// Records the messages and options it is called with and answers "ok"
+    #[derive(Default)]
+    struct RecordingProvider {
+        calls: Mutex<Vec<(Vec<Message>, CallOptions)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for RecordingProvider {
+        async fn call(&self, messages: Vec<Message>, tools: Vec<Tool>) -> Result<LLMResponse> {
+            self.call_with_options(messages, tools, CallOptions::default())
+                .await
+        }
+
+        async fn call_with_options(
+            &self,
+            messages: Vec<Message>,
+            _tools: Vec<Tool>,
+            options: CallOptions,
+        ) -> Result<LLMResponse> {
+            self.calls.lock().push((messages, options));
+            Ok(LLMResponse {
+                content: vec![ContentBlock::Text {
+                    text: "ok".to_string(),
+                }],
+                stop_reason: StopReason::MaxTokens,
+            })
+        }
+
+        fn model(&self) -> &str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_message() {
+        let provider = Arc::new(RecordingProvider::default());
+        let handler = SamplingHandler::new(provider.clone());
+
+        let params = CreateMessageParams::new(vec![SamplingMessage::user("Summarize this")], 100)
+            .with_system_prompt("Be brief");
+        let result = handler.create_message(params).await.unwrap();
+
+        assert_eq!(result.role, Role::Assistant);
+        assert!(matches!(result.content, SamplingContent::Text { ref text, .. } if text == "ok"));
+        assert_eq!(result.model, "recording");
+        assert_eq!(result.stop_reason.as_deref(), Some("maxTokens"));
+
+        let calls = provider.calls.lock();
+        let (messages, options) = &calls[0];
+        assert_eq!(messages.len(), 1);
+        assert_eq!(options.system_prompt.as_deref(), Some("Be brief"));
+        assert_eq!(options.max_tokens, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_provider_without_options_refuses() {
+        struct PlainProvider;
+
+        #[async_trait::async_trait]
+        impl LLMProvider for PlainProvider {
+            async fn call(
+                &self,
+                _messages: Vec<Message>,
+                _tools: Vec<Tool>,
+            ) -> Result<LLMResponse> {
+                unreachable!("settings it cannot honour are refused first")
+            }
+        }
+
+        let handler = SamplingHandler::new(Arc::new(PlainProvider));
+        let params = CreateMessageParams::new(vec![SamplingMessage::user("Hi")], 100);
+        let refused = handler.create_message(params).await.unwrap_err();
+        assert!(matches!(refused, Error::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_request_is_not_run() {
+        let provider = Arc::new(RecordingProvider::default());
+        let handler =
+            SamplingHandler::new(provider.clone()).with_approval(|params| params.max_tokens <= 10);
+
+        let params = CreateMessageParams::new(vec![SamplingMessage::user("Write an essay")], 5000);
+        let rejected = handler.create_message(params).await.unwrap_err();
+        assert!(matches!(rejected, Error::Rejected(_)));
+        assert_eq!(rejected.error_code(), -1);
+        assert!(provider.calls.lock().is_empty());
+    }
+}
@@ -0,0 +1,487 @@
+//! JSON Schema validation of tool arguments.
+//!
+//! Covers the subset of JSON Schema used by tool input schemas: `type`,
+//! `enum`, `const`, `required`, `properties`, `additionalProperties`,
+//! `items`, numeric and length bounds, `anyOf` / `oneOf` / `allOf` and local
+//! `$ref`s into `$defs` or `definitions`. Unknown keywords are ignored.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fmt;
+
+/// A single validation failure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, e.g. `/items/0/name`. Empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Validate `instance` against `schema`, returning every violation found
+pub fn validate(schema: &Value, instance: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    Validator {
+        root: schema,
+        resolving: RefCell::default(),
+    }
+    .validate(schema, instance, "", &mut violations);
+    violations
+}
+
+/// Join violations into a single line for error messages
+pub fn summarize(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    /// `$ref`s being followed, with the value each is applied to, so a
+    /// reference cycle is reported instead of recursing forever
+    resolving: RefCell<Vec<(&'a str, *const Value)>>,
+}
+
+impl<'a> Validator<'a> {
+    fn validate(
+        &self,
+        schema: &'a Value,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return violation(out, path, "no value is allowed here"),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            self.validate_ref(reference, instance, path, out);
+        }
+
+        if let Some(expected) = schema.get("type")
+            && !type_matches(expected, instance)
+        {
+            // Further keywords would only repeat the type mismatch
+            return violation(
+                out,
+                path,
+                format!(
+                    "expected {}, found {}",
+                    describe_type(expected),
+                    type_name(instance)
+                ),
+            );
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+            && !allowed.contains(instance)
+        {
+            violation(
+                out,
+                path,
+                format!("must be one of {}", Value::Array(allowed.clone())),
+            );
+        }
+        if let Some(expected) = schema.get("const")
+            && expected != instance
+        {
+            violation(out, path, format!("must be {}", expected));
+        }
+
+        self.validate_combinators(schema, instance, path, out);
+
+        match instance {
+            Value::Object(object) => self.validate_object(schema, object, path, out),
+            Value::Array(items) => self.validate_array(schema, items, path, out),
+            Value::String(s) => validate_length(schema, s.chars().count(), "character", path, out),
+            Value::Number(n) => validate_range(schema, n.as_f64().unwrap_or_default(), path, out),
+            _ => {}
+        }
+    }
+
+    fn validate_ref(
+        &self,
+        reference: &'a str,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        // Following the same reference for the same value again can only loop
+        let key = (reference, instance as *const Value);
+        if self.resolving.borrow().contains(&key) {
+            return violation(
+                out,
+                path,
+                format!("circular schema reference '{}'", reference),
+            );
+        }
+        let Some(target) = self.resolve(reference) else {
+            return violation(
+                out,
+                path,
+                format!("unresolvable schema reference '{}'", reference),
+            );
+        };
+
+        self.resolving.borrow_mut().push(key);
+        self.validate(target, instance, path, out);
+        self.resolving.borrow_mut().pop();
+    }
+
+    fn validate_combinators(
+        &self,
+        schema: &'a Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for subschema in all {
+                self.validate(subschema, instance, path, out);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array)
+            && !any
+                .iter()
+                .any(|subschema| self.is_valid(subschema, instance))
+        {
+            violation(out, path, "does not match any of the allowed schemas");
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let matching = one
+                .iter()
+                .filter(|subschema| self.is_valid(subschema, instance))
+                .count();
+            if matching != 1 {
+                violation(
+                    out,
+                    path,
+                    format!("must match exactly one schema, matched {}", matching),
+                );
+            }
+        }
+    }
+
+    fn validate_object(
+        &self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        for name in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(name) = name.as_str()
+                && !object.contains_key(name)
+            {
+                violation(out, &pointer(path, name), "is required");
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, value) in object {
+            let child = pointer(path, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(property) => self.validate(property, value, &child, out),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        violation(out, &child, "is not an allowed property")
+                    }
+                    Some(additional) => self.validate(additional, value, &child, out),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn validate_array(
+        &self,
+        schema: &'a Map<String, Value>,
+        items: &[Value],
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        validate_length(schema, items.len(), "item", path, out);
+
+        match schema.get("items") {
+            // Tuple form, one schema per position
+            Some(Value::Array(positional)) => {
+                for (i, (item, item_schema)) in items.iter().zip(positional).enumerate() {
+                    self.validate(item_schema, item, &pointer(path, &i.to_string()), out);
+                }
+            }
+            Some(item_schema) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.validate(item_schema, item, &pointer(path, &i.to_string()), out);
+                }
+            }
+            None => {}
+        }
+
+        if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+            let has_duplicates = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if has_duplicates {
+                violation(out, path, "items must be unique");
+            }
+        }
+    }
+
+    fn is_valid(&self, schema: &'a Value, instance: &Value) -> bool {
+        let mut violations = Vec::new();
+        self.validate(schema, instance, "", &mut violations);
+        violations.is_empty()
+    }
+
+    /// Resolve a local reference such as `#/$defs/Point`
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+}
+
+fn validate_length(
+    schema: &Map<String, Value>,
+    len: usize,
+    unit: &str,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let (min_key, max_key) = if unit == "item" {
+        ("minItems", "maxItems")
+    } else {
+        ("minLength", "maxLength")
+    };
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64)
+        && (len as u64) < min
+    {
+        violation(out, path, format!("must have at least {} {}(s)", min, unit));
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64)
+        && (len as u64) > max
+    {
+        violation(out, path, format!("must have at most {} {}(s)", max, unit));
+    }
+}
+
+fn validate_range(schema: &Map<String, Value>, n: f64, path: &str, out: &mut Vec<SchemaViolation>) {
+    let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+
+    if let Some(min) = bound("minimum").filter(|min| n < *min) {
+        violation(out, path, format!("must be >= {}", min));
+    }
+    if let Some(max) = bound("maximum").filter(|max| n > *max) {
+        violation(out, path, format!("must be <= {}", max));
+    }
+    if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+        violation(out, path, format!("must be > {}", min));
+    }
+    if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+        violation(out, path, format!("must be < {}", max));
+    }
+}
+
+fn type_matches(expected: &Value, instance: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, instance),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| is_type(name, instance)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, instance: &Value) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => match instance {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        "array" => instance.is_array(),
+        "object" => instance.is_object(),
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("valid type").to_string(),
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Append a reference token to a JSON pointer (RFC 6901)
+fn pointer(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn violation(out: &mut Vec<SchemaViolation>, path: &str, message: impl Into<String>) {
+    out.push(SchemaViolation {
+        path: path.to_string(),
+        message: message.into(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(violations: &[SchemaViolation]) -> Vec<&str> {
+        violations.iter().map(|v| v.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_types_and_required() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "number" },
+                "b": { "type": "integer" },
+                "op": { "type": "string", "enum": ["add", "sub"] }
+            },
+            "required": ["a", "b"]
+        });
+
+        assert!(validate(&schema, &json!({ "a": 1.5, "b": 2, "op": "add" })).is_empty());
+
+        let violations = validate(&schema, &json!({ "a": "1", "op": "mul" }));
+        let mut found = paths(&violations);
+        found.sort();
+        assert_eq!(found, vec!["/a", "/b", "/op"]);
+        let a = violations.iter().find(|v| v.path == "/a").unwrap();
+        assert_eq!(a.message, "expected number, found string");
+
+        let violations = validate(&schema, &json!([1, 2]));
+        assert_eq!(paths(&violations), vec![""]);
+    }
+
+    #[test]
+    fn test_nested_objects_and_arrays() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "points": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": { "x": { "type": "number" }, "y": { "type": "number" } },
+                        "required": ["x", "y"],
+                        "additionalProperties": false
+                    }
+                },
+                "a/b": { "type": "boolean" }
+            }
+        });
+
+        let violations = validate(
+            &schema,
+            &json!({
+                "points": [{ "x": 1, "y": 2 }, { "x": 1, "z": 3 }],
+                "a/b": "yes"
+            }),
+        );
+        let mut found = paths(&violations);
+        found.sort();
+        assert_eq!(found, vec!["/a~1b", "/points/1/y", "/points/1/z"]);
+
+        let violations = validate(&schema, &json!({ "points": [] }));
+        assert_eq!(violations[0].message, "must have at least 1 item(s)");
+    }
+
+    #[test]
+    fn test_combinators_and_refs() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "shape": { "$ref": "#/$defs/Shape" },
+                "label": { "type": ["string", "null"] }
+            },
+            "$defs": {
+                "Shape": {
+                    "oneOf": [
+                        { "type": "object", "properties": { "radius": { "type": "number", "minimum": 0 } }, "required": ["radius"] },
+                        { "type": "object", "properties": { "side": { "type": "number" } }, "required": ["side"] }
+                    ]
+                }
+            }
+        });
+
+        assert!(validate(&schema, &json!({ "shape": { "radius": 2 }, "label": null })).is_empty());
+
+        let violations = validate(&schema, &json!({ "shape": { "radius": -1 }, "label": 3 }));
+        let mut found = paths(&violations);
+        found.sort();
+        assert_eq!(found, vec!["/label", "/shape"]);
+    }
+
+    #[test]
+    fn test_recursive_and_circular_refs() {
+        let schema = json!({
+            "$ref": "#/$defs/Node",
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": { "children": { "type": "array", "items": { "$ref": "#/$defs/Node" } } }
+                },
+                "A": { "$ref": "#/$defs/B" },
+                "B": { "$ref": "#/$defs/A" }
+            }
+        });
+        assert!(validate(&schema, &json!({ "children": [{ "children": [] }] })).is_empty());
+        let violations = validate(&schema, &json!({ "children": [{ "children": 1 }] }));
+        assert_eq!(paths(&violations), vec!["/children/0/children"]);
+
+        let violations = validate(&json!({ "$ref": "#" }), &json!(1));
+        assert_eq!(violations[0].message, "circular schema reference '#'");
+        let mut mutual = schema.clone();
+        mutual["$ref"] = json!("#/$defs/A");
+        assert_eq!(validate(&mutual, &json!({})).len(), 1);
+    }
+}
@@ -1,32 +1,411 @@
-use crate::protocol::*;
+use crate::context::{
+    notification, CancellationToken, PendingRequests, RequestContext, SessionHandle,
+};
 use crate::error::{Error, Result};
+use crate::logging::{ClientLogLayer, LogForwarding, LogSinks};
+use crate::middleware::{Middleware, Next};
+use crate::protocol::*;
+use crate::session_manager::{SessionInfo, SessionManager, TransportKind};
+use crate::uri_template::UriTemplate;
 use async_trait::async_trait;
-use dashmap::DashMap;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use dashmap::{mapref::entry::Entry, DashMap};
+use futures::future::{BoxFuture, FutureExt};
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::{json, Value};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{broadcast, Semaphore};
+use tracing::Instrument;
 
 #[async_trait]
 pub trait ToolHandler: Send + Sync {
     async fn execute(&self, name: &str, arguments: Value) -> Result<Vec<ResultContent>>;
+
+    /// Execute with the context of the request, for tools that report
+    /// progress or stop early when cancelled. Defaults to [`execute`](Self::execute).
+    async fn execute_with_context(
+        &self,
+        name: &str,
+        arguments: Value,
+        ctx: &RequestContext,
+    ) -> Result<Vec<ResultContent>> {
+        let _ = ctx;
+        self.execute(name, arguments).await
+    }
+
+    /// Execute, returning structured content along with the content blocks.
+    /// Tools with an output schema must override this; the default returns
+    /// the content of [`execute_with_context`](Self::execute_with_context).
+    async fn execute_with_output(
+        &self,
+        name: &str,
+        arguments: Value,
+        ctx: &RequestContext,
+    ) -> Result<ToolOutput> {
+        Ok(ToolOutput::from(
+            self.execute_with_context(name, arguments, ctx).await?,
+        ))
+    }
 }
 
 #[async_trait]
 pub trait ResourceHandler: Send + Sync {
-    async fn get(&self, uri: &str) -> Result<Resource>;
+    /// Read the contents of a resource. A single read may return several
+    /// entries, e.g. the files of a directory.
+    async fn read(&self, uri: &str) -> Result<Vec<ResourceContents>>;
     async fn list(&self) -> Result<Vec<Resource>>;
 }
 
+/// What a tool call produced: content blocks for the model and, for tools
+/// with an output schema, the result as structured JSON
+#[derive(Debug, Clone, Default)]
+pub struct ToolOutput {
+    pub content: Vec<ResultContent>,
+    pub structured_content: Option<Value>,
+}
+
+impl From<Vec<ResultContent>> for ToolOutput {
+    fn from(content: Vec<ResultContent>) -> Self {
+        Self {
+            content,
+            structured_content: None,
+        }
+    }
+}
+
+/// Return values accepted from typed tool handlers registered with [`McpServer::tool`]
+pub trait IntoToolContent {
+    fn into_content(self) -> Vec<ResultContent>;
+
+    /// The content, with structured content for types that have it
+    fn into_output(self) -> Result<ToolOutput>
+    where
+        Self: Sized,
+    {
+        Ok(ToolOutput::from(self.into_content()))
+    }
+}
+
+impl IntoToolContent for ToolOutput {
+    fn into_content(self) -> Vec<ResultContent> {
+        self.content
+    }
+
+    fn into_output(self) -> Result<ToolOutput> {
+        Ok(self)
+    }
+}
+
+/// A typed tool result, returned as `structuredContent` and, for clients
+/// that only read content blocks, as JSON text
+#[derive(Debug, Clone)]
+pub struct Structured<T>(pub T);
+
+impl<T: Serialize> IntoToolContent for Structured<T> {
+    fn into_content(self) -> Vec<ResultContent> {
+        self.into_output()
+            .map(|output| output.content)
+            .unwrap_or_default()
+    }
+
+    fn into_output(self) -> Result<ToolOutput> {
+        let value = serde_json::to_value(&self.0)?;
+        Ok(ToolOutput {
+            content: vec![ResultContent::text(value.to_string())],
+            structured_content: Some(value),
+        })
+    }
+}
+
+impl IntoToolContent for Vec<ResultContent> {
+    fn into_content(self) -> Vec<ResultContent> {
+        self
+    }
+}
+
+impl IntoToolContent for ResultContent {
+    fn into_content(self) -> Vec<ResultContent> {
+        vec![self]
+    }
+}
+
+impl IntoToolContent for String {
+    fn into_content(self) -> Vec<ResultContent> {
+        vec![ResultContent::text(self)]
+    }
+}
+
+impl IntoToolContent for &'static str {
+    fn into_content(self) -> Vec<ResultContent> {
+        self.to_string().into_content()
+    }
+}
+
+type BoxedToolFn =
+    Box<dyn Fn(Value, RequestContext) -> BoxFuture<'static, Result<ToolOutput>> + Send + Sync>;
+
+/// Adapts a typed closure to [`ToolHandler`]
+struct FnToolHandler(BoxedToolFn);
+
+#[async_trait]
+impl ToolHandler for FnToolHandler {
+    async fn execute(&self, _name: &str, arguments: Value) -> Result<Vec<ResultContent>> {
+        let ctx = RequestContext::new(uuid::Uuid::new_v4().to_string());
+        Ok((self.0)(arguments, ctx).await?.content)
+    }
+
+    async fn execute_with_context(
+        &self,
+        _name: &str,
+        arguments: Value,
+        ctx: &RequestContext,
+    ) -> Result<Vec<ResultContent>> {
+        Ok((self.0)(arguments, ctx.clone()).await?.content)
+    }
+
+    async fn execute_with_output(
+        &self,
+        _name: &str,
+        arguments: Value,
+        ctx: &RequestContext,
+    ) -> Result<ToolOutput> {
+        (self.0)(arguments, ctx.clone()).await
+    }
+}
+
+/// What happens to a call when its tool is already running at its
+/// concurrency limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverloadPolicy {
+    /// Wait for a running call to finish
+    #[default]
+    Queue,
+    /// Fail the call right away with an error result
+    Reject,
+}
+
+/// Execution limits of a tool. Calls that exceed them, like calls whose
+/// handler panics, end with an `isError` result instead of a protocol error.
+#[derive(Debug, Clone, Default)]
+pub struct ToolLimits {
+    /// Longest a call may run
+    pub timeout: Option<Duration>,
+    /// Most calls that may run at once
+    pub max_concurrency: Option<usize>,
+    pub overload: OverloadPolicy,
+}
+
+impl ToolLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Allow at most `max` concurrent calls (at least one)
+    pub fn max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max.max(1));
+        self
+    }
+
+    pub fn overload(mut self, policy: OverloadPolicy) -> Self {
+        self.overload = policy;
+        self
+    }
+}
+
+/// A tool definition together with the handler that executes it.
+///
+/// Generated by `#[mcp_tool]` and registered with [`McpServer::register`].
+pub struct ToolDefinition {
+    pub tool: Tool,
+    pub handler: Arc<dyn ToolHandler>,
+    pub limits: ToolLimits,
+}
+
+impl ToolDefinition {
+    /// Define a tool with a typed handler. The input schema is generated from
+    /// `A`, and arguments are deserialized into it before `handler` is called.
+    pub fn typed<A, R, F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        R: IntoToolContent,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        Self::typed_with_context(name, description, move |args: A, _ctx: RequestContext| {
+            handler(args)
+        })
+    }
+
+    /// Like [`typed`](Self::typed), but the handler also receives the
+    /// request's context to report progress and observe cancellation
+    pub fn typed_with_context<A, R, F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        R: IntoToolContent,
+        F: Fn(A, RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        let name = name.into();
+        let tool = Tool::new(name.clone(), description)
+            .with_input_schema(ToolInputSchema::from_type::<A>());
+
+        let handler = Arc::new(handler);
+        let call: BoxedToolFn = Box::new(move |arguments, ctx| {
+            let handler = handler.clone();
+            let name = name.clone();
+            Box::pin(async move {
+                let args: A = serde_json::from_value(arguments).map_err(|e| {
+                    Error::InvalidParams(format!("Invalid arguments for '{}': {}", name, e))
+                })?;
+                handler(args, ctx).await?.into_output()
+            })
+        });
+
+        Self {
+            tool,
+            handler: Arc::new(FnToolHandler(call)),
+            limits: ToolLimits::default(),
+        }
+    }
+
+    /// Define a tool whose handler returns a typed result. The output schema
+    /// is generated from `T`, which must serialize to a JSON object, and
+    /// results are returned as structured content.
+    pub fn structured<A, T, F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        T: Serialize + schemars::JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let mut definition = Self::typed(name, description, move |args: A| {
+            let output = handler(args);
+            async move { output.await.map(Structured) }
+        });
+        definition.tool.output_schema = Some(ToolInputSchema::from_type::<T>());
+        definition
+    }
+
+    /// Set the tool's timeout and concurrency limits
+    pub fn with_limits(mut self, limits: ToolLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the human-readable name shown to users
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.tool.title = Some(title.into());
+        self
+    }
+
+    /// Describe the tool's behavior, e.g. that it is read-only
+    pub fn with_annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.tool.annotations = Some(annotations);
+        self
+    }
+
+    /// Attach implementation-specific `_meta` to the tool
+    pub fn with_meta(mut self, meta: Value) -> Self {
+        self.tool.meta = Some(meta);
+        self
+    }
+}
+
+/// Reads resources whose URI matches a registered resource template
+#[async_trait]
+pub trait ResourceTemplateHandler: Send + Sync {
+    /// Read `uri`, given the variables extracted from it by the template
+    async fn read(
+        &self,
+        uri: &str,
+        variables: HashMap<String, String>,
+    ) -> Result<Vec<ResourceContents>>;
+}
+
+#[async_trait]
+impl<F, Fut> ResourceTemplateHandler for F
+where
+    F: Fn(String, HashMap<String, String>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Vec<ResourceContents>>> + Send,
+{
+    async fn read(
+        &self,
+        uri: &str,
+        variables: HashMap<String, String>,
+    ) -> Result<Vec<ResourceContents>> {
+        (self)(uri.to_string(), variables).await
+    }
+}
+
 #[async_trait]
 pub trait PromptHandler: Send + Sync {
-    async fn get(&self, name: &str) -> Result<Prompt>;
+    /// Render a prompt with the given arguments. Required arguments declared
+    /// by the prompt are validated before this is called.
+    async fn get(&self, name: &str, arguments: HashMap<String, String>) -> Result<GetPromptResult>;
     async fn list(&self) -> Result<Vec<Prompt>>;
 }
 
+/// Suggests values for a prompt argument or resource template variable
+#[async_trait]
+pub trait CompletionHandler: Send + Sync {
+    /// Suggest values for the partial `value`, best first. `context` holds
+    /// the arguments the user has already filled in.
+    async fn complete(&self, value: &str, context: &HashMap<String, String>)
+        -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl<F, Fut> CompletionHandler for F
+where
+    F: Fn(String, HashMap<String, String>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Vec<String>>> + Send,
+{
+    async fn complete(
+        &self,
+        value: &str,
+        context: &HashMap<String, String>,
+    ) -> Result<Vec<String>> {
+        (self)(value.to_string(), context.clone()).await
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub name: String,
     pub version: String,
     pub capabilities: ServerCapabilities,
+    /// Maximum number of items per page of `tools/list`, `resources/list`,
+    /// `resources/templates/list` and `prompts/list`. `None` returns
+    /// everything in one page.
+    pub page_size: Option<usize>,
+    /// How long a Streamable HTTP session may go without a message before
+    /// it is ended; sessions with an open GET stream do not idle out.
+    /// Defaults to 30 minutes. `None` keeps sessions until the client
+    /// deletes them.
+    pub session_idle_timeout: Option<Duration>,
 }
 
 impl Default for ServerConfig {
@@ -35,43 +414,431 @@ impl Default for ServerConfig {
             name: "MCP Server".to_string(),
             version: "1.0.0".to_string(),
             capabilities: ServerCapabilities::default(),
+            page_size: None,
+            session_idle_timeout: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// A server with default capabilities and settings. Prefer this or
+    /// `..ServerConfig::default()` to a full struct literal, which breaks
+    /// whenever a setting is added.
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_capabilities(mut self, capabilities: ServerCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    pub fn with_session_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.session_idle_timeout = Some(timeout);
+        self
+    }
+}
+
+/// What a client declared when it initialized its session
+#[derive(Debug, Clone)]
+pub struct ClientSession {
+    /// Version negotiated in `initialize`
+    pub protocol_version: ProtocolVersion,
+    pub client_info: Implementation,
+    pub capabilities: ClientCapabilities,
+    /// Set once the client sent `notifications/initialized`
+    pub initialized: bool,
+    /// Minimum level of log messages the client asked for with `logging/setLevel`
+    pub log_level: Option<LoggingLevel>,
+}
+
+/// Cancellation tokens of requests being handled, by session and request ID
+type InFlightRequests = Arc<DashMap<(String, RequestId), CancellationToken>>;
+
+/// A tool definition bundled with the handler that executes it
+struct RegisteredTool {
+    tool: Tool,
+    /// `None` for tools served by the server-wide fallback handler
+    handler: Option<Arc<dyn ToolHandler>>,
+    limits: ToolLimits,
+    /// Enforces `limits.max_concurrency`
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl RegisteredTool {
+    fn new(tool: Tool, handler: Option<Arc<dyn ToolHandler>>, limits: ToolLimits) -> Self {
+        let permits = limits
+            .max_concurrency
+            .map(|max| Arc::new(Semaphore::new(max)));
+        Self {
+            tool,
+            handler,
+            limits,
+            permits,
         }
     }
 }
 
+struct RegisteredTemplate {
+    template: ResourceTemplate,
+    matcher: UriTemplate,
+    handler: Arc<dyn ResourceTemplateHandler>,
+}
+
 pub struct McpServer {
     config: ServerConfig,
-    tools: Arc<DashMap<String, Tool>>,
+    tools: Arc<DashMap<String, RegisteredTool>>,
     resources: Arc<DashMap<String, Resource>>,
+    resource_contents: Arc<DashMap<String, Vec<ResourceContents>>>,
+    resource_templates: Arc<RwLock<Vec<RegisteredTemplate>>>,
+    /// Sessions subscribed to updates of each resource URI
+    subscriptions: Arc<DashMap<String, Vec<SessionHandle>>>,
     prompts: Arc<DashMap<String, Prompt>>,
-    tool_handler: Arc<dyn ToolHandler>,
+    prompt_messages: Arc<DashMap<String, Vec<PromptMessage>>>,
+    /// Completion handlers, by reference and argument name
+    completions: Arc<DashMap<(CompletionReference, String), Arc<dyn CompletionHandler>>>,
+    tool_handler: Option<Arc<dyn ToolHandler>>,
     resource_handler: Option<Arc<dyn ResourceHandler>>,
     prompt_handler: Option<Arc<dyn PromptHandler>>,
+    notifications: broadcast::Sender<Value>,
+    /// Initialized client sessions, by session ID
+    clients: Arc<DashMap<String, ClientSession>>,
+    /// Sessions opened by transports, initialized or not
+    sessions: SessionManager,
+    in_flight: InFlightRequests,
+    /// Requests sent to clients, waiting for their responses
+    pending: PendingRequests,
+    /// Tool calls whose tracing events go to their client
+    log_sinks: LogSinks,
+    middlewares: Vec<Arc<dyn Middleware>>,
 }
 
 impl McpServer {
+    /// Create a server whose tools registered with [`register_tool`](Self::register_tool)
+    /// are executed by `tool_handler`
     pub fn new(config: ServerConfig, tool_handler: Arc<dyn ToolHandler>) -> Self {
+        Self {
+            tool_handler: Some(tool_handler),
+            ..Self::with_config(config)
+        }
+    }
+
+    /// Create a server without a fallback tool handler, for servers whose
+    /// tools are all registered with [`tool`](Self::tool)
+    pub fn with_config(config: ServerConfig) -> Self {
         Self {
             config,
             tools: Arc::new(DashMap::new()),
             resources: Arc::new(DashMap::new()),
+            resource_contents: Arc::new(DashMap::new()),
+            resource_templates: Arc::new(RwLock::new(Vec::new())),
+            subscriptions: Arc::new(DashMap::new()),
             prompts: Arc::new(DashMap::new()),
-            tool_handler,
+            prompt_messages: Arc::new(DashMap::new()),
+            completions: Arc::new(DashMap::new()),
+            tool_handler: None,
             resource_handler: None,
             prompt_handler: None,
+            notifications: broadcast::channel(256).0,
+            clients: Arc::new(DashMap::new()),
+            sessions: SessionManager::default(),
+            in_flight: Arc::new(DashMap::new()),
+            pending: Arc::new(DashMap::new()),
+            log_sinks: Arc::new(DashMap::new()),
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Subscribe to the notifications the server broadcasts to every
+    /// session. Transports forward these to their connected clients.
+    pub fn notifications(&self) -> broadcast::Receiver<Value> {
+        self.notifications.subscribe()
+    }
+
+    /// Broadcast a notification to every connected session
+    pub fn notify(&self, method: &str, params: Value) {
+        // An error only means no session is connected
+        let _ = self.notifications.send(notification(method, params));
+    }
+
+    /// Announce a registry change if the capability advertises `listChanged`.
+    /// Changes before any session is ready are part of startup; transports
+    /// skip sessions that are not ready yet.
+    fn notify_list_changed(&self, method: &str, list_changed: Option<bool>) {
+        if list_changed.unwrap_or(false) && self.clients.iter().any(|client| client.initialized) {
+            self.notify(method, json!({}));
         }
     }
 
+    fn tools_changed(&self) {
+        let list_changed = self
+            .config
+            .capabilities
+            .tools
+            .as_ref()
+            .and_then(|c| c.list_changed);
+        self.notify_list_changed("notifications/tools/list_changed", list_changed);
+    }
+
+    fn resources_changed(&self) {
+        let list_changed = self
+            .config
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|c| c.list_changed);
+        self.notify_list_changed("notifications/resources/list_changed", list_changed);
+    }
+
+    fn prompts_changed(&self) {
+        let list_changed = self
+            .config
+            .capabilities
+            .prompts
+            .as_ref()
+            .and_then(|c| c.list_changed);
+        self.notify_list_changed("notifications/prompts/list_changed", list_changed);
+    }
+
+    /// Register a tool executed by the server-wide [`ToolHandler`]
     pub fn register_tool(&self, tool: Tool) {
-        self.tools.insert(tool.name.to_string(), tool);
+        self.tools.insert(
+            tool.name.to_string(),
+            RegisteredTool::new(tool, None, ToolLimits::default()),
+        );
+        self.tools_changed();
+    }
+
+    /// Register a tool together with its own handler
+    pub fn register_tool_with_handler(&self, tool: Tool, handler: Arc<dyn ToolHandler>) {
+        self.register(ToolDefinition {
+            tool,
+            handler,
+            limits: ToolLimits::default(),
+        });
+    }
+
+    /// Change the limits of a registered tool. Returns `false` if no tool
+    /// has that name. Calls already running keep their old limits.
+    pub fn set_tool_limits(&self, name: &str, limits: ToolLimits) -> bool {
+        match self.tools.get_mut(name) {
+            Some(mut registered) => {
+                let tool = registered.tool.clone();
+                let handler = registered.handler.clone();
+                *registered = RegisteredTool::new(tool, handler, limits);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a tool, returning its definition if it was registered
+    pub fn unregister_tool(&self, name: &str) -> Option<Tool> {
+        let (_, removed) = self.tools.remove(name)?;
+        self.tools_changed();
+        Some(removed.tool)
+    }
+
+    /// Register a tool with a typed handler.
+    ///
+    /// The input schema is generated from `A`, and arguments are deserialized
+    /// into it before `handler` is called:
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct AddArgs { a: f64, b: f64 }
+    ///
+    /// server.tool("add", "Add numbers", |args: AddArgs| async move {
+    ///     Ok(format!("{}", args.a + args.b))
+    /// });
+    /// ```
+    pub fn tool<A, R, F, Fut>(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> &Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        R: IntoToolContent,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        self.register(ToolDefinition::typed(name, description, handler))
+    }
+
+    /// Register a typed tool whose handler also receives the request's
+    /// [`RequestContext`], for progress reporting and cancellation
+    pub fn tool_with_context<A, R, F, Fut>(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> &Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        R: IntoToolContent,
+        F: Fn(A, RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        self.register(ToolDefinition::typed_with_context(
+            name,
+            description,
+            handler,
+        ))
+    }
+
+    /// Register a tool with a typed handler whose result is returned as
+    /// structured content, described by an output schema generated from `T`:
+    ///
+    /// ```ignore
+    /// #[derive(Serialize, JsonSchema)]
+    /// struct Weather { temperature: f64, conditions: String }
+    ///
+    /// server.structured_tool("get_weather", "Current weather", |args: CityArgs| async move {
+    ///     Ok(Weather { temperature: 22.5, conditions: "Sunny".to_string() })
+    /// });
+    /// ```
+    pub fn structured_tool<A, T, F, Fut>(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> &Self
+    where
+        A: DeserializeOwned + schemars::JsonSchema + Send + 'static,
+        T: Serialize + schemars::JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.register(ToolDefinition::structured(name, description, handler))
+    }
+
+    /// Register a tool definition, e.g. one generated by `#[mcp_tool]`
+    pub fn register(&self, definition: ToolDefinition) -> &Self {
+        let ToolDefinition {
+            tool,
+            handler,
+            limits,
+        } = definition;
+        self.tools.insert(
+            tool.name.to_string(),
+            RegisteredTool::new(tool, Some(handler), limits),
+        );
+        self.tools_changed();
+        self
     }
 
     pub fn register_resource(&self, resource: Resource) {
         self.resources.insert(resource.uri.clone(), resource);
+        self.resources_changed();
+    }
+
+    /// Register a resource whose contents are known up front.
+    ///
+    /// Reads of its URI are answered directly, without the resource handler.
+    pub fn register_resource_with_contents(
+        &self,
+        resource: Resource,
+        contents: Vec<ResourceContents>,
+    ) {
+        self.resource_contents
+            .insert(resource.uri.clone(), contents);
+        self.register_resource(resource);
+    }
+
+    /// Register an RFC 6570 resource template such as `db://tables/{name}/schema`.
+    ///
+    /// Reads of URIs matching the template are routed to `handler` with the
+    /// extracted variables. Templates are tried in registration order, after
+    /// static contents and before the resource handler. Registering the same
+    /// template again replaces it.
+    ///
+    /// A simple expression matches one path segment only, so
+    /// `file:///{path}` does not match `file:///src/main.rs`; write
+    /// `file:///{+path}` for values that may contain `/`.
+    pub fn register_resource_template(
+        &self,
+        template: ResourceTemplate,
+        handler: Arc<dyn ResourceTemplateHandler>,
+    ) -> Result<()> {
+        let matcher = UriTemplate::parse(&template.uri_template)?;
+        if matcher.segment_variables().contains(&"path") {
+            tracing::warn!(
+                template = %matcher,
+                "'{{path}}' matches a single path segment; use '{{+path}}' to match nested paths"
+            );
+        }
+        let registered = RegisteredTemplate {
+            template,
+            matcher,
+            handler,
+        };
+
+        let mut templates = self.resource_templates.write();
+        match templates
+            .iter_mut()
+            .find(|t| t.matcher.as_str() == registered.matcher.as_str())
+        {
+            Some(existing) => *existing = registered,
+            None => templates.push(registered),
+        }
+        drop(templates);
+
+        self.resources_changed();
+        Ok(())
     }
 
     pub fn register_prompt(&self, prompt: Prompt) {
         self.prompts.insert(prompt.name.to_string(), prompt);
+        self.prompts_changed();
+    }
+
+    /// Register a prompt rendered from message templates.
+    ///
+    /// `{{name}}` placeholders in text messages and embedded text resources
+    /// are replaced with the argument values passed to `prompts/get`;
+    /// placeholders for omitted optional arguments render as empty.
+    pub fn register_prompt_with_messages(&self, prompt: Prompt, messages: Vec<PromptMessage>) {
+        self.prompt_messages
+            .insert(prompt.name.to_string(), messages);
+        self.register_prompt(prompt);
+    }
+
+    /// Suggest values for an argument of a prompt in `completion/complete`
+    pub fn register_prompt_completion(
+        &self,
+        prompt: impl Into<String>,
+        argument: impl Into<String>,
+        handler: Arc<dyn CompletionHandler>,
+    ) {
+        self.completions.insert(
+            (CompletionReference::prompt(prompt), argument.into()),
+            handler,
+        );
+    }
+
+    /// Suggest values for a variable of a resource template, e.g. `name` in
+    /// `db://tables/{name}/schema`, in `completion/complete`
+    pub fn register_resource_completion(
+        &self,
+        uri_template: impl Into<String>,
+        variable: impl Into<String>,
+        handler: Arc<dyn CompletionHandler>,
+    ) {
+        self.completions.insert(
+            (CompletionReference::resource(uri_template), variable.into()),
+            handler,
+        );
     }
 
     pub fn set_resource_handler(&mut self, handler: Arc<dyn ResourceHandler>) {
@@ -82,19 +849,179 @@ impl McpServer {
         self.prompt_handler = Some(handler);
     }
 
-    pub async fn handle_initialize(&self) -> JsonRpcResponse {
-        JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id: "1".to_string(),
-            result: Some(json!({
-                "protocolVersion": "2024-11-05",
-                "capabilities": self.config.capabilities,
-                "serverInfo": {
-                    "name": self.config.name,
-                    "version": self.config.version,
-                }
-            })),
-            error: None,
+    /// Wrap request handling in a middleware. Middlewares run in the order
+    /// they are added, so the first one sees each request first and each
+    /// response last.
+    pub fn layer(&mut self, middleware: impl Middleware + 'static) -> &mut Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Run `hook` each time a transport opens a session, before any of its
+    /// messages are handled
+    pub fn on_connect(&mut self, hook: impl Fn(&SessionInfo) + Send + Sync + 'static) -> &mut Self {
+        self.sessions.on_connect.push(Arc::new(hook));
+        self
+    }
+
+    /// Run `hook` each time a session ends: its connection closed, the
+    /// client deleted it or it expired
+    pub fn on_disconnect(
+        &mut self,
+        hook: impl Fn(&SessionInfo) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.sessions.on_disconnect.push(Arc::new(hook));
+        self
+    }
+
+    /// A `tracing` layer that sends what tool handlers log to the client
+    /// that called them. Add it to the global subscriber; see [`crate::logging`].
+    pub fn logging_layer(&self) -> ClientLogLayer {
+        ClientLogLayer::new(self.log_sinks.clone())
+    }
+
+    /// Answer an `initialize` request, agreeing on the client's protocol
+    /// version if it is supported and otherwise offering the latest
+    pub async fn handle_initialize(&self, params: &InitializeParams) -> InitializeResult {
+        // Every server accepts logging/setLevel
+        let mut capabilities = self.config.capabilities.clone();
+        capabilities.logging.get_or_insert_with(|| json!({}));
+        if !self.completions.is_empty() {
+            capabilities.completions.get_or_insert_with(|| json!({}));
+        }
+
+        InitializeResult {
+            protocol_version: ProtocolVersion::negotiate(&params.protocol_version).to_string(),
+            capabilities,
+            server_info: Implementation {
+                name: self.config.name.clone(),
+                version: self.config.version.clone(),
+                title: None,
+            },
+        }
+    }
+
+    /// State of an initialized client session
+    pub fn client_session(&self, session_id: &str) -> Option<ClientSession> {
+        self.clients.get(session_id).map(|client| client.clone())
+    }
+
+    /// Whether a session's client has sent `notifications/initialized`
+    pub(crate) fn session_ready(&self, session_id: &str) -> bool {
+        self.clients
+            .get(session_id)
+            .is_some_and(|client| client.initialized)
+    }
+
+    /// Protocol version negotiated on a session, if it is initialized
+    pub fn protocol_version(&self, session_id: &str) -> Option<ProtocolVersion> {
+        self.clients
+            .get(session_id)
+            .map(|client| client.protocol_version)
+    }
+
+    /// Register a session a transport has opened and run the connect hooks.
+    /// Returns a token that is cancelled when the session ends, whether
+    /// through [`end_session`](Self::end_session) or by idling out.
+    pub fn open_session(
+        self: &Arc<Self>,
+        session: &SessionHandle,
+        transport: TransportKind,
+    ) -> CancellationToken {
+        let closed = self.sessions.open(session.id(), transport);
+        if let Some(timeout) = self.config.session_idle_timeout
+            && !transport.is_connection_bound()
+            && self.sessions.start_reaper()
+        {
+            spawn_session_reaper(Arc::downgrade(self), timeout);
+        }
+        closed
+    }
+
+    /// An open session, with what its client declared if it is initialized
+    pub fn session_info(&self, session_id: &str) -> Option<SessionInfo> {
+        self.sessions
+            .info(session_id, self.client_session(session_id))
+    }
+
+    /// Every open session
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .ids()
+            .iter()
+            .filter_map(|id| self.session_info(id))
+            .collect()
+    }
+
+    /// Note a message from a session's client, keeping it from expiring
+    pub(crate) fn touch_session(&self, session_id: &str) {
+        self.sessions.touch(session_id);
+    }
+
+    /// Note that a session's server-to-client stream opened or closed; a
+    /// session does not idle out while it is open
+    pub(crate) fn set_session_streaming(&self, session_id: &str, streaming: bool) {
+        self.sessions.set_streaming(session_id, streaming);
+    }
+
+    /// End the sessions that have been idle for longer than
+    /// [`ServerConfig::session_idle_timeout`]; returns how many ended. Runs
+    /// periodically once the first Streamable HTTP session opens.
+    pub fn expire_idle_sessions(&self) -> usize {
+        let Some(timeout) = self.config.session_idle_timeout else {
+            return 0;
+        };
+        let idle = self.sessions.idle(timeout);
+        for session_id in &idle {
+            tracing::debug!(session = %session_id, "Session expired");
+            self.end_session(session_id);
+        }
+        idle.len()
+    }
+
+    /// Forget a session once its transport closes: its client state and
+    /// subscriptions are dropped, its in-flight requests cancelled and the
+    /// disconnect hooks run
+    pub fn end_session(&self, session_id: &str) {
+        let client = self.clients.remove(session_id).map(|(_, client)| client);
+        self.subscriptions.retain(|_, sessions| {
+            sessions.retain(|session| session.id() != session_id);
+            !sessions.is_empty()
+        });
+        for request in self
+            .in_flight
+            .iter()
+            .filter(|request| request.key().0 == session_id)
+        {
+            request.value().cancel();
+        }
+        self.drop_pending_requests(session_id);
+        self.sessions.close(session_id, client);
+    }
+
+    /// Stop waiting for answers to requests sent on a session that can no
+    /// longer reply; their senders fail with a connection error
+    pub(crate) fn drop_pending_requests(&self, session_id: &str) {
+        self.pending
+            .retain(|_, pending| pending.session_id.as_deref() != Some(session_id));
+    }
+
+    /// Deliver a client's response to the request the server sent it.
+    /// `session_id` names the session it arrived on; responses from any
+    /// other session than the request's are ignored.
+    pub fn handle_response(&self, response: JsonRpcResponse, session_id: Option<&str>) {
+        let Some(id) = response.id.clone() else {
+            tracing::warn!(error = ?response.error, "Client reported an error without a request ID");
+            return;
+        };
+        let pending = self.pending.remove_if(&id, |_, pending| {
+            pending.session_id.as_deref() == session_id
+        });
+        match pending {
+            Some((_, pending)) => {
+                let _ = pending.reply.send(response);
+            }
+            None => tracing::debug!(id = %id, "Ignoring response to an unknown request"),
         }
     }
 
@@ -102,20 +1029,158 @@ impl McpServer {
         Ok(self
             .tools
             .iter()
-            .map(|entry| entry.value().clone())
+            .map(|entry| entry.value().tool.clone())
             .collect())
     }
 
+    /// Call a tool after validating its arguments against the tool's input schema.
+    ///
+    /// Only an unknown tool, invalid arguments or cancellation fail the call.
+    /// Errors from the handler itself come back as a result with `isError`
+    /// set, so the model can see them; return [`Error::ToolError`] to choose
+    /// the content it gets.
     pub async fn handle_tool_call(&self, name: &str, arguments: Value) -> Result<ToolResult> {
-        if !self.tools.contains_key(name) {
-            return Err(Error::ToolNotFound(name.to_string()));
+        let ctx = RequestContext::new(RequestId::random());
+        self.handle_tool_call_with_context(name, arguments, &ctx)
+            .await
+    }
+
+    /// Call a tool on behalf of a request, passing its context to the handler
+    pub async fn handle_tool_call_with_context(
+        &self,
+        name: &str,
+        arguments: Value,
+        ctx: &RequestContext,
+    ) -> Result<ToolResult> {
+        let (input_schema, output_schema, handler, limits, permits) = match self.tools.get(name) {
+            Some(registered) => (
+                registered.tool.input_schema.clone(),
+                registered.tool.output_schema.clone(),
+                registered
+                    .handler
+                    .clone()
+                    .or_else(|| self.tool_handler.clone()),
+                registered.limits.clone(),
+                registered.permits.clone(),
+            ),
+            None => return Err(Error::ToolNotFound(name.to_string())),
+        };
+        let handler = handler.ok_or_else(|| {
+            Error::InternalError(format!("No handler registered for tool '{}'", name))
+        })?;
+
+        if let Some(input_schema) = input_schema {
+            let violations =
+                crate::schema::validate(&serde_json::to_value(&input_schema)?, &arguments);
+            if !violations.is_empty() {
+                return Err(Error::InvalidArguments(violations));
+            }
         }
 
-        let content = self.tool_handler.execute(name, arguments).await?;
+        // Held until the call finishes
+        let _permit = match permits {
+            Some(permits) if limits.overload == OverloadPolicy::Reject => {
+                match permits.try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    Err(_) => {
+                        return Ok(error_result(Error::tool(format!(
+                            "Tool '{}' is busy, try again later",
+                            name
+                        ))))
+                    }
+                }
+            }
+            Some(permits) => Some(
+                permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| Error::InternalError(e.to_string()))?,
+            ),
+            None => None,
+        };
+
+        // What the handler logs inside its span is forwarded to the client,
+        // if it asked for log messages
+        let span = tracing::info_span!("mcp_tool", tool = name);
+        let _forwarding = span
+            .id()
+            .filter(|_| ctx.log_level().is_some())
+            .map(|id| LogForwarding::register(&self.log_sinks, id, ctx.clone()));
+
+        // A panicking handler must not take the request path down with it
+        let call = handler
+            .execute_with_output(name, arguments, ctx)
+            .instrument(span.clone());
+        let call = AssertUnwindSafe(call).catch_unwind();
+        let outcome = match limits.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(outcome) => outcome,
+                Err(_) => {
+                    tracing::warn!(tool = name, ?timeout, "Tool call timed out");
+                    return Ok(error_result(Error::tool(format!(
+                        "Tool '{}' timed out after {:?}",
+                        name, timeout
+                    ))));
+                }
+            },
+            None => call.await,
+        };
+
+        let output = match outcome {
+            Ok(Ok(output)) => output,
+            // Problems with the request itself are reported to the client
+            Ok(Err(
+                error @ (Error::ToolNotFound(_)
+                | Error::InvalidParams(_)
+                | Error::InvalidArguments(_)
+                | Error::Cancelled(_)),
+            )) => return Err(error),
+            // Failures of the tool are reported to the model
+            Ok(Err(error)) => {
+                tracing::debug!(tool = name, "Tool call failed: {}", error);
+                return Ok(error_result(error));
+            }
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                tracing::error!(tool = name, "Tool handler panicked: {}", message);
+                return Ok(error_result(Error::tool(format!(
+                    "Tool '{}' failed: {}",
+                    name, message
+                ))));
+            }
+        };
+
+        // Structured content is the tool's contract with its clients
+        if let Some(output_schema) = output_schema {
+            let Some(structured) = &output.structured_content else {
+                return Ok(error_result(Error::tool(format!(
+                    "Tool '{}' returned no structured content",
+                    name
+                ))));
+            };
+            let violations =
+                crate::schema::validate(&serde_json::to_value(&output_schema)?, structured);
+            if !violations.is_empty() {
+                tracing::warn!(
+                    tool = name,
+                    "Structured content does not match the output schema"
+                );
+                return Ok(error_result(Error::tool(format!(
+                    "Tool '{}' returned structured content that does not match its output schema: {}",
+                    name,
+                    crate::schema::summarize(&violations)
+                ))));
+            }
+        }
 
         Ok(ToolResult {
             id: Some(uuid::Uuid::new_v4().to_string()),
-            content,
+            content: output.content,
+            structured_content: output.structured_content,
             is_error: None,
         })
     }
@@ -132,20 +1197,74 @@ impl McpServer {
         }
     }
 
-    pub async fn handle_resource_read(&self, uri: &str) -> Result<String> {
-        if let Some(handler) = &self.resource_handler {
-            let resource = handler.get(uri).await?;
-            Ok(resource.uri.to_string())
-        } else if let Some(resource) = self.resources.get(uri) {
-            Ok(resource.uri.to_string())
+    pub async fn handle_resource_templates_list(&self) -> Result<Vec<ResourceTemplate>> {
+        Ok(self
+            .resource_templates
+            .read()
+            .iter()
+            .map(|t| t.template.clone())
+            .collect())
+    }
+
+    pub async fn handle_resource_read(&self, uri: &str) -> Result<Vec<ResourceContents>> {
+        if let Some(contents) = self.resource_contents.get(uri) {
+            return Ok(contents.value().clone());
+        }
+
+        let matched = self
+            .resource_templates
+            .read()
+            .iter()
+            .find_map(|t| t.matcher.matches(uri).map(|vars| (t.handler.clone(), vars)));
+
+        if let Some((handler, variables)) = matched {
+            handler.read(uri, variables).await
+        } else if let Some(handler) = &self.resource_handler {
+            handler.read(uri).await
         } else {
             Err(Error::ResourceNotFound(uri.to_string()))
         }
     }
 
-    pub async fn handle_prompts_list(&self) -> Result<Vec<Prompt>> {
-        if let Some(handler) = &self.prompt_handler {
-            handler.list().await
+    /// Subscribe a session to `notifications/resources/updated` for `uri`
+    pub fn subscribe_resource(&self, uri: &str, session: SessionHandle) {
+        let mut sessions = self.subscriptions.entry(uri.to_string()).or_default();
+        if !sessions.iter().any(|s| s.id() == session.id()) {
+            sessions.push(session);
+        }
+    }
+
+    /// Remove a session's subscription to `uri`
+    pub fn unsubscribe_resource(&self, uri: &str, session_id: &str) {
+        if let Some(mut sessions) = self.subscriptions.get_mut(uri) {
+            sessions.retain(|s| s.id() != session_id);
+        }
+        self.subscriptions
+            .remove_if(uri, |_, sessions| sessions.is_empty());
+    }
+
+    /// Tell every session subscribed to `uri` that the resource has changed.
+    ///
+    /// Sessions that have gone away are dropped from the subscription.
+    /// Returns the number of sessions notified.
+    pub fn notify_resource_updated(&self, uri: &str) -> usize {
+        let message = notification("notifications/resources/updated", json!({ "uri": uri }));
+
+        let notified = match self.subscriptions.get_mut(uri) {
+            Some(mut sessions) => {
+                sessions.retain(|session| session.send(message.clone()));
+                sessions.len()
+            }
+            None => 0,
+        };
+        self.subscriptions
+            .remove_if(uri, |_, sessions| sessions.is_empty());
+        notified
+    }
+
+    pub async fn handle_prompts_list(&self) -> Result<Vec<Prompt>> {
+        if let Some(handler) = &self.prompt_handler {
+            handler.list().await
         } else {
             Ok(self
                 .prompts
@@ -155,150 +1274,522 @@ impl McpServer {
         }
     }
 
-    pub async fn handle_prompt_get(&self, name: &str) -> Result<Prompt> {
-        if let Some(handler) = &self.prompt_handler {
-            handler.get(name).await
-        } else if let Some(prompt) = self.prompts.get(name) {
-            Ok(prompt.value().clone())
+    pub async fn handle_prompt_get(
+        &self,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let prompt = match self.prompts.get(name) {
+            Some(prompt) => Some(prompt.value().clone()),
+            None => match &self.prompt_handler {
+                Some(handler) => handler.list().await?.into_iter().find(|p| p.name == name),
+                None => None,
+            },
+        };
+        let prompt = prompt.ok_or_else(|| Error::PromptNotFound(name.to_string()))?;
+
+        for argument in prompt.arguments.iter().flatten() {
+            if argument.required.unwrap_or(false) && !arguments.contains_key(&argument.name) {
+                return Err(Error::InvalidParams(format!(
+                    "Missing required argument '{}' for prompt '{}'",
+                    argument.name, name
+                )));
+            }
+        }
+
+        if let Some(messages) = self.prompt_messages.get(name) {
+            Ok(GetPromptResult {
+                description: prompt.description.clone(),
+                messages: messages
+                    .iter()
+                    .map(|message| render_prompt_message(message, &arguments))
+                    .collect(),
+            })
+        } else if let Some(handler) = &self.prompt_handler {
+            handler.get(name, arguments).await
         } else {
-            Err(Error::ToolNotFound(name.to_string()))
+            Err(Error::PromptNotFound(name.to_string()))
+        }
+    }
+
+    /// Suggest values for an argument. Arguments without a completion
+    /// handler get no suggestions.
+    pub async fn handle_complete(&self, params: CompleteParams) -> Result<Completion> {
+        let key = (params.reference, params.argument.name);
+        let Some(handler) = self
+            .completions
+            .get(&key)
+            .map(|handler| handler.value().clone())
+        else {
+            return Ok(Completion::default());
+        };
+
+        let context = params
+            .context
+            .map(|context| context.arguments)
+            .unwrap_or_default();
+        let values = handler.complete(&params.argument.value, &context).await?;
+        Ok(Completion::from_values(values))
+    }
+
+    /// Handle any JSON-RPC message. Requests get a response; notifications
+    /// and responses do not.
+    pub async fn handle_message(
+        &self,
+        message: JsonRpcMessage,
+        ctx: RequestContext,
+    ) -> Option<JsonRpcResponse> {
+        match message {
+            JsonRpcMessage::Request(request) => {
+                Some(self.handle_request_with_context(request, ctx).await)
+            }
+            JsonRpcMessage::Notification(notification) => {
+                self.handle_notification(notification, ctx.session_id());
+                None
+            }
+            JsonRpcMessage::Response(response) => {
+                self.handle_response(response, ctx.session_id());
+                None
+            }
         }
     }
 
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
-        let result = match request.method.as_str() {
-            "initialize" => self.handle_initialize().await.result,
-            "tools/list" => match self.handle_tools_list().await {
-                Ok(tools) => Some(json!({ "tools": tools })),
-                Err(e) => {
-                    return JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id.clone(),
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: e.error_code(),
-                            message: e.to_string(),
-                            data: None,
-                        }),
-                    }
+        let ctx = RequestContext::new(request.id.clone());
+        self.handle_request_with_context(request, ctx).await
+    }
+
+    /// Handle a request that arrived through a transport.
+    ///
+    /// The context carries the session and any outbound channel the
+    /// transport provides for messages related to this request. The request
+    /// passes through the middleware chain before it is dispatched.
+    pub async fn handle_request_with_context(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+    ) -> JsonRpcResponse {
+        tracing::debug!(method = %request.method, session = ?ctx.session_id(), "Handling request");
+
+        let ctx = match request
+            .params
+            .as_ref()
+            .and_then(|params| params.pointer("/_meta/progressToken"))
+            .and_then(|token| serde_json::from_value::<ProgressToken>(token.clone()).ok())
+        {
+            Some(token) => ctx.with_progress_token(token),
+            None => ctx,
+        };
+
+        // Until the client sends `notifications/initialized` the server
+        // sends it nothing but responses: no requests and no log messages
+        let client = ctx.session_id().and_then(|id| self.client_session(id));
+        let ready =
+            ctx.session_id().is_none() || client.as_ref().is_some_and(|client| client.initialized);
+        let ctx = match client {
+            Some(client) if client.initialized => {
+                let ctx = ctx
+                    .with_protocol_version(client.protocol_version)
+                    .with_client_capabilities(client.capabilities);
+                match client.log_level {
+                    Some(level) => ctx.with_log_level(level),
+                    None => ctx,
                 }
-            },
-            "tools/call" => {
-                let params = match request.params {
-                    Some(p) => p,
-                    None => {
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id.clone(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32602,
-                                message: "Missing params".to_string(),
-                                data: None,
-                            }),
-                        }
-                    }
-                };
+            }
+            Some(client) => ctx.with_protocol_version(client.protocol_version),
+            None => ctx,
+        };
+        let ctx = match ctx.session_id().and_then(|id| self.session_info(id)) {
+            Some(info) => ctx.with_session_info(info),
+            None => ctx,
+        };
+        let ctx = if ready {
+            ctx.with_pending(self.pending.clone())
+        } else {
+            ctx
+        };
 
-                let name = match params.get("name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => {
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id.clone(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: -32602,
-                                message: "Missing tool name".to_string(),
-                                data: None,
-                            }),
-                        }
-                    }
-                };
+        Next::new(self, &self.middlewares).run(request, ctx).await
+    }
 
-                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+    /// Handle a request once it has passed every middleware
+    pub(crate) async fn process_request(
+        &self,
+        request: JsonRpcRequest,
+        ctx: RequestContext,
+    ) -> JsonRpcResponse {
+        // A session starts with initialize followed by notifications/initialized,
+        // and only ping is served before both have arrived. Sessionless
+        // requests (direct calls, stateless HTTP) are not tracked and are
+        // always served.
+        if let Some(session_id) = ctx.session_id() {
+            let initialized = self
+                .clients
+                .get(session_id)
+                .map(|client| client.initialized);
+            let refusal = match (initialized, request.method.as_str()) {
+                (_, "ping") | (None, "initialize") | (Some(true), _) => None,
+                (None, _) => Some("Session is not initialized; send 'initialize' first"),
+                (Some(false), _) => {
+                    Some("Session is not ready; send 'notifications/initialized' first")
+                }
+            };
+            if let Some(message) = refusal {
+                let error = Error::InvalidRequest(message.to_string());
+                return JsonRpcResponse::failure(Some(request.id), JsonRpcError::from(&error));
+            }
+        }
 
-                match self.handle_tool_call(name, arguments).await {
-                    Ok(result) => Some(json!(result)),
-                    Err(e) => {
-                        return JsonRpcResponse {
-                            jsonrpc: "2.0".to_string(),
-                            id: request.id.clone(),
-                            result: None,
-                            error: Some(JsonRpcError {
-                                code: e.error_code(),
-                                message: e.to_string(),
-                                data: None,
-                            }),
-                        }
-                    }
+        // Only requests on a session can be named by a later cancellation
+        let _in_flight = ctx.session_id().map(|session_id| {
+            InFlight::register(
+                &self.in_flight,
+                session_id,
+                &request.id,
+                ctx.cancellation_token().clone(),
+            )
+        });
+
+        let id = request.id.clone();
+        let result = tokio::select! {
+            biased;
+            _ = ctx.cancellation_token().cancelled() => {
+                Err(Error::Cancelled(format!("Request {} was cancelled", id)))
+            }
+            result = self.dispatch(request, &ctx) => result,
+        };
+
+        match result {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err(e) => JsonRpcResponse::failure(Some(id), JsonRpcError::from(&e)),
+        }
+    }
+
+    /// Handle a notification from a client. `session_id` names the session
+    /// it arrived on, if the transport has one.
+    pub fn handle_notification(&self, notification: JsonRpcNotification, session_id: Option<&str>) {
+        match notification.method.as_str() {
+            "notifications/initialized" => {
+                if let Some(mut client) = session_id.and_then(|id| self.clients.get_mut(id)) {
+                    client.initialized = true;
                 }
             }
-            "resources/list" => match self.handle_resources_list().await {
-                Ok(resources) => Some(json!({ "resources": resources })),
-                Err(e) => {
-                    return JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id.clone(),
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: e.error_code(),
-                            message: e.to_string(),
-                            data: None,
-                        }),
-                    }
+            "notifications/cancelled" => {
+                let params = notification.params.unwrap_or_default();
+                let request_id = params
+                    .get("requestId")
+                    .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+                let (Some(session_id), Some(request_id)) = (session_id, request_id) else {
+                    return;
+                };
+
+                if let Some(token) = self
+                    .in_flight
+                    .get(&(session_id.to_string(), request_id.clone()))
+                {
+                    tracing::debug!(
+                        request = %request_id,
+                        reason = ?params.get("reason"),
+                        "Client cancelled request"
+                    );
+                    token.cancel();
                 }
-            },
-            "prompts/list" => match self.handle_prompts_list().await {
-                Ok(prompts) => Some(json!({ "prompts": prompts })),
-                Err(e) => {
-                    return JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id.clone(),
-                        result: None,
-                        error: Some(JsonRpcError {
-                            code: e.error_code(),
-                            message: e.to_string(),
-                            data: None,
-                        }),
+            }
+            method => tracing::debug!(method, "Ignoring notification"),
+        }
+    }
+
+    /// Route a request to its handler and build the JSON result
+    async fn dispatch(&self, request: JsonRpcRequest, ctx: &RequestContext) -> Result<Value> {
+        let params = request.params.unwrap_or_else(|| json!({}));
+
+        match request.method.as_str() {
+            "initialize" => {
+                let params: InitializeParams = serde_json::from_value(params).map_err(|e| {
+                    Error::InvalidParams(format!("Invalid initialize params: {}", e))
+                })?;
+                let result = self.handle_initialize(&params).await;
+                tracing::info!(
+                    client = %params.client_info.name,
+                    version = %result.protocol_version,
+                    "Client initialized"
+                );
+
+                if let Some(session_id) = ctx.session_id() {
+                    match self.clients.entry(session_id.to_string()) {
+                        Entry::Occupied(_) => {
+                            return Err(Error::InvalidRequest(
+                                "Session is already initialized".to_string(),
+                            ));
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(ClientSession {
+                                protocol_version: ProtocolVersion::negotiate(
+                                    &params.protocol_version,
+                                ),
+                                client_info: params.client_info,
+                                capabilities: params.capabilities,
+                                initialized: false,
+                                log_level: None,
+                            });
+                        }
                     }
                 }
-            },
-            _ => {
-                return JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id.clone(),
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32601,
-                        message: format!("Method not found: {}", request.method),
-                        data: None,
-                    }),
+                Ok(json!(result))
+            }
+            "ping" => Ok(json!({})),
+            "logging/setLevel" => {
+                let level: LoggingLevel = params
+                    .get("level")
+                    .and_then(|level| serde_json::from_value(level.clone()).ok())
+                    .ok_or_else(|| {
+                        Error::InvalidParams("'level' must be a logging level".to_string())
+                    })?;
+                let mut client = ctx
+                    .session_id()
+                    .and_then(|id| self.clients.get_mut(id))
+                    .ok_or_else(|| {
+                        Error::InvalidRequest("Logging requires a session".to_string())
+                    })?;
+                client.log_level = Some(level);
+                Ok(json!({}))
+            }
+            "tools/list" => {
+                let page = self.paginate(self.handle_tools_list().await?, |t| &t.name, &params)?;
+                Ok(page_result("tools", page))
+            }
+            "tools/call" => {
+                let name = required_str(&params, "name")?;
+                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+                Ok(json!(
+                    self.handle_tool_call_with_context(name, arguments, ctx)
+                        .await?
+                ))
+            }
+            "resources/list" => {
+                let page =
+                    self.paginate(self.handle_resources_list().await?, |r| &r.uri, &params)?;
+                Ok(page_result("resources", page))
+            }
+            "resources/read" => {
+                let uri = required_str(&params, "uri")?;
+                let contents = self.handle_resource_read(uri).await?;
+                Ok(json!(ReadResourceResult { contents }))
+            }
+            "resources/subscribe" => {
+                let uri = required_str(&params, "uri")?;
+                let session = ctx.session().ok_or_else(|| {
+                    Error::InvalidRequest("Subscriptions require a session".to_string())
+                })?;
+                self.subscribe_resource(uri, session.clone());
+                Ok(json!({}))
+            }
+            "resources/unsubscribe" => {
+                let uri = required_str(&params, "uri")?;
+                if let Some(session_id) = ctx.session_id() {
+                    self.unsubscribe_resource(uri, session_id);
                 }
+                Ok(json!({}))
+            }
+            "resources/templates/list" => {
+                let templates = self.handle_resource_templates_list().await?;
+                let page = self.paginate(templates, |t| &t.uri_template, &params)?;
+                Ok(page_result("resourceTemplates", page))
+            }
+            "prompts/list" => {
+                let page =
+                    self.paginate(self.handle_prompts_list().await?, |p| &p.name, &params)?;
+                Ok(page_result("prompts", page))
+            }
+            "prompts/get" => {
+                let name = required_str(&params, "name")?;
+                let arguments = match params.get("arguments") {
+                    None | Some(Value::Null) => HashMap::new(),
+                    Some(arguments) => serde_json::from_value(arguments.clone()).map_err(|_| {
+                        Error::InvalidParams("'arguments' must be an object of strings".to_string())
+                    })?,
+                };
+                Ok(json!(self.handle_prompt_get(name, arguments).await?))
+            }
+            "completion/complete" => {
+                let params: CompleteParams = serde_json::from_value(params).map_err(|e| {
+                    Error::InvalidParams(format!("Invalid completion params: {}", e))
+                })?;
+                let completion = self.handle_complete(params).await?;
+                Ok(json!(CompleteResult { completion }))
+            }
+            method => Err(Error::MethodNotFound(method.to_string())),
+        }
+    }
+
+    /// Cut the page after the request's `cursor` out of `items`, ordered by
+    /// `key`. The cursor encodes the key of the last item served, so paging
+    /// stays consistent while items are added or removed.
+    fn paginate<T>(
+        &self,
+        mut items: Vec<T>,
+        key: impl Fn(&T) -> &String,
+        params: &Value,
+    ) -> Result<Page<T>> {
+        items.sort_by(|a, b| key(a).cmp(key(b)));
+
+        if let Some(cursor) = params.get("cursor").filter(|c| !c.is_null()) {
+            let after = cursor
+                .as_str()
+                .and_then(|c| URL_SAFE_NO_PAD.decode(c).ok())
+                .and_then(|c| String::from_utf8(c).ok())
+                .ok_or_else(|| Error::InvalidParams("Invalid cursor".to_string()))?;
+            items.retain(|item| *key(item) > after);
+        }
+
+        let next_cursor = match self.config.page_size.filter(|size| *size > 0) {
+            Some(size) if items.len() > size => {
+                items.truncate(size);
+                items.last().map(|last| URL_SAFE_NO_PAD.encode(key(last)))
             }
+            _ => None,
         };
+        Ok(Page { items, next_cursor })
+    }
+}
 
-        JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id: request.id,
-            result,
-            error: None,
+/// Result reporting that a tool call failed during execution. The content
+/// of an [`Error::ToolError`] is passed on as is.
+fn error_result(error: Error) -> ToolResult {
+    let content = match error {
+        Error::ToolError(content) => content,
+        error => vec![ResultContent::text(error.to_string())],
+    };
+    ToolResult {
+        id: Some(uuid::Uuid::new_v4().to_string()),
+        content,
+        structured_content: None,
+        is_error: Some(true),
+    }
+}
+
+/// Expire idle sessions until the server is dropped, checking twice per
+/// timeout
+fn spawn_session_reaper(server: Weak<McpServer>, timeout: Duration) {
+    tokio::spawn(async move {
+        let period = (timeout / 2).max(Duration::from_millis(100));
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            ticks.tick().await;
+            let Some(server) = server.upgrade() else {
+                break;
+            };
+            server.expire_idle_sessions();
+        }
+    });
+}
+
+/// Build a list result, e.g. `{ "tools": [...], "nextCursor": "..." }`
+fn page_result<T: serde::Serialize>(key: &str, page: Page<T>) -> Value {
+    let mut result = json!({ key: page.items });
+    if let Some(cursor) = page.next_cursor {
+        result["nextCursor"] = json!(cursor);
+    }
+    result
+}
+
+/// Registration of an in-flight request, removed when the request finishes
+struct InFlight {
+    requests: InFlightRequests,
+    key: (String, RequestId),
+}
+
+impl InFlight {
+    fn register(
+        requests: &InFlightRequests,
+        session_id: &str,
+        request_id: &RequestId,
+        token: CancellationToken,
+    ) -> Self {
+        let key = (session_id.to_string(), request_id.clone());
+        requests.insert(key.clone(), token);
+        Self {
+            requests: requests.clone(),
+            key,
         }
     }
 }
 
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.requests.remove(&self.key);
+    }
+}
+
+/// Read a required string parameter
+fn required_str<'a>(params: &'a Value, key: &str) -> Result<&'a str> {
+    params
+        .get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| Error::InvalidParams(format!("Missing '{}'", key)))
+}
+
+/// Substitute `{{name}}` placeholders in a prompt message
+fn render_prompt_message(
+    message: &PromptMessage,
+    arguments: &HashMap<String, String>,
+) -> PromptMessage {
+    let content = match &message.content {
+        PromptContent::Text { text } => PromptContent::Text {
+            text: substitute(text, arguments),
+        },
+        PromptContent::Resource {
+            resource:
+                ResourceContents::Text {
+                    uri,
+                    mime_type,
+                    text,
+                },
+        } => PromptContent::Resource {
+            resource: ResourceContents::Text {
+                uri: uri.clone(),
+                mime_type: mime_type.clone(),
+                text: substitute(text, arguments),
+            },
+        },
+        content => content.clone(),
+    };
+    PromptMessage {
+        role: message.role,
+        content,
+    }
+}
+
+fn substitute(template: &str, arguments: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        if let Some(value) = arguments.get(name) {
+            output.push_str(value);
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rmcp::model::AnnotateAble;
 
     struct TestToolHandler;
 
     #[async_trait]
     impl ToolHandler for TestToolHandler {
         async fn execute(&self, _name: &str, _arguments: Value) -> Result<Vec<ResultContent>> {
-            Ok(vec![ResultContent::Text {
-                text: "test result".to_string(),
-            }])
+            Ok(vec![ResultContent::text("test result")])
         }
     }
 
@@ -318,15 +1809,1249 @@ mod tests {
 
         let tool = Tool {
             name: "test_tool".to_string(),
+            title: None,
             description: Some("A test tool".to_string()),
             input_schema: Some(ToolInputSchema {
                 schema_type: "object".to_string(),
                 properties: Default::default(),
                 required: None,
+                defs: None,
             }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
         };
 
         server.register_tool(tool);
         assert!(server.tools.contains_key("test_tool"));
     }
+
+    #[tokio::test]
+    async fn test_tool_call_validates_arguments() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let mut properties = std::collections::HashMap::new();
+        properties.insert("a".to_string(), json!({ "type": "number" }));
+        properties.insert(
+            "tags".to_string(),
+            json!({ "type": "array", "items": { "type": "string" } }),
+        );
+        server.register_tool(Tool {
+            name: "add".to_string(),
+            title: None,
+            description: None,
+            input_schema: Some(ToolInputSchema {
+                schema_type: "object".to_string(),
+                properties,
+                required: Some(vec!["a".to_string()]),
+                defs: None,
+            }),
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        });
+
+        let call = |arguments: Value| {
+            JsonRpcRequest::new(
+                "tools/call",
+                Some(json!({ "name": "add", "arguments": arguments })),
+            )
+        };
+
+        let response = server
+            .handle_request(call(json!({ "a": 1, "tags": ["x"] })))
+            .await;
+        assert!(response.error.is_none());
+
+        let response = server
+            .handle_request(call(json!({ "tags": ["x", 2] })))
+            .await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        let mut paths: Vec<_> = error.data.unwrap()["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/a", "/tags/1"]);
+    }
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    struct AddArgs {
+        a: f64,
+        b: f64,
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.tool("add", "Add numbers", |args: AddArgs| async move {
+            Ok(format!("{}", args.a + args.b))
+        });
+
+        let tools = server.handle_tools_list().await.unwrap();
+        let schema = tools[0].input_schema.as_ref().unwrap();
+        assert_eq!(schema.properties["a"]["type"], "number");
+        assert_eq!(
+            schema.required,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let result = server
+            .handle_tool_call("add", json!({ "a": 2, "b": 3.5 }))
+            .await
+            .unwrap();
+        assert!(matches!(&result.content[0], ResultContent::Text { text, .. } if text == "5.5"));
+
+        let error = server
+            .handle_tool_call("add", json!({ "a": "2" }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.error_code(), -32602);
+    }
+
+    #[derive(serde::Serialize, schemars::JsonSchema)]
+    struct Sum {
+        total: f64,
+    }
+
+    #[tokio::test]
+    async fn test_structured_output() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.structured_tool("sum", "Add numbers", |args: AddArgs| async move {
+            Ok(Sum {
+                total: args.a + args.b,
+            })
+        });
+        // Claims an output schema it does not honor
+        let mut liar = ToolDefinition::typed("liar", "Add numbers", |args: AddArgs| async move {
+            Ok(Structured(
+                json!({ "total": format!("{}", args.a + args.b) }),
+            ))
+        });
+        liar.tool.output_schema = Some(ToolInputSchema::from_type::<Sum>());
+        server.register(liar);
+
+        let tools = server
+            .handle_request(JsonRpcRequest::new("tools/list", None))
+            .await
+            .result
+            .unwrap();
+        let sum = tools["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["name"] == "sum")
+            .unwrap();
+        assert_eq!(sum["outputSchema"]["properties"]["total"]["type"], "number");
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(json!({ "name": "sum", "arguments": { "a": 1, "b": 2 } })),
+        );
+        let result = server.handle_request(call).await.result.unwrap();
+        assert_eq!(result["structuredContent"], json!({ "total": 3.0 }));
+        assert_eq!(result["content"][0]["text"], "{\"total\":3.0}");
+
+        let result = server
+            .handle_tool_call("liar", json!({ "a": 1, "b": 2 }))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.structured_content.is_none());
+        assert!(
+            matches!(&result.content[0], ResultContent::Text { text, .. } if text.contains("output schema"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_annotations_listed() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.register(
+            ToolDefinition::typed("add", "Add numbers", |args: AddArgs| async move {
+                Ok((args.a + args.b).to_string())
+            })
+            .with_title("Add")
+            .with_annotations(ToolAnnotations::new().read_only(true).open_world(false))
+            .with_meta(json!({ "example.com/cost": 0 })),
+        );
+
+        let result = server
+            .handle_request(JsonRpcRequest::new("tools/list", None))
+            .await
+            .result
+            .unwrap();
+        let tool = &result["tools"][0];
+        assert_eq!(tool["title"], "Add");
+        assert_eq!(
+            tool["annotations"],
+            json!({ "readOnlyHint": true, "openWorldHint": false })
+        );
+        assert_eq!(tool["_meta"]["example.com/cost"], 0);
+
+        let tool: Tool = serde_json::from_value(tool.clone()).unwrap();
+        assert!(tool.is_read_only());
+    }
+
+    #[tokio::test]
+    async fn test_typed_tools_alongside_fallback_handler() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        server.register_tool(Tool {
+            name: "legacy".to_string(),
+            title: None,
+            description: None,
+            input_schema: None,
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        });
+        server.tool("add", "Add numbers", |args: AddArgs| async move {
+            Ok(ResultContent::text((args.a + args.b).to_string()))
+        });
+
+        let legacy = server.handle_tool_call("legacy", json!({})).await.unwrap();
+        assert!(
+            matches!(&legacy.content[0], ResultContent::Text { text, .. } if text == "test result")
+        );
+        let add = server
+            .handle_tool_call("add", json!({ "a": 1, "b": 1 }))
+            .await
+            .unwrap();
+        assert!(matches!(&add.content[0], ResultContent::Text { text, .. } if text == "2"));
+
+        let bare = McpServer::with_config(ServerConfig::default());
+        bare.register_tool(Tool {
+            name: "legacy".to_string(),
+            title: None,
+            description: None,
+            input_schema: None,
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        });
+        assert!(bare.handle_tool_call("legacy", json!({})).await.is_err());
+    }
+
+    /// Multiply two numbers
+    #[crate::mcp_tool]
+    async fn multiply(a: f64, b: f64) -> Result<String> {
+        Ok((a * b).to_string())
+    }
+
+    /// Count to `n`, reporting each step
+    #[crate::mcp_tool]
+    async fn count(n: u32, ctx: &RequestContext) -> Result<String> {
+        for i in 1..=n {
+            ctx.report_progress(i as f64, Some(n as f64), None);
+        }
+        Ok(n.to_string())
+    }
+
+    /// Session the call came in on
+    #[crate::mcp_tool(read_only)]
+    async fn session(ctx: RequestContext) -> Result<String> {
+        Ok(ctx.session_id().unwrap_or("none").to_string())
+    }
+
+    /// Remove a file
+    #[crate::mcp_tool(
+        title = "Remove File",
+        destructive,
+        idempotent = true,
+        open_world = false
+    )]
+    async fn remove(path: String) -> Result<String> {
+        Ok(path)
+    }
+
+    struct Greeter {
+        greeting: String,
+    }
+
+    #[crate::mcp_server]
+    impl Greeter {
+        /// Greet someone
+        #[mcp_tool]
+        async fn greet(&self, name: String, excited: Option<bool>) -> Result<String> {
+            let mark = if excited.unwrap_or(false) { "!" } else { "." };
+            Ok(format!("{}, {}{}", self.greeting, name, mark))
+        }
+
+        #[mcp_tool(name = "server_time", description = "Current server time")]
+        async fn now() -> Result<String> {
+            Ok("12:00".to_string())
+        }
+
+        #[allow(dead_code)]
+        fn not_a_tool(&self) {}
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tool_macro() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.register(multiply_tool());
+
+        let tools = server.handle_tools_list().await.unwrap();
+        assert_eq!(tools[0].name, "multiply");
+        assert_eq!(
+            tools[0].description.as_deref(),
+            Some("Multiply two numbers")
+        );
+        assert_eq!(
+            tools[0].input_schema.as_ref().unwrap().properties["b"]["type"],
+            "number"
+        );
+
+        let result = server
+            .handle_tool_call("multiply", json!({ "a": 3, "b": 4 }))
+            .await
+            .unwrap();
+        assert!(matches!(&result.content[0], ResultContent::Text { text, .. } if text == "12"));
+
+        // The context parameter is not part of the input schema
+        server.register(count_tool());
+        let schema = server
+            .tools
+            .get("count")
+            .unwrap()
+            .tool
+            .input_schema
+            .clone()
+            .unwrap();
+        assert_eq!(schema.required, Some(vec!["n".to_string()]));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let ctx = RequestContext::new("1".to_string())
+            .with_outbound(tx)
+            .with_progress_token("p".into());
+        server
+            .handle_tool_call_with_context("count", json!({ "n": 3 }), &ctx)
+            .await
+            .unwrap();
+        drop(ctx);
+        let mut updates = 0;
+        while rx.recv().await.is_some() {
+            updates += 1;
+        }
+        assert_eq!(updates, 3);
+
+        let remove = remove_tool().tool;
+        assert_eq!(remove.title.as_deref(), Some("Remove File"));
+        assert_eq!(
+            remove.annotations,
+            Some(
+                ToolAnnotations::new()
+                    .destructive(true)
+                    .idempotent(true)
+                    .open_world(false)
+            )
+        );
+        assert!(multiply_tool().tool.annotations.is_none());
+
+        // A context taken by value works the same
+        server.register(session_tool());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let ctx = RequestContext::new("2".to_string()).with_session(SessionHandle::new("s1", tx));
+        let result = server
+            .handle_tool_call_with_context("session", json!({}), &ctx)
+            .await
+            .unwrap();
+        assert!(matches!(&result.content[0], ResultContent::Text { text, .. } if text == "s1"));
+        assert_eq!(
+            session_tool().tool.annotations,
+            Some(ToolAnnotations::new().read_only(true))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mcp_server_macro() {
+        let server = McpServer::with_config(ServerConfig::default());
+        Arc::new(Greeter {
+            greeting: "Hello".to_string(),
+        })
+        .register_tools(&server);
+
+        let mut names: Vec<_> = server
+            .handle_tools_list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["greet", "server_time"]);
+
+        let result = server
+            .handle_tool_call("greet", json!({ "name": "Ada", "excited": true }))
+            .await
+            .unwrap();
+        assert!(
+            matches!(&result.content[0], ResultContent::Text { text, .. } if text == "Hello, Ada!")
+        );
+
+        let tool = server.tools.get("greet").unwrap();
+        assert_eq!(
+            tool.tool.input_schema.as_ref().unwrap().required,
+            Some(vec!["name".to_string()])
+        );
+        assert_eq!(
+            server
+                .tools
+                .get("server_time")
+                .unwrap()
+                .tool
+                .description
+                .as_deref(),
+            Some("Current server time")
+        );
+    }
+
+    fn initialize_request(version: &str) -> JsonRpcRequest {
+        JsonRpcRequest::new(
+            "initialize",
+            Some(json!({
+                "protocolVersion": version,
+                "capabilities": { "sampling": {} },
+                "clientInfo": { "name": "test-client", "version": "0.1.0" }
+            })),
+        )
+    }
+
+    async fn initialize_session(server: &McpServer, session: &SessionHandle, capabilities: Value) {
+        let mut request = initialize_request(MCP_VERSION);
+        request.params.as_mut().unwrap()["capabilities"] = capabilities;
+        let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+        assert!(server
+            .handle_request_with_context(request, ctx)
+            .await
+            .error
+            .is_none());
+        server.handle_notification(
+            JsonRpcNotification::new("notifications/initialized", None),
+            Some(session.id()),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_version() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+
+        let request = initialize_request("2025-03-26");
+        let id = request.id.clone();
+        let response = server.handle_request(request).await;
+        assert_eq!(response.id, Some(id));
+        let result = response.result.unwrap();
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+        assert_eq!(result["serverInfo"]["name"], "MCP Server");
+
+        // Unknown versions are answered with the latest one
+        let response = server
+            .handle_request(initialize_request("1999-01-01"))
+            .await;
+        assert_eq!(response.result.unwrap()["protocolVersion"], MCP_VERSION);
+
+        let response = server
+            .handle_request(JsonRpcRequest::new("initialize", None))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        let send = |request: JsonRpcRequest| {
+            let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+            server.handle_request_with_context(request, ctx)
+        };
+
+        // Only ping is served before initialize
+        assert!(send(JsonRpcRequest::new("ping", None))
+            .await
+            .error
+            .is_none());
+        let response = send(JsonRpcRequest::new("tools/list", None)).await;
+        assert_eq!(response.error.unwrap().code, -32600);
+
+        assert!(send(initialize_request("2024-11-05")).await.error.is_none());
+        let client = server.client_session("s1").unwrap();
+        assert_eq!(client.protocol_version, ProtocolVersion::V2024_11_05);
+        assert_eq!(client.client_info.name, "test-client");
+        assert!(client.capabilities.sampling.is_some());
+        assert!(!client.initialized);
+
+        // Then nothing but ping until the client confirms with initialized
+        let response = send(JsonRpcRequest::new("tools/list", None)).await;
+        assert_eq!(response.error.unwrap().code, -32600);
+        assert!(send(JsonRpcRequest::new("ping", None))
+            .await
+            .error
+            .is_none());
+
+        server.handle_notification(
+            JsonRpcNotification::new("notifications/initialized", None),
+            Some("s1"),
+        );
+        assert!(server.client_session("s1").unwrap().initialized);
+        assert!(send(JsonRpcRequest::new("tools/list", None))
+            .await
+            .error
+            .is_none());
+
+        // A session is initialized once
+        let response = send(initialize_request("2024-11-05")).await;
+        assert_eq!(response.error.unwrap().code, -32600);
+
+        server.end_session("s1");
+        assert!(server.client_session("s1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handlers_see_session_info() {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        server.tool_with_context(
+            "visit",
+            "Count visits",
+            |_: Value, ctx: RequestContext| async move {
+                let session = ctx
+                    .session_info()
+                    .ok_or_else(|| Error::tool("No session"))?;
+                let visits = session.state.get::<u32>("visits").unwrap_or(0) + 1;
+                session.state.set("visits", visits)?;
+                let client = session
+                    .client
+                    .as_ref()
+                    .map_or("unknown", |client| client.client_info.name.as_str());
+                Ok(format!(
+                    "visit {} from {} over {:?}",
+                    visits, client, session.transport
+                ))
+            },
+        );
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        server.open_session(&session, TransportKind::Stdio);
+        initialize_session(&server, &session, json!({})).await;
+        let call = || {
+            let request = JsonRpcRequest::new(
+                "tools/call",
+                Some(json!({ "name": "visit", "arguments": {} })),
+            );
+            let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+            server.handle_request_with_context(request, ctx)
+        };
+
+        call().await;
+        let response = call().await;
+        assert_eq!(
+            response.result.unwrap()["content"][0]["text"],
+            "visit 2 from test-client over Stdio"
+        );
+        assert_eq!(server.sessions().len(), 1);
+
+        server.end_session("s1");
+        assert!(server.sessions().is_empty());
+        let response = server.handle_tool_call("visit", json!({})).await.unwrap();
+        assert_eq!(response.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_become_error_results() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.tool("fetch", "Fetch pages", |args: CrawlArgs| async move {
+            match args.pages {
+                0 => Err::<String, _>(Error::InternalError("upstream unavailable".to_string())),
+                _ => Err(Error::ToolError(vec![
+                    ResultContent::text("Rate limited"),
+                    ResultContent::text("Retry in 30s"),
+                ])),
+            }
+        });
+
+        let result = server
+            .handle_tool_call("fetch", json!({ "pages": 0 }))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(
+            matches!(&result.content[0], ResultContent::Text { text, .. } if text.contains("upstream unavailable"))
+        );
+
+        let response = server
+            .handle_request(JsonRpcRequest::new(
+                "tools/call",
+                Some(json!({ "name": "fetch", "arguments": { "pages": 2 } })),
+            ))
+            .await;
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["content"][1]["text"], "Retry in 30s");
+
+        // Unknown tools and bad arguments are still protocol errors
+        assert!(matches!(
+            server.handle_tool_call("missing", json!({})).await,
+            Err(Error::ToolNotFound(_))
+        ));
+        assert!(matches!(
+            server
+                .handle_tool_call("fetch", json!({ "pages": "many" }))
+                .await,
+            Err(Error::InvalidArguments(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tool_panics_become_error_results() {
+        let server = McpServer::with_config(ServerConfig::default());
+        server.tool("explode", "Always panics", |_: CrawlArgs| async move {
+            if true {
+                panic!("boom");
+            }
+            Ok("unreachable")
+        });
+
+        let result = server
+            .handle_tool_call("explode", json!({ "pages": 1 }))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(
+            matches!(&result.content[0], ResultContent::Text { text, .. } if text.contains("boom"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_limits() {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        let slow = |name: &str| {
+            ToolDefinition::typed(name.to_string(), "Sleeps", |args: CrawlArgs| async move {
+                tokio::time::sleep(Duration::from_millis(args.pages as u64)).await;
+                Ok("done")
+            })
+        };
+        server.register(
+            slow("timed").with_limits(ToolLimits::new().timeout(Duration::from_millis(20))),
+        );
+        server.register(
+            slow("exclusive").with_limits(
+                ToolLimits::new()
+                    .max_concurrency(1)
+                    .overload(OverloadPolicy::Reject),
+            ),
+        );
+        server.register(slow("queued").with_limits(ToolLimits::new().max_concurrency(1)));
+
+        let result = server
+            .handle_tool_call("timed", json!({ "pages": 1000 }))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(server
+            .handle_tool_call("timed", json!({ "pages": 1 }))
+            .await
+            .unwrap()
+            .is_error
+            .is_none());
+
+        // Over the limit, "exclusive" rejects the second call and "queued" waits for the first
+        for (tool, rejected) in [("exclusive", true), ("queued", false)] {
+            let first = tokio::spawn({
+                let server = server.clone();
+                async move { server.handle_tool_call(tool, json!({ "pages": 50 })).await }
+            });
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let second = server
+                .handle_tool_call(tool, json!({ "pages": 1 }))
+                .await
+                .unwrap();
+            assert_eq!(second.is_error.is_some(), rejected, "{}", tool);
+            assert!(first.await.unwrap().unwrap().is_error.is_none());
+        }
+
+        assert!(!server.set_tool_limits("missing", ToolLimits::new()));
+        assert!(server.set_tool_limits("exclusive", ToolLimits::new()));
+    }
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    struct SummarizeArgs {
+        text: String,
+    }
+
+    #[tokio::test]
+    async fn test_create_message_round_trip() {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        server.tool_with_context(
+            "summarize",
+            "Summarize text",
+            |args: SummarizeArgs, ctx: RequestContext| async move {
+                let params = CreateMessageParams::new(vec![SamplingMessage::user(args.text)], 50);
+                match ctx.create_message(params).await?.content {
+                    SamplingContent::Text { text, .. } => Ok(text),
+                    _ => Err(Error::tool("Expected a text summary")),
+                }
+            },
+        );
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        initialize_session(&server, &session, json!({ "sampling": {} })).await;
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(json!({ "name": "summarize", "arguments": { "text": "long" } })),
+        );
+        let ctx = RequestContext::new(call.id.clone()).with_session(session.clone());
+        let task = tokio::spawn({
+            let server = server.clone();
+            async move { server.handle_request_with_context(call, ctx).await }
+        });
+
+        let request = rx.recv().await.unwrap();
+        assert_eq!(request["method"], "sampling/createMessage");
+        assert_eq!(request["params"]["messages"][0]["content"]["text"], "long");
+
+        // Only the session the request went to may answer it
+        let id: RequestId = serde_json::from_value(request["id"].clone()).unwrap();
+        let result = json!({ "role": "assistant", "content": { "type": "text", "text": "short" }, "model": "m" });
+        server.handle_response(
+            JsonRpcResponse::success(id.clone(), result.clone()),
+            Some("s2"),
+        );
+        server.handle_response(JsonRpcResponse::success(id, result), Some("s1"));
+
+        let response = task.await.unwrap();
+        assert_eq!(response.result.unwrap()["content"][0]["text"], "short");
+
+        // Without a session there is no sampling capability to use
+        let result = server
+            .handle_tool_call("summarize", json!({ "text": "long" }))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_elicit_validates_answers() {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        server.tool_with_context(
+            "deploy",
+            "Deploy a branch",
+            |_: Value, ctx: RequestContext| async move {
+                let schema = json!({
+                    "type": "object",
+                    "properties": { "branch": { "type": "string" } },
+                    "required": ["branch"]
+                });
+                let answer = ctx
+                    .elicit(ElicitRequestParams::new("Which branch?", schema))
+                    .await?;
+                match (answer.action, answer.content) {
+                    (ElicitAction::Accept, Some(content)) => {
+                        Ok(format!("Deployed {}", content["branch"]))
+                    }
+                    (action, _) => Ok(format!("Not deployed: {:?}", action)),
+                }
+            },
+        );
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        initialize_session(&server, &session, json!({ "elicitation": {} })).await;
+
+        for (answer, expected) in [
+            (
+                json!({ "action": "accept", "content": { "branch": "main" } }),
+                "Deployed \"main\"",
+            ),
+            (json!({ "action": "decline" }), "Not deployed: Decline"),
+            (
+                json!({ "action": "accept", "content": { "branch": 7 } }),
+                "does not match",
+            ),
+        ] {
+            let call = JsonRpcRequest::new(
+                "tools/call",
+                Some(json!({ "name": "deploy", "arguments": {} })),
+            );
+            let ctx = RequestContext::new(call.id.clone()).with_session(session.clone());
+            let task = tokio::spawn({
+                let server = server.clone();
+                async move { server.handle_request_with_context(call, ctx).await }
+            });
+
+            let request = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+                .await
+                .expect("no elicitation/create request was sent")
+                .unwrap();
+            assert_eq!(request["method"], "elicitation/create");
+            let id: RequestId = serde_json::from_value(request["id"].clone()).unwrap();
+            server.handle_response(JsonRpcResponse::success(id, answer), Some("s1"));
+
+            let result = task.await.unwrap().result.unwrap();
+            assert!(
+                result["content"][0]["text"]
+                    .as_str()
+                    .unwrap()
+                    .contains(expected),
+                "{}",
+                result
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_pagination() {
+        let config = ServerConfig {
+            page_size: Some(2),
+            ..ServerConfig::default()
+        };
+        let server = McpServer::new(config, Arc::new(TestToolHandler));
+        for name in ["d", "b", "e", "a", "c"] {
+            server.register_tool(Tool {
+                name: name.to_string(),
+                title: None,
+                description: None,
+                input_schema: None,
+                output_schema: None,
+                annotations: None,
+                meta: None,
+            });
+        }
+
+        let mut names = Vec::new();
+        let mut cursor = None;
+        loop {
+            let params = cursor.map(|cursor: Value| json!({ "cursor": cursor }));
+            let result = server
+                .handle_request(JsonRpcRequest::new("tools/list", params))
+                .await
+                .result
+                .unwrap();
+            let page: Vec<Tool> = serde_json::from_value(result["tools"].clone()).unwrap();
+            assert!(page.len() <= 2);
+            names.extend(page.into_iter().map(|t| t.name));
+            match result.get("nextCursor") {
+                Some(next) => cursor = Some(next.clone()),
+                None => break,
+            }
+        }
+        assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+
+        let params = json!({ "cursor": "not a cursor!" });
+        let response = server
+            .handle_request(JsonRpcRequest::new("tools/list", Some(params)))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_list_changed_notifications() {
+        let config = ServerConfig {
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(true),
+                }),
+                resources: None,
+                prompts: None,
+                logging: None,
+                completions: None,
+            },
+            ..ServerConfig::default()
+        };
+        let server = McpServer::new(config, Arc::new(TestToolHandler));
+        let mut notifications = server.notifications();
+        let tool = |name: &str| Tool {
+            name: name.to_string(),
+            title: None,
+            description: None,
+            input_schema: None,
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        };
+
+        // Registrations during startup are not announced
+        server.register_tool(tool("startup"));
+        assert!(notifications.try_recv().is_err());
+
+        // Nor are they while the only session has not confirmed it is ready
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        let request = initialize_request(MCP_VERSION);
+        let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+        server.handle_request_with_context(request, ctx).await;
+        assert!(!server.session_ready("s1"));
+        server.register_tool(tool("early"));
+        assert!(notifications.try_recv().is_err());
+
+        server.handle_notification(
+            JsonRpcNotification::new("notifications/initialized", None),
+            Some("s1"),
+        );
+        assert!(server.session_ready("s1"));
+        server.register_tool(tool("added"));
+        let message = notifications.try_recv().unwrap();
+        assert_eq!(message["method"], "notifications/tools/list_changed");
+
+        assert!(server.unregister_tool("startup").is_some());
+        assert!(notifications.try_recv().is_ok());
+        assert!(server.unregister_tool("startup").is_none());
+        assert!(notifications.try_recv().is_err());
+
+        // Prompts do not advertise listChanged
+        server.register_prompt(code_review_prompt());
+        assert!(notifications.try_recv().is_err());
+
+        let mut names: Vec<_> = server
+            .handle_tools_list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["added", "early"]);
+    }
+
+    #[tokio::test]
+    async fn test_resource_subscriptions() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        initialize_session(&server, &session, json!({})).await;
+        let subscribe = |method: &str| {
+            let request = JsonRpcRequest::new(method, Some(json!({ "uri": "file:///log.txt" })));
+            let ctx = RequestContext::new(request.id.clone()).with_session(session.clone());
+            server.handle_request_with_context(request, ctx)
+        };
+
+        assert!(subscribe("resources/subscribe").await.error.is_none());
+        assert!(subscribe("resources/subscribe").await.error.is_none());
+        assert_eq!(server.notify_resource_updated("file:///log.txt"), 1);
+        assert_eq!(server.notify_resource_updated("file:///other.txt"), 0);
+
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message["method"], "notifications/resources/updated");
+        assert_eq!(message["params"]["uri"], "file:///log.txt");
+        assert!(rx.try_recv().is_err());
+
+        assert!(subscribe("resources/unsubscribe").await.error.is_none());
+        assert_eq!(server.notify_resource_updated("file:///log.txt"), 0);
+
+        // Stateless requests cannot subscribe
+        let response = server
+            .handle_request(JsonRpcRequest::new(
+                "resources/subscribe",
+                Some(json!({ "uri": "file:///log.txt" })),
+            ))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32600);
+    }
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    struct CrawlArgs {
+        pages: u32,
+    }
+
+    #[tokio::test]
+    async fn test_progress_and_cancellation() {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        server.tool_with_context(
+            "crawl",
+            "Crawl some pages",
+            |args: CrawlArgs, ctx: RequestContext| async move {
+                ctx.report_progress(1.0, Some(args.pages as f64), Some("started"));
+                ctx.cancellation_token().cancelled().await;
+                Ok("unreachable")
+            },
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = SessionHandle::new("s1", tx);
+        initialize_session(&server, &session, json!({})).await;
+        let request = JsonRpcRequest::new(
+            "tools/call",
+            Some(
+                json!({ "name": "crawl", "arguments": { "pages": 5 }, "_meta": { "progressToken": 7 } }),
+            ),
+        );
+        let request_id = request.id.clone();
+        let ctx = RequestContext::new(request.id.clone()).with_session(session);
+        let call = tokio::spawn({
+            let server = server.clone();
+            async move { server.handle_request_with_context(request, ctx).await }
+        });
+
+        let progress = rx.recv().await.unwrap();
+        assert_eq!(progress["method"], "notifications/progress");
+        assert_eq!(progress["params"]["progressToken"], 7);
+        assert_eq!(progress["params"]["total"], 5.0);
+
+        let cancel = JsonRpcNotification::new(
+            "notifications/cancelled",
+            Some(json!({ "requestId": request_id })),
+        );
+        server.handle_notification(cancel, Some("s1"));
+
+        let response = call.await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32800);
+        assert!(server.in_flight.is_empty());
+    }
+
+    fn read_request(uri: &str) -> JsonRpcRequest {
+        JsonRpcRequest::new("resources/read", Some(json!({ "uri": uri })))
+    }
+
+    #[tokio::test]
+    async fn test_resource_read_static_contents() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        server.register_resource_with_contents(
+            rmcp::model::RawResource::new("file:///readme.md", "readme").no_annotation(),
+            vec![
+                ResourceContents::text("file:///readme.md", "# Hello")
+                    .with_mime_type("text/markdown"),
+                ResourceContents::blob("file:///readme.md", "aGk=", "application/octet-stream"),
+            ],
+        );
+
+        let response = server
+            .handle_request(read_request("file:///readme.md"))
+            .await;
+        let contents = &response.result.unwrap()["contents"];
+        assert_eq!(contents[0]["text"], "# Hello");
+        assert_eq!(contents[0]["mimeType"], "text/markdown");
+        assert_eq!(contents[1]["blob"], "aGk=");
+    }
+
+    fn table_template() -> ResourceTemplate {
+        RawResourceTemplate {
+            uri_template: "db://tables/{name}/schema".to_string(),
+            name: "table_schema".to_string(),
+            title: None,
+            description: Some("Schema of a table".to_string()),
+            mime_type: Some("application/sql".to_string()),
+        }
+        .no_annotation()
+    }
+
+    #[tokio::test]
+    async fn test_resource_template_routing() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        server
+            .register_resource_template(
+                table_template(),
+                Arc::new(|uri: String, vars: HashMap<String, String>| async move {
+                    Ok(vec![ResourceContents::text(
+                        uri,
+                        format!("CREATE TABLE {} ();", vars["name"]),
+                    )])
+                }),
+            )
+            .unwrap();
+
+        let response = server
+            .handle_request(JsonRpcRequest::new("resources/templates/list", None))
+            .await;
+        let templates = &response.result.unwrap()["resourceTemplates"];
+        assert_eq!(templates[0]["uriTemplate"], "db://tables/{name}/schema");
+        assert_eq!(templates[0]["mimeType"], "application/sql");
+
+        let response = server
+            .handle_request(read_request("db://tables/users/schema"))
+            .await;
+        let contents = &response.result.unwrap()["contents"];
+        assert_eq!(contents[0]["uri"], "db://tables/users/schema");
+        assert_eq!(contents[0]["text"], "CREATE TABLE users ();");
+
+        let response = server
+            .handle_request(read_request("db://tables/users/rows"))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32002);
+    }
+
+    #[tokio::test]
+    async fn test_register_invalid_resource_template() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let mut template = table_template();
+        template.raw.uri_template = "db://tables/{name".to_string();
+
+        let handler =
+            Arc::new(|_uri: String, _vars: HashMap<String, String>| async move { Ok(vec![]) });
+        assert!(server
+            .register_resource_template(template, handler)
+            .is_err());
+    }
+
+    fn code_review_prompt() -> Prompt {
+        serde_json::from_value(json!({
+            "name": "code_review",
+            "description": "Review a piece of code",
+            "arguments": [
+                { "name": "language", "required": true },
+                { "name": "focus", "required": false }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn get_prompt_request(arguments: Value) -> JsonRpcRequest {
+        JsonRpcRequest::new(
+            "prompts/get",
+            Some(json!({ "name": "code_review", "arguments": arguments })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_prompt_get_renders_messages() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        server.register_prompt_with_messages(
+            code_review_prompt(),
+            vec![
+                PromptMessage::user("Review this {{language}} code. Focus: {{ focus }}."),
+                PromptMessage::resource(
+                    Role::User,
+                    ResourceContents::text("file:///main.rs", "// {{language}}"),
+                ),
+                PromptMessage::image(Role::User, "aGk=", "image/png"),
+            ],
+        );
+
+        let response = server
+            .handle_request(get_prompt_request(json!({ "language": "Rust" })))
+            .await;
+        let result: GetPromptResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.description.as_deref(),
+            Some("Review a piece of code")
+        );
+        assert!(
+            matches!(&result.messages[0].content, PromptContent::Text { text } if text == "Review this Rust code. Focus: .")
+        );
+        assert!(matches!(
+            &result.messages[1].content,
+            PromptContent::Resource { resource } if resource.as_text() == Some("// Rust")
+        ));
+        assert!(matches!(
+            &result.messages[2].content,
+            PromptContent::Image { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_get_validates_arguments() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        server.register_prompt_with_messages(
+            code_review_prompt(),
+            vec![PromptMessage::user("{{language}}")],
+        );
+
+        let response = server
+            .handle_request(get_prompt_request(json!({ "focus": "safety" })))
+            .await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("language"));
+
+        let response = server
+            .handle_request(get_prompt_request(json!({ "language": 1 })))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32602);
+
+        let response = server
+            .handle_request(JsonRpcRequest::new(
+                "prompts/get",
+                Some(json!({ "name": "missing" })),
+            ))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_completion() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let initialized = server
+            .handle_request(initialize_request(MCP_VERSION))
+            .await
+            .result
+            .unwrap();
+        assert!(initialized["capabilities"].get("completions").is_none());
+
+        server.register_prompt_completion(
+            "code_review",
+            "language",
+            Arc::new(
+                |value: String, _context: HashMap<String, String>| async move {
+                    Ok(["python", "perl", "php", "rust"]
+                        .into_iter()
+                        .filter(|language| language.starts_with(&value))
+                        .map(str::to_string)
+                        .collect())
+                },
+            ),
+        );
+        server.register_resource_completion(
+            "db://tables/{name}/schema",
+            "name",
+            Arc::new(
+                |value: String, context: HashMap<String, String>| async move {
+                    let schema = context.get("schema").cloned().unwrap_or_default();
+                    Ok((0..150)
+                        .map(|i| format!("{}{}_{}", schema, value, i))
+                        .collect())
+                },
+            ),
+        );
+        let initialized = server
+            .handle_request(initialize_request(MCP_VERSION))
+            .await
+            .result
+            .unwrap();
+        assert!(initialized["capabilities"].get("completions").is_some());
+
+        let complete = |params: CompleteParams| {
+            JsonRpcRequest::new("completion/complete", Some(json!(params)))
+        };
+        let response = server
+            .handle_request(complete(CompleteParams::new(
+                CompletionReference::prompt("code_review"),
+                "language",
+                "p",
+            )))
+            .await;
+        let completion = &response.result.unwrap()["completion"];
+        assert_eq!(completion["values"], json!(["python", "perl", "php"]));
+        assert_eq!(completion["hasMore"], false);
+
+        let params = CompleteParams::new(
+            CompletionReference::resource("db://tables/{name}/schema"),
+            "name",
+            "t",
+        )
+        .with_context(HashMap::from([("schema".to_string(), "s.".to_string())]));
+        let completion = server.handle_complete(params).await.unwrap();
+        assert_eq!(completion.values.len(), Completion::MAX_VALUES);
+        assert_eq!(completion.values[0], "s.t_0");
+        assert_eq!(completion.total, Some(150));
+        assert_eq!(completion.has_more, Some(true));
+
+        // Arguments without a handler have no suggestions
+        let params = CompleteParams::new(CompletionReference::prompt("code_review"), "focus", "");
+        assert_eq!(
+            server.handle_complete(params).await.unwrap(),
+            Completion::default()
+        );
+
+        let response = server
+            .handle_request(JsonRpcRequest::new("completion/complete", Some(json!({}))))
+            .await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_resource_read_unknown_uri() {
+        let server = McpServer::new(ServerConfig::default(), Arc::new(TestToolHandler));
+        let response = server.handle_request(read_request("file:///missing")).await;
+        assert_eq!(response.error.unwrap().code, -32002);
+    }
 }
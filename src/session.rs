@@ -1,10 +1,52 @@
 /// Session to an MCP server. Wraps a connector and caches tools/resources/prompts.
-
-use crate::connectors::base::Connector;
-use crate::protocol::{Tool, Resource, Prompt, ToolResult};
-use crate::error::Result;
-use serde_json::Value;
+use crate::connectors::base::{
+    paginate, tool_result, Connector, NotificationHandler, RequestHandler,
+};
+use crate::context::CancellationToken;
+use crate::error::{Error, Result};
+use crate::protocol::{
+    ClientCapabilities, CompleteParams, Completion, GetPromptResult, JsonRpcNotification,
+    JsonRpcRequest, LoggingLevel, Page, Progress, Prompt, ProtocolVersion, RequestId, Resource,
+    ResourceContents, ResourceTemplate, Tool, ToolResult,
+};
+use dashmap::DashMap;
+use futures::stream::BoxStream;
+use parking_lot::RwLock;
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Receives the progress notifications of a single request
+pub type ProgressHandler = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// Options for a tool call that reports progress or can be cancelled
+#[derive(Clone, Default)]
+pub struct CallToolOptions {
+    progress: Option<ProgressHandler>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl CallToolOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the server for progress and pass each update to `handler`
+    pub fn on_progress(mut self, handler: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(handler));
+        self
+    }
+
+    /// Cancel the call when `token` is cancelled. The server is sent
+    /// `notifications/cancelled` and the call returns `Error::Cancelled`.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+/// Progress handlers of requests in flight, keyed by progress token
+type ProgressHandlers = Arc<DashMap<RequestId, ProgressHandler>>;
 
 pub struct Session {
     /// Unique name for this session (usually the server name)
@@ -16,6 +58,9 @@ pub struct Session {
     /// Whether the session has been initialized
     initialized: bool,
 
+    /// Protocol version agreed with the server at initialization
+    protocol_version: Option<ProtocolVersion>,
+
     /// Cached tools from the server (refreshed when needed)
     tools_cache: HashMap<String, Tool>,
 
@@ -24,18 +69,43 @@ pub struct Session {
 
     /// Cached prompts from the server
     prompts_cache: HashMap<String, Prompt>,
+
+    /// Capabilities declared to the server at initialization
+    capabilities: ClientCapabilities,
+
+    /// Handler for notifications that are not routed to a request
+    notification_handler: Arc<RwLock<Option<NotificationHandler>>>,
+
+    /// Handler for requests from the server, e.g. `sampling/createMessage`
+    request_handler: Arc<RwLock<Option<RequestHandler>>>,
+
+    progress_handlers: ProgressHandlers,
 }
 
 impl Session {
     /// Create a new session with a connector
-    pub fn new(name: impl Into<String>, connector: Box<dyn Connector>) -> Self {
+    pub fn new(name: impl Into<String>, mut connector: Box<dyn Connector>) -> Self {
+        let notification_handler = Arc::new(RwLock::new(None));
+        let progress_handlers = ProgressHandlers::default();
+        connector.set_notification_handler(route_notifications(
+            progress_handlers.clone(),
+            notification_handler.clone(),
+        ));
+        let request_handler = Arc::new(RwLock::new(None));
+        connector.set_request_handler(route_requests(request_handler.clone()));
+
         Self {
             name: name.into(),
             connector,
             initialized: false,
+            protocol_version: None,
             tools_cache: HashMap::new(),
             resources_cache: HashMap::new(),
             prompts_cache: HashMap::new(),
+            capabilities: ClientCapabilities::default(),
+            notification_handler,
+            request_handler,
+            progress_handlers,
         }
     }
 
@@ -47,7 +117,14 @@ impl Session {
 
     /// Initialize the session (send initialize request to server)
     pub async fn initialize(&mut self) -> Result<Value> {
-        let capabilities = self.connector.initialize().await?;
+        let capabilities = self
+            .connector
+            .initialize_with(self.capabilities.clone())
+            .await?;
+        self.protocol_version = capabilities
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .and_then(ProtocolVersion::parse);
         self.initialized = true;
         self.refresh_tools().await.ok(); // Cache tools, but don't fail if it doesn't work
         Ok(capabilities)
@@ -63,10 +140,32 @@ impl Session {
         self.initialized
     }
 
+    /// Protocol version negotiated with the server, once initialized
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.protocol_version
+    }
+
+    /// Receive notifications sent by the server
+    pub fn set_notification_handler(&mut self, handler: NotificationHandler) {
+        *self.notification_handler.write() = Some(handler);
+    }
+
+    /// Set the capabilities to declare when the session is initialized
+    pub fn set_capabilities(&mut self, capabilities: ClientCapabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Answer requests sent by the server. Requests arriving while no
+    /// handler is set fail with "method not found".
+    pub fn set_request_handler(&mut self, handler: RequestHandler) {
+        *self.request_handler.write() = Some(handler);
+    }
+
     /// Disconnect from the server
     pub async fn disconnect(&mut self) -> Result<()> {
         self.connector.disconnect().await?;
         self.initialized = false;
+        self.protocol_version = None;
         Ok(())
     }
 
@@ -84,6 +183,16 @@ impl Session {
         Ok(())
     }
 
+    /// Fetch one page of tools, continuing after `cursor`
+    pub async fn list_tools_page(&self, cursor: Option<String>) -> Result<Page<Tool>> {
+        self.connector.list_tools_page(cursor).await
+    }
+
+    /// Stream the server's tools, fetching pages as they are consumed
+    pub fn tools_stream(&self) -> BoxStream<'_, Result<Tool>> {
+        paginate(|cursor| self.connector.list_tools_page(cursor))
+    }
+
     /// Get all cached tools
     pub fn get_tools(&self) -> Vec<Tool> {
         self.tools_cache.values().cloned().collect()
@@ -99,6 +208,49 @@ impl Session {
         self.connector.call_tool(tool_name, arguments).await
     }
 
+    /// Call a tool, receiving progress updates and allowing cancellation
+    pub async fn call_tool_with(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        options: CallToolOptions,
+    ) -> Result<ToolResult> {
+        let mut request = JsonRpcRequest::new("tools/call", None);
+        let mut params = json!({
+            "name": tool_name,
+            "arguments": arguments,
+        });
+
+        // The request ID doubles as the progress token
+        let _progress = options.progress.map(|handler| {
+            params["_meta"] = json!({ "progressToken": request.id });
+            self.progress_handlers.insert(request.id.clone(), handler);
+            ProgressGuard {
+                handlers: &self.progress_handlers,
+                token: request.id.clone(),
+            }
+        });
+        request.params = Some(params);
+
+        let id = request.id.clone();
+        let response = match options.cancellation {
+            Some(token) => tokio::select! {
+                response = self.connector.send_request(request) => response?,
+                _ = token.cancelled() => {
+                    let params = json!({ "requestId": id, "reason": "Cancelled by client" });
+                    let notification = JsonRpcNotification::new("notifications/cancelled", Some(params));
+                    if let Err(e) = self.connector.send_notification(notification).await {
+                        tracing::warn!("Failed to notify server of cancellation: {}", e);
+                    }
+                    return Err(Error::Cancelled(format!("Call to '{}' was cancelled", tool_name)));
+                }
+            },
+            None => self.connector.send_request(request).await?,
+        };
+
+        tool_result(response)
+    }
+
     // =========================================================================
     // Resources
     // =========================================================================
@@ -113,6 +265,16 @@ impl Session {
         Ok(())
     }
 
+    /// Fetch one page of resources, continuing after `cursor`
+    pub async fn list_resources_page(&self, cursor: Option<String>) -> Result<Page<Resource>> {
+        self.connector.list_resources_page(cursor).await
+    }
+
+    /// Stream the server's resources, fetching pages as they are consumed
+    pub fn resources_stream(&self) -> BoxStream<'_, Result<Resource>> {
+        paginate(|cursor| self.connector.list_resources_page(cursor))
+    }
+
     /// Get all cached resources
     pub fn get_resources(&self) -> Vec<Resource> {
         self.resources_cache.values().cloned().collect()
@@ -123,11 +285,44 @@ impl Session {
         self.resources_cache.get(uri).cloned()
     }
 
+    /// List the resource templates exposed by the server
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        self.connector.list_resource_templates().await
+    }
+
+    /// Fetch one page of resource templates, continuing after `cursor`
+    pub async fn list_resource_templates_page(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<Page<ResourceTemplate>> {
+        self.connector.list_resource_templates_page(cursor).await
+    }
+
+    /// Stream the server's resource templates, fetching pages as they are consumed
+    pub fn resource_templates_stream(&self) -> BoxStream<'_, Result<ResourceTemplate>> {
+        paginate(|cursor| self.connector.list_resource_templates_page(cursor))
+    }
+
     /// Read a resource from the server
-    pub async fn read_resource(&self, uri: &str) -> Result<String> {
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>> {
         self.connector.read_resource(uri).await
     }
 
+    /// Ask the server to notify this session when a resource changes
+    pub async fn subscribe_resource(&self, uri: &str) -> Result<()> {
+        self.connector.subscribe_resource(uri).await
+    }
+
+    /// Stop receiving update notifications for a resource
+    pub async fn unsubscribe_resource(&self, uri: &str) -> Result<()> {
+        self.connector.unsubscribe_resource(uri).await
+    }
+
+    /// Ask the server for log messages at `level` and above
+    pub async fn set_log_level(&self, level: LoggingLevel) -> Result<()> {
+        self.connector.set_log_level(level).await
+    }
+
     // =========================================================================
     // Prompts
     // =========================================================================
@@ -142,6 +337,16 @@ impl Session {
         Ok(())
     }
 
+    /// Fetch one page of prompts, continuing after `cursor`
+    pub async fn list_prompts_page(&self, cursor: Option<String>) -> Result<Page<Prompt>> {
+        self.connector.list_prompts_page(cursor).await
+    }
+
+    /// Stream the server's prompts, fetching pages as they are consumed
+    pub fn prompts_stream(&self) -> BoxStream<'_, Result<Prompt>> {
+        paginate(|cursor| self.connector.list_prompts_page(cursor))
+    }
+
     /// Get all cached prompts
     pub fn get_prompts(&self) -> Vec<Prompt> {
         self.prompts_cache.values().cloned().collect()
@@ -153,9 +358,73 @@ impl Session {
     }
 
     /// Get a prompt from the server
-    pub async fn get_prompt(&self, name: &str, arguments: Option<Value>) -> Result<Value> {
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Option<Value>,
+    ) -> Result<GetPromptResult> {
         self.connector.get_prompt(name, arguments).await
     }
+
+    // =========================================================================
+    // Completions
+    // =========================================================================
+
+    /// Ask the server for suggestions for an argument value
+    pub async fn complete(&self, params: CompleteParams) -> Result<Completion> {
+        self.connector.complete(params).await
+    }
+}
+
+/// Removes a request's progress handler once the call finishes
+struct ProgressGuard<'a> {
+    handlers: &'a ProgressHandlers,
+    token: RequestId,
+}
+
+impl Drop for ProgressGuard<'_> {
+    fn drop(&mut self) {
+        self.handlers.remove(&self.token);
+    }
+}
+
+/// Connector handler that passes progress to the request it belongs to,
+/// and every notification to the session's handler
+fn route_notifications(
+    progress_handlers: ProgressHandlers,
+    notification_handler: Arc<RwLock<Option<NotificationHandler>>>,
+) -> NotificationHandler {
+    Arc::new(move |method: &str, params: &Value| {
+        if method == "notifications/progress"
+            && let Ok(progress) = serde_json::from_value::<Progress>(params.clone())
+        {
+            let handler = progress_handlers
+                .get(&progress.progress_token)
+                .map(|h| h.value().clone());
+            if let Some(handler) = handler {
+                handler(progress);
+            }
+        }
+
+        let handler = notification_handler.read().clone();
+        if let Some(handler) = handler {
+            handler(method, params);
+        }
+    })
+}
+
+/// Connector handler that passes each server request to the session's
+/// handler, which may be set after the connector has started
+fn route_requests(request_handler: Arc<RwLock<Option<RequestHandler>>>) -> RequestHandler {
+    Arc::new(move |request: JsonRpcRequest| {
+        let handler = request_handler.read().clone();
+        Box::pin(async move {
+            match handler {
+                Some(handler) => handler(request).await,
+                None => Err(Error::MethodNotFound(request.method)),
+            }
+        })
+    })
 }
 
 #[cfg(test)]
@@ -175,7 +444,7 @@ mod tests {
             ) -> Result<crate::protocol::JsonRpcResponse> {
                 Ok(crate::protocol::JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
-                    id: "1".to_string(),
+                    id: Some("1".into()),
                     result: None,
                     error: None,
                 })
@@ -199,4 +468,252 @@ mod tests {
         assert_eq!(session.name, "test");
         assert!(!session.is_initialized());
     }
+
+    /// Answers initialize with a fixed protocol version
+    struct InitializingConnector {
+        version: &'static str,
+        sent: Arc<parking_lot::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Connector for InitializingConnector {
+        async fn send_request(
+            &self,
+            request: JsonRpcRequest,
+        ) -> Result<crate::protocol::JsonRpcResponse> {
+            if request.method != "initialize" {
+                return Ok(crate::protocol::JsonRpcResponse::success(
+                    request.id,
+                    json!({ "tools": [] }),
+                ));
+            }
+            assert_eq!(
+                request.params.as_ref().unwrap()["protocolVersion"],
+                crate::protocol::MCP_VERSION
+            );
+            let result = json!({
+                "protocolVersion": self.version,
+                "capabilities": {},
+                "serverInfo": { "name": "test", "version": "1.0" }
+            });
+            Ok(crate::protocol::JsonRpcResponse::success(
+                request.id, result,
+            ))
+        }
+
+        async fn send_notification(&self, notification: JsonRpcNotification) -> Result<()> {
+            self.sent.lock().push(notification.method);
+            Ok(())
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_version() {
+        let sent = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let connector = InitializingConnector {
+            version: "2025-03-26",
+            sent: sent.clone(),
+        };
+        let mut session = Session::new("test", Box::new(connector));
+
+        session.initialize().await.unwrap();
+        assert_eq!(
+            session.protocol_version(),
+            Some(ProtocolVersion::V2025_03_26)
+        );
+        assert_eq!(sent.lock()[0], "notifications/initialized");
+
+        // A version the client does not speak fails the handshake
+        let connector = InitializingConnector {
+            version: "2023-01-01",
+            sent: sent.clone(),
+        };
+        let mut session = Session::new("test", Box::new(connector));
+        assert!(matches!(
+            session.initialize().await,
+            Err(Error::ConnectionError(_))
+        ));
+        assert!(!session.is_initialized());
+        assert_eq!(sent.lock().len(), 1);
+    }
+
+    /// Serves requests with an in-process server
+    struct ServerConnector(crate::server::McpServer);
+
+    #[async_trait::async_trait]
+    impl Connector for ServerConnector {
+        async fn send_request(
+            &self,
+            request: JsonRpcRequest,
+        ) -> Result<crate::protocol::JsonRpcResponse> {
+            Ok(self.0.handle_request(request).await)
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_follows_cursors() {
+        use crate::server::{McpServer, ServerConfig};
+        use futures::TryStreamExt;
+
+        let server = McpServer::with_config(ServerConfig {
+            page_size: Some(2),
+            ..ServerConfig::default()
+        });
+        for i in 0..5 {
+            server.register_prompt(
+                serde_json::from_value(json!({ "name": format!("prompt-{}", i) })).unwrap(),
+            );
+        }
+        let mut session = Session::new("test", Box::new(ServerConnector(server)));
+
+        let page = session.list_prompts_page(None).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert!(page.next_cursor.is_some());
+
+        let streamed: Vec<_> = session
+            .prompts_stream()
+            .map_ok(|p| p.name)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            streamed,
+            vec!["prompt-0", "prompt-1", "prompt-2", "prompt-3", "prompt-4"]
+        );
+
+        session.refresh_prompts().await.unwrap();
+        assert_eq!(session.get_prompts().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_complete() {
+        use crate::protocol::CompletionReference;
+        use crate::server::{McpServer, ServerConfig};
+
+        let server = McpServer::with_config(ServerConfig::default());
+        server.register_prompt_completion(
+            "greet",
+            "name",
+            Arc::new(
+                |value: String, _context: HashMap<String, String>| async move {
+                    Ok(vec![format!("{}da", value), format!("{}lan", value)])
+                },
+            ),
+        );
+        let session = Session::new("test", Box::new(ServerConnector(server)));
+
+        let completion = session
+            .complete(CompleteParams::new(
+                CompletionReference::prompt("greet"),
+                "name",
+                "A",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(completion.values, vec!["Ada", "Alan"]);
+    }
+
+    /// Reports progress on every request and never answers
+    #[derive(Default)]
+    struct StallingConnector {
+        handler: Option<NotificationHandler>,
+        sent: Arc<parking_lot::Mutex<Vec<(String, Value)>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Connector for StallingConnector {
+        async fn send_request(
+            &self,
+            request: JsonRpcRequest,
+        ) -> Result<crate::protocol::JsonRpcResponse> {
+            let token = request.params.as_ref().unwrap()["_meta"]["progressToken"].clone();
+            if let Some(handler) = &self.handler {
+                handler(
+                    "notifications/progress",
+                    &json!({ "progressToken": token, "progress": 1, "total": 2 }),
+                );
+            }
+            futures::future::pending().await
+        }
+
+        async fn send_notification(&self, notification: JsonRpcNotification) -> Result<()> {
+            self.sent
+                .lock()
+                .push((notification.method, notification.params.unwrap_or_default()));
+            Ok(())
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn set_notification_handler(&mut self, handler: NotificationHandler) {
+            self.handler = Some(handler);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_progress_and_cancellation() {
+        let connector = StallingConnector::default();
+        let sent = connector.sent.clone();
+        let session = Session::new("test", Box::new(connector));
+
+        // Cancel as soon as the first progress update arrives
+        let token = CancellationToken::new();
+        let updates = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let options = CallToolOptions::new()
+            .cancellation(token.clone())
+            .on_progress({
+                let updates = updates.clone();
+                move |progress| {
+                    updates.lock().push(progress.progress);
+                    token.cancel();
+                }
+            });
+
+        let error = session
+            .call_tool_with("crawl", json!({}), options)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Cancelled(_)));
+        assert_eq!(*updates.lock(), vec![1.0]);
+        assert!(session.progress_handlers.is_empty());
+
+        let sent = sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "notifications/cancelled");
+        assert!(sent[0].1["requestId"].is_string());
+    }
 }
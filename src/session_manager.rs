@@ -0,0 +1,260 @@
+//! Server-side registry of the client sessions connected to an
+//! [`McpServer`](crate::server::McpServer).
+//!
+//! Streamable HTTP sessions are keyed by their `Mcp-Session-Id` and end when
+//! the client deletes them or after `ServerConfig::session_idle_timeout`
+//! without a message while no GET stream is open. Stdio and WebSocket
+//! sessions last exactly as long as their connection.
+
+use crate::context::CancellationToken;
+use crate::error::Result;
+use crate::server::ClientSession;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Transport a session is served over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportKind {
+    /// Streamable HTTP, keyed by the `Mcp-Session-Id` header
+    Http,
+    Stdio,
+    WebSocket,
+}
+
+impl TransportKind {
+    /// Whether the session ends when its connection closes. Other sessions
+    /// end when deleted or once idle for too long.
+    pub fn is_connection_bound(self) -> bool {
+        !matches!(self, TransportKind::Http)
+    }
+}
+
+/// Values a server keeps for one client, shared by all requests on its
+/// session and dropped when it ends
+#[derive(Debug, Clone, Default)]
+pub struct SessionState(Arc<DashMap<String, Value>>);
+
+impl SessionState {
+    /// The value under `key`, if there is one of type `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.0
+            .get(key)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    pub fn set(&self, key: impl Into<String>, value: impl Serialize) -> Result<()> {
+        self.0.insert(key.into(), serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.0.remove(key).map(|(_, value)| value)
+    }
+}
+
+/// A session as seen by lifecycle hooks and request handlers
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub id: String,
+    pub transport: TransportKind,
+    pub connected_at: DateTime<Utc>,
+    /// What the client declared in `initialize`; `None` until then
+    pub client: Option<ClientSession>,
+    pub state: SessionState,
+}
+
+/// Called as a session opens or ends
+pub type SessionHook = Arc<dyn Fn(&SessionInfo) + Send + Sync>;
+
+struct ManagedSession {
+    transport: TransportKind,
+    connected_at: DateTime<Utc>,
+    last_active: Instant,
+    /// Whether a server-to-client stream is open, which keeps the session
+    /// from idling out
+    streaming: bool,
+    state: SessionState,
+    /// Cancelled when the session ends, so its transport can let go of it
+    closed: CancellationToken,
+}
+
+impl ManagedSession {
+    fn info(&self, id: &str, client: Option<ClientSession>) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            transport: self.transport,
+            connected_at: self.connected_at,
+            client,
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Open sessions, with the hooks run as they come and go
+#[derive(Default)]
+pub(crate) struct SessionManager {
+    sessions: DashMap<String, ManagedSession>,
+    pub(crate) on_connect: Vec<SessionHook>,
+    pub(crate) on_disconnect: Vec<SessionHook>,
+    reaper_started: AtomicBool,
+}
+
+impl SessionManager {
+    /// Register a session and run the connect hooks. Returns a token that is
+    /// cancelled when the session ends.
+    pub(crate) fn open(&self, id: &str, transport: TransportKind) -> CancellationToken {
+        let session = ManagedSession {
+            transport,
+            connected_at: Utc::now(),
+            last_active: Instant::now(),
+            streaming: false,
+            state: SessionState::default(),
+            closed: CancellationToken::new(),
+        };
+        let closed = session.closed.clone();
+        let info = session.info(id, None);
+        self.sessions.insert(id.to_string(), session);
+
+        for hook in &self.on_connect {
+            hook(&info);
+        }
+        closed
+    }
+
+    /// Note that a session's client was just heard from
+    pub(crate) fn touch(&self, id: &str) {
+        if let Some(mut session) = self.sessions.get_mut(id) {
+            session.last_active = Instant::now();
+        }
+    }
+
+    /// Note that a session's stream opened or closed. A session starts
+    /// idling once its stream closes.
+    pub(crate) fn set_streaming(&self, id: &str, streaming: bool) {
+        if let Some(mut session) = self.sessions.get_mut(id) {
+            session.streaming = streaming;
+            session.last_active = Instant::now();
+        }
+    }
+
+    pub(crate) fn info(&self, id: &str, client: Option<ClientSession>) -> Option<SessionInfo> {
+        self.sessions
+            .get(id)
+            .map(|session| session.info(id, client))
+    }
+
+    pub(crate) fn ids(&self) -> Vec<String> {
+        self.sessions
+            .iter()
+            .map(|session| session.key().clone())
+            .collect()
+    }
+
+    /// Remove a session and run the disconnect hooks. Does nothing for a
+    /// session that is not open.
+    pub(crate) fn close(&self, id: &str, client: Option<ClientSession>) {
+        let Some((_, session)) = self.sessions.remove(id) else {
+            return;
+        };
+        session.closed.cancel();
+
+        let info = session.info(id, client);
+        for hook in &self.on_disconnect {
+            hook(&info);
+        }
+    }
+
+    /// Sessions not bound to a connection and without an open stream that
+    /// have had no message for at least `timeout`
+    pub(crate) fn idle(&self, timeout: Duration) -> Vec<String> {
+        self.sessions
+            .iter()
+            .filter(|session| {
+                !session.transport.is_connection_bound()
+                    && !session.streaming
+                    && session.last_active.elapsed() >= timeout
+            })
+            .map(|session| session.key().clone())
+            .collect()
+    }
+
+    /// Claim the job of running the idle reaper; only the first call succeeds
+    pub(crate) fn start_reaper(&self) -> bool {
+        !self.reaper_started.swap(true, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn test_session_state() {
+        let state = SessionState::default();
+        state.set("user", "alice").unwrap();
+        state.set("visits", 3).unwrap();
+
+        assert_eq!(state.get::<String>("user").as_deref(), Some("alice"));
+        assert_eq!(state.get::<u32>("visits"), Some(3));
+        assert_eq!(state.get::<u32>("user"), None);
+        assert!(state.remove("user").is_some());
+        assert_eq!(state.get::<String>("user"), None);
+    }
+
+    #[test]
+    fn test_open_and_close_run_hooks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = SessionManager::default();
+        let log = events.clone();
+        manager.on_connect.push(Arc::new(move |info: &SessionInfo| {
+            log.lock()
+                .push(format!("connect {} {:?}", info.id, info.transport))
+        }));
+        let log = events.clone();
+        manager
+            .on_disconnect
+            .push(Arc::new(move |info: &SessionInfo| {
+                log.lock().push(format!("disconnect {}", info.id))
+            }));
+
+        let closed = manager.open("a", TransportKind::Stdio);
+        manager.open("b", TransportKind::Http);
+        assert!(manager.info("a", None).is_some());
+
+        manager.close("a", None);
+        manager.close("a", None);
+        assert!(closed.is_cancelled());
+        assert!(manager.info("a", None).is_none());
+        assert_eq!(
+            *events.lock(),
+            ["connect a Stdio", "connect b Http", "disconnect a"]
+        );
+    }
+
+    #[test]
+    fn test_only_unbound_sessions_idle_out() {
+        let manager = SessionManager::default();
+        manager.open("http", TransportKind::Http);
+        manager.open("ws", TransportKind::WebSocket);
+
+        assert_eq!(manager.idle(Duration::ZERO), ["http"]);
+        assert!(manager.idle(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn test_streaming_sessions_do_not_idle_out() {
+        let manager = SessionManager::default();
+        manager.open("http", TransportKind::Http);
+
+        manager.set_streaming("http", true);
+        assert!(manager.idle(Duration::ZERO).is_empty());
+        manager.set_streaming("http", false);
+        assert_eq!(manager.idle(Duration::ZERO), ["http"]);
+    }
+}
@@ -0,0 +1,705 @@
+//! Streamable HTTP transport for MCP servers.
+//!
+//! A single endpoint handles three methods:
+//! - `POST` - one JSON-RPC message or batch from the client. Requests are
+//!   answered with `application/json`, or with a `text/event-stream` when the
+//!   server sends notifications for the request before its response is ready.
+//!   Batches are answered with a JSON array; notifications get `202 Accepted`.
+//! - `GET` - opens the session's standalone SSE stream for server-initiated
+//!   messages. Messages sent while no stream is open are dropped.
+//! - `DELETE` - ends the session named by the `Mcp-Session-Id` header.
+//!
+//! Sessions also end after `ServerConfig::session_idle_timeout` without a
+//! message from the client, unless their GET stream is open.
+
+use crate::context::{CancellationToken, OutboundSender, RequestContext, SessionHandle};
+use crate::error::{Error, Result};
+use crate::protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, ProtocolVersion};
+use crate::server::McpServer;
+use crate::session_manager::TransportKind;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::post,
+    Json, Router,
+};
+use dashmap::DashMap;
+use futures::{Stream, StreamExt};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// Header carrying the session ID assigned at initialization
+pub const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// Header carrying the negotiated protocol version on requests after
+/// initialization
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Server-side state of one Streamable HTTP session
+struct HttpSession {
+    outbound: OutboundSender,
+    /// The standalone GET stream, while one is open. Server-initiated
+    /// messages sent without one are dropped rather than queued.
+    stream: Arc<Mutex<Option<OutboundSender>>>,
+    /// Cancelled when the server ends the session
+    closed: CancellationToken,
+}
+
+#[derive(Clone)]
+struct HttpState {
+    server: Arc<McpServer>,
+    sessions: Arc<DashMap<String, HttpSession>>,
+}
+
+impl McpServer {
+    /// Build an axum router serving this server over Streamable HTTP at `/`.
+    ///
+    /// Nest it to mount the endpoint elsewhere:
+    /// `Router::new().nest("/mcp", server.into_router())`
+    pub fn into_router(self: Arc<Self>) -> Router {
+        let state = HttpState {
+            server: self,
+            sessions: Arc::new(DashMap::new()),
+        };
+
+        Router::new()
+            .route("/", post(handle_post).get(handle_get).delete(handle_delete))
+            .with_state(state)
+    }
+
+    /// Serve over Streamable HTTP on `addr` (e.g. `"127.0.0.1:3000"`)
+    pub async fn serve_http(self: Arc<Self>, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::ConnectionError(format!("Failed to bind {}: {}", addr, e)))?;
+
+        tracing::info!("MCP server listening on http://{}", addr);
+
+        axum::serve(listener, self.into_router())
+            .await
+            .map_err(|e| Error::ServerError(e.to_string()))
+    }
+}
+
+fn header_session_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// The version named in the protocol version header. Fails with the raw
+/// header if it names a version this server does not support.
+fn header_protocol_version(
+    headers: &HeaderMap,
+) -> std::result::Result<Option<ProtocolVersion>, String> {
+    match headers.get(PROTOCOL_VERSION_HEADER) {
+        None => Ok(None),
+        Some(value) => {
+            let version = value.to_str().unwrap_or_default();
+            ProtocolVersion::parse(version)
+                .map(Some)
+                .ok_or_else(|| version.to_string())
+        }
+    }
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.contains("text/event-stream"))
+        .unwrap_or(false)
+}
+
+fn internal_error(request: &JsonRpcRequest, message: String) -> JsonRpcResponse {
+    JsonRpcResponse::failure(Some(request.id.clone()), JsonRpcError::new(-32603, message))
+}
+
+fn receiver_stream(rx: mpsc::UnboundedReceiver<Value>) -> impl Stream<Item = Value> {
+    futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|m| (m, rx)) })
+}
+
+fn event_stream<S>(messages: S) -> Response
+where
+    S: Stream<Item = Value> + Send + 'static,
+{
+    let events =
+        messages.map(|message| Ok::<_, Infallible>(Event::default().data(message.to_string())));
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn handle_post(State(state): State<HttpState>, headers: HeaderMap, body: Bytes) -> Response {
+    let header_version = match header_protocol_version(&headers) {
+        Ok(version) => version,
+        Err(version) => {
+            let message = format!("Unsupported protocol version '{}'", version);
+            return (StatusCode::BAD_REQUEST, message).into_response();
+        }
+    };
+    // An initialized session keeps the version it negotiated
+    let version = header_session_id(&headers)
+        .and_then(|id| state.server.protocol_version(&id))
+        .or(header_version);
+
+    let received = match super::receive(
+        &state.server,
+        &body,
+        header_session_id(&headers).as_deref(),
+        version,
+    ) {
+        Ok(received) => received,
+        Err(invalid) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(JsonRpcResponse::from(invalid)),
+            )
+                .into_response()
+        }
+    };
+
+    // Notifications and responses from the client need no reply
+    if received.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+    if let Some(error) = received.single_error() {
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    // A new session starts with every initialize; afterwards the client
+    // names it in the header. Requests without the header run statelessly.
+    let initialize = received
+        .single_request()
+        .is_some_and(|request| request.method == "initialize");
+    let (session, created) = if initialize {
+        (Some(open_session(&state)), true)
+    } else if let Some(id) = header_session_id(&headers) {
+        match state.sessions.get(&id) {
+            Some(session) if !session.closed.is_cancelled() => (
+                Some(SessionHandle::new(id.clone(), session.outbound.clone())),
+                false,
+            ),
+            _ => return (StatusCode::NOT_FOUND, "Unknown session").into_response(),
+        }
+    } else {
+        (None, false)
+    };
+
+    let context = |request: &JsonRpcRequest| {
+        let ctx = RequestContext::new(request.id.clone());
+        let ctx = match version {
+            Some(version) => ctx.with_protocol_version(version),
+            None => ctx,
+        };
+        match &session {
+            Some(session) => ctx.with_session(session.clone()),
+            None => ctx,
+        }
+    };
+
+    let mut response = match received.into_single_request() {
+        Ok(request) if accepts_event_stream(&headers) => {
+            let ctx = context(&request);
+            respond_streaming(state.server.clone(), request, ctx).await
+        }
+        Ok(request) => {
+            let ctx = context(&request);
+            Json(state.server.handle_request_with_context(request, ctx).await).into_response()
+        }
+        // Batches are answered with a single JSON array
+        Err(received) => match received.respond(&state.server, context).await {
+            Some(reply) => Json(reply).into_response(),
+            None => StatusCode::ACCEPTED.into_response(),
+        },
+    };
+
+    if let (true, Some(session)) = (created, &session) {
+        // A failed initialize leaves no session behind
+        if state.server.client_session(session.id()).is_none() {
+            state.sessions.remove(session.id());
+            state.server.end_session(session.id());
+        } else if let Ok(value) = HeaderValue::from_str(session.id()) {
+            response.headers_mut().insert(SESSION_ID_HEADER, value);
+        }
+    }
+
+    response
+}
+
+/// Start a session for an initialize request. It is forgotten as soon as
+/// the server ends it, whether deleted or expired.
+fn open_session(state: &HttpState) -> SessionHandle {
+    let id = uuid::Uuid::new_v4().to_string();
+    let (outbound, inbox) = mpsc::unbounded_channel();
+    let handle = SessionHandle::new(id.clone(), outbound.clone());
+    let closed = state.server.open_session(&handle, TransportKind::Http);
+    let stream = Arc::new(Mutex::new(None));
+    state.sessions.insert(
+        id,
+        HttpSession {
+            outbound,
+            stream: stream.clone(),
+            closed: closed.clone(),
+        },
+    );
+
+    let sessions = state.sessions.clone();
+    let server = state.server.clone();
+    let notifications = server.notifications();
+    let forwarded = handle.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = closed.cancelled() => {}
+            _ = super::forward_notifications(server.clone(), notifications, forwarded.clone()) => {}
+            _ = deliver(&server, forwarded.id(), inbox, stream) => {}
+        }
+        sessions.remove(forwarded.id());
+    });
+    handle
+}
+
+/// Pass server-initiated messages on to the session's GET stream, dropping
+/// those sent while no stream is open. A dropped request fails at once
+/// rather than waiting for an answer that cannot come.
+async fn deliver(
+    server: &McpServer,
+    session_id: &str,
+    mut inbox: mpsc::UnboundedReceiver<Value>,
+    stream: Arc<Mutex<Option<OutboundSender>>>,
+) {
+    while let Some(message) = inbox.recv().await {
+        let unsent = match stream.lock().as_ref() {
+            Some(stream) => stream.send(message).err().map(|unsent| unsent.0),
+            None => Some(message),
+        };
+        let Some(message) = unsent else {
+            continue;
+        };
+        tracing::debug!(
+            session = session_id,
+            "Dropped a message for a session without an open stream"
+        );
+        if let Ok(request) = serde_json::from_value::<JsonRpcRequest>(message) {
+            let error = JsonRpcError::new(-32603, "Client has no open stream".to_string());
+            server.handle_response(
+                JsonRpcResponse::failure(Some(request.id), error),
+                Some(session_id),
+            );
+        }
+    }
+}
+
+/// Answer with plain JSON unless the server sends something for the request
+/// before its response is ready; then switch to an SSE stream that carries
+/// those messages and ends with the response.
+async fn respond_streaming(
+    server: Arc<McpServer>,
+    request: JsonRpcRequest,
+    ctx: RequestContext,
+) -> Response {
+    let fallback = internal_error(&request, "Request handler failed".to_string());
+    let (outbound, mut related) = mpsc::unbounded_channel();
+    let ctx = ctx.with_outbound(outbound);
+    let mut task =
+        tokio::spawn(async move { server.handle_request_with_context(request, ctx).await });
+
+    let first = tokio::select! {
+        biased;
+        Some(message) = related.recv() => message,
+        response = &mut task => {
+            return Json(response.unwrap_or(fallback)).into_response();
+        }
+    };
+
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    let _ = events_tx.send(first);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = related.recv() => {
+                    let _ = events_tx.send(message);
+                }
+                response = &mut task => {
+                    while let Ok(message) = related.try_recv() {
+                        let _ = events_tx.send(message);
+                    }
+                    if let Ok(value) = serde_json::to_value(response.unwrap_or(fallback)) {
+                        let _ = events_tx.send(value);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    event_stream(receiver_stream(events_rx))
+}
+
+async fn handle_get(State(state): State<HttpState>, headers: HeaderMap) -> Response {
+    if !accepts_event_stream(&headers) {
+        return (
+            StatusCode::NOT_ACCEPTABLE,
+            "Client must accept text/event-stream",
+        )
+            .into_response();
+    }
+
+    let id = match header_session_id(&headers) {
+        Some(id) => id,
+        None => return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response(),
+    };
+
+    let (stream, closed) = match state.sessions.get(&id) {
+        Some(session) if !session.closed.is_cancelled() => {
+            (session.stream.clone(), session.closed.clone())
+        }
+        _ => return (StatusCode::NOT_FOUND, "Unknown session").into_response(),
+    };
+
+    // A stream is gone once the client disconnects and its receiver is dropped
+    let (outbound, messages) = mpsc::unbounded_channel();
+    {
+        let mut stream = stream.lock();
+        if stream.as_ref().is_some_and(|open| !open.is_closed()) {
+            return (StatusCode::CONFLICT, "Session already has an open stream").into_response();
+        }
+        *stream = Some(outbound);
+    }
+
+    // The session stays alive while the client listens
+    let open = OpenStream::new(state.server.clone(), id);
+    let messages = futures::stream::unfold((messages, open), |(mut messages, open)| async move {
+        messages.recv().await.map(|m| (m, (messages, open)))
+    });
+
+    // The stream ends with the session
+    event_stream(messages.take_until(closed.cancelled_owned()))
+}
+
+/// Marks a session as streaming until dropped along with its GET stream
+struct OpenStream {
+    server: Arc<McpServer>,
+    session_id: String,
+}
+
+impl OpenStream {
+    fn new(server: Arc<McpServer>, session_id: String) -> Self {
+        server.set_session_streaming(&session_id, true);
+        Self { server, session_id }
+    }
+}
+
+impl Drop for OpenStream {
+    fn drop(&mut self) {
+        self.server.set_session_streaming(&self.session_id, false);
+    }
+}
+
+async fn handle_delete(State(state): State<HttpState>, headers: HeaderMap) -> StatusCode {
+    match header_session_id(&headers) {
+        Some(id) if state.sessions.remove(&id).is_some() => {
+            state.server.end_session(&id);
+            StatusCode::OK
+        }
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::ServerConfig;
+    use axum::body::to_bytes;
+    use serde_json::json;
+
+    fn state() -> HttpState {
+        HttpState {
+            server: Arc::new(McpServer::with_config(ServerConfig::default())),
+            sessions: Arc::new(DashMap::new()),
+        }
+    }
+
+    fn headers(session: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/json, text/event-stream"),
+        );
+        if let Some(session) = session {
+            headers.insert(SESSION_ID_HEADER, HeaderValue::from_str(session).unwrap());
+        }
+        headers
+    }
+
+    async fn post(state: &HttpState, body: Value, session: Option<&str>) -> Response {
+        handle_post(
+            State(state.clone()),
+            headers(session),
+            Bytes::from(body.to_string()),
+        )
+        .await
+    }
+
+    fn initialize() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        })
+    }
+
+    async fn body_json(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_assigns_session() {
+        let state = state();
+        let response = post(&state, initialize(), None).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(state.sessions.contains_key(&session));
+
+        let body = body_json(response).await;
+        assert_eq!(body["id"], "1");
+        assert!(body["result"]["serverInfo"].is_object());
+        assert_eq!(
+            state.server.protocol_version(&session),
+            Some(ProtocolVersion::V2025_06_18)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_initialize_creates_no_session() {
+        let state = state();
+        let response = post(
+            &state,
+            json!({ "jsonrpc": "2.0", "id": "1", "method": "initialize", "params": {} }),
+            None,
+        )
+        .await;
+
+        assert!(response.headers().get(SESSION_ID_HEADER).is_none());
+        assert!(state.sessions.is_empty());
+        assert_eq!(body_json(response).await["error"]["code"], -32602);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_version_header() {
+        let mut headers = headers(None);
+        headers.insert(
+            PROTOCOL_VERSION_HEADER,
+            HeaderValue::from_static("1999-01-01"),
+        );
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+        let response = handle_post(State(state()), headers, Bytes::from(body.to_string())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Stateless batches are refused once the client speaks 2025-06-18
+        let mut headers = self::headers(None);
+        headers.insert(
+            PROTOCOL_VERSION_HEADER,
+            HeaderValue::from_static("2025-06-18"),
+        );
+        let body = json!([{ "jsonrpc": "2.0", "id": 1, "method": "ping" }]);
+        let response = handle_post(State(state()), headers, Bytes::from(body.to_string())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_session_is_rejected() {
+        let response = post(
+            &state(),
+            json!({ "jsonrpc": "2.0", "id": "1", "method": "tools/list" }),
+            Some("missing"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_notification_is_accepted() {
+        let response = post(
+            &state(),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn test_parse_error() {
+        let response = handle_post(State(state()), headers(None), Bytes::from("{not json")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"]["code"], -32700);
+
+        let response = post(&state(), json!({ "jsonrpc": "2.0", "id": 1 }), None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let response = post(
+            &state(),
+            json!([
+                { "jsonrpc": "2.0", "id": 1, "method": "tools/list" },
+                { "jsonrpc": "2.0", "method": "notifications/initialized" },
+                { "jsonrpc": "2.0", "id": 2, "method": "missing" },
+            ]),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = body_json(response).await;
+        let mut responses = body.as_array().unwrap().clone();
+        responses.sort_by_key(|r| r["id"].as_i64());
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0]["result"]["tools"].is_array());
+        assert_eq!(responses[1]["error"]["code"], -32601);
+
+        // A batch of notifications gets no response body
+        let response = post(
+            &state(),
+            json!([{ "jsonrpc": "2.0", "method": "notifications/initialized" }]),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn test_delete_ends_session() {
+        let state = state();
+        let response = post(&state, initialize(), None).await;
+        let session = response.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let status = handle_delete(State(state.clone()), headers(Some(&session))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let status = handle_delete(State(state), headers(Some(&session))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_idle_session_expires() {
+        let config = ServerConfig {
+            session_idle_timeout: Some(std::time::Duration::from_millis(50)),
+            ..ServerConfig::default()
+        };
+        let state = HttpState {
+            server: Arc::new(McpServer::with_config(config)),
+            sessions: Arc::new(DashMap::new()),
+        };
+        let response = post(&state, initialize(), None).await;
+        let session = response.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            state.server.session_info(&session).unwrap().transport,
+            TransportKind::Http
+        );
+
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(state.server.session_info(&session).is_none());
+        assert!(state.sessions.is_empty());
+
+        let ping = json!({ "jsonrpc": "2.0", "id": "2", "method": "ping" });
+        assert_eq!(
+            post(&state, ping, Some(&session)).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_stream_keeps_session() {
+        let config = ServerConfig {
+            session_idle_timeout: Some(std::time::Duration::from_millis(50)),
+            ..ServerConfig::default()
+        };
+        let state = HttpState {
+            server: Arc::new(McpServer::with_config(config)),
+            sessions: Arc::new(DashMap::new()),
+        };
+        let response = post(&state, initialize(), None).await;
+        let session = response.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let stream = handle_get(State(state.clone()), headers(Some(&session))).await;
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(state.server.session_info(&session).is_some());
+
+        drop(stream);
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert!(state.server.session_info(&session).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_messages_need_an_open_stream() {
+        let state = state();
+        let response = post(&state, initialize(), None).await;
+        let session = response.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let outbound = state.sessions.get(&session).unwrap().outbound.clone();
+
+        // Nothing is queued for a stream that is not open
+        outbound.send(json!({ "n": 1 })).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        let response = handle_get(State(state.clone()), headers(Some(&session))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let conflict = handle_get(State(state.clone()), headers(Some(&session))).await;
+        assert_eq!(conflict.status(), StatusCode::CONFLICT);
+
+        outbound.send(json!({ "n": 2 })).unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let event = body.next().await.unwrap().unwrap();
+        assert_eq!(String::from_utf8_lossy(&event), "data: {\"n\":2}\n\n");
+
+        // Once the client goes away another stream may open
+        drop(body);
+        let response = handle_get(State(state), headers(Some(&session))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_requires_session() {
+        let response = handle_get(State(state()), headers(None)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,265 @@
+//! Server-side transports for [`McpServer`](crate::server::McpServer)
+//!
+//! - Streamable HTTP - `McpServer::into_router` / `McpServer::serve_http`
+//! - Stdio - `McpServer::serve_stdio`, for servers launched as a subprocess
+//! - WebSocket - `McpServer::serve_websocket`, one session per connection
+
+pub mod http;
+pub mod stdio;
+pub mod websocket;
+
+pub use http::{PROTOCOL_VERSION_HEADER, SESSION_ID_HEADER};
+
+use crate::context::{OutboundSender, RequestContext, SessionHandle};
+use crate::error::Result;
+use crate::jsonrpc::{
+    self, InvalidMessage, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse, INVALID_REQUEST,
+};
+use crate::protocol::ProtocolVersion;
+use crate::server::McpServer;
+use crate::session_manager::TransportKind;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinSet;
+
+/// Forward server-wide notifications to a session until either side closes.
+/// Nothing is forwarded before the client sends `notifications/initialized`.
+pub(crate) async fn forward_notifications(
+    server: Arc<McpServer>,
+    mut notifications: broadcast::Receiver<Value>,
+    session: SessionHandle,
+) {
+    loop {
+        match notifications.recv().await {
+            Ok(message) => {
+                if !server.session_ready(session.id()) {
+                    continue;
+                }
+                if !session.send(message) {
+                    break;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    session = session.id(),
+                    "Dropped {} notification(s) for a slow session",
+                    skipped
+                );
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Serve a session bound to a single connection until the client stops
+/// sending.
+///
+/// `incoming` yields each message or batch the client sends; responses and
+/// notifications go out on `outbound`. Requests are handled concurrently.
+/// Once `incoming` ends, in-flight requests finish before the session is
+/// ended. A read error ends the session the same way and is returned.
+pub(crate) async fn serve_connection<S>(
+    server: Arc<McpServer>,
+    incoming: S,
+    outbound: OutboundSender,
+    transport: TransportKind,
+) -> Result<()>
+where
+    S: Stream<Item = Result<String>>,
+{
+    // The whole connection is a single session
+    let session = SessionHandle::new(uuid::Uuid::new_v4().to_string(), outbound.clone());
+    server.open_session(&session, transport);
+    let forwarder = tokio::spawn(forward_notifications(
+        server.clone(),
+        server.notifications(),
+        session.clone(),
+    ));
+
+    let mut incoming = std::pin::pin!(incoming);
+    let mut in_flight = JoinSet::new();
+    let mut read = Ok(());
+
+    while let Some(message) = incoming.next().await {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                read = Err(e);
+                break;
+            }
+        };
+        let message = message.trim();
+        if message.is_empty() {
+            continue;
+        }
+
+        let version = server.protocol_version(session.id());
+        let received = match receive(&server, message.as_bytes(), Some(session.id()), version) {
+            Ok(received) => received,
+            Err(invalid) => {
+                let _ = outbound.send(json!(JsonRpcResponse::from(invalid)));
+                continue;
+            }
+        };
+        if received.is_empty() {
+            continue;
+        }
+
+        let server = server.clone();
+        let session = session.clone();
+        let outbound = outbound.clone();
+
+        let initialize = received
+            .single_request()
+            .is_some_and(|request| request.method == "initialize");
+        let respond = async move {
+            let context = |request: &JsonRpcRequest| {
+                RequestContext::new(request.id.clone())
+                    .with_session(session.clone())
+                    .with_outbound(outbound.clone())
+            };
+            if let Some(reply) = received.respond(&server, context).await {
+                let _ = outbound.send(reply);
+            }
+        };
+
+        // Initialize completes before the next message is read, so requests
+        // that follow it see the negotiated session
+        if initialize {
+            respond.await;
+        } else {
+            in_flight.spawn(respond);
+        }
+
+        // Reap finished requests so the set does not grow unbounded
+        while in_flight.try_join_next().is_some() {}
+    }
+
+    // Nothing can answer the server's own requests any more
+    server.drop_pending_requests(session.id());
+    tracing::debug!(
+        ?transport,
+        "Client closed, waiting for {} in-flight request(s)",
+        in_flight.len()
+    );
+    while in_flight.join_next().await.is_some() {}
+
+    forwarder.abort();
+    server.end_session(session.id());
+    read
+}
+
+/// A message or batch from a client whose notifications have been handled
+pub(crate) struct Received {
+    requests: Vec<JsonRpcRequest>,
+    /// Error responses for entries that were not valid JSON-RPC
+    errors: Vec<JsonRpcResponse>,
+    batch: bool,
+}
+
+/// Parse what a client sent and handle its notifications and responses
+/// right away, so they take effect before any request that follows. Fails with the error
+/// to send back if nothing could be parsed, or if the message is a batch and
+/// the negotiated `version` no longer allows batching.
+pub(crate) fn receive(
+    server: &McpServer,
+    text: &[u8],
+    session_id: Option<&str>,
+    version: Option<ProtocolVersion>,
+) -> std::result::Result<Received, InvalidMessage> {
+    if let Some(session_id) = session_id {
+        server.touch_session(session_id);
+    }
+    let incoming = jsonrpc::parse(text)?;
+    if let Some(version) = version.filter(|v| incoming.is_batch() && !v.supports_batching()) {
+        return Err(InvalidMessage::new(
+            None,
+            INVALID_REQUEST,
+            format!(
+                "Invalid request: batches are not supported in protocol version {}",
+                version
+            ),
+        ));
+    }
+    let mut received = Received {
+        requests: Vec::new(),
+        errors: Vec::new(),
+        batch: incoming.is_batch(),
+    };
+
+    for message in incoming.into_messages() {
+        match message {
+            Ok(JsonRpcMessage::Request(request)) => received.requests.push(request),
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                server.handle_notification(notification, session_id)
+            }
+            Ok(JsonRpcMessage::Response(response)) => server.handle_response(response, session_id),
+            Err(invalid) => received.errors.push(invalid.into()),
+        }
+    }
+    Ok(received)
+}
+
+impl Received {
+    /// Whether nothing needs an answer
+    pub(crate) fn is_empty(&self) -> bool {
+        self.requests.is_empty() && self.errors.is_empty()
+    }
+
+    /// The request, if this is a single valid request rather than a batch
+    pub(crate) fn single_request(&self) -> Option<&JsonRpcRequest> {
+        match (self.batch, self.requests.as_slice()) {
+            (false, [request]) => Some(request),
+            _ => None,
+        }
+    }
+
+    /// Take the request out if this is a single valid request
+    pub(crate) fn into_single_request(mut self) -> std::result::Result<JsonRpcRequest, Self> {
+        match self.single_request() {
+            Some(_) => Ok(self.requests.remove(0)),
+            None => Err(self),
+        }
+    }
+
+    /// The error, if this is a single invalid message rather than a batch
+    pub(crate) fn single_error(&self) -> Option<&JsonRpcResponse> {
+        match (self.batch, self.errors.as_slice()) {
+            (false, [error]) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Handle the requests concurrently and build the reply: one response,
+    /// an array for a batch, or nothing when no response is due. Requests
+    /// the client cancelled are not answered.
+    pub(crate) async fn respond<F>(self, server: &McpServer, context: F) -> Option<Value>
+    where
+        F: Fn(&JsonRpcRequest) -> RequestContext,
+    {
+        let handled = self.requests.into_iter().map(|request| {
+            let ctx = context(&request);
+            let cancellation = ctx.cancellation_token().clone();
+            async move {
+                let response = server.handle_request_with_context(request, ctx).await;
+                (!cancellation.is_cancelled()).then_some(response)
+            }
+        });
+
+        let mut responses = self.errors;
+        responses.extend(
+            futures::future::join_all(handled)
+                .await
+                .into_iter()
+                .flatten(),
+        );
+
+        if self.batch {
+            (!responses.is_empty()).then(|| json!(responses))
+        } else {
+            responses.pop().map(|response| json!(response))
+        }
+    }
+}
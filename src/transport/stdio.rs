@@ -0,0 +1,194 @@
+//! Stdio transport for MCP servers.
+//!
+//! Messages are newline-delimited JSON-RPC: requests are read from stdin and
+//! responses and notifications are written to stdout. Stdout belongs to the
+//! protocol, so anything else (logs, diagnostics) must go to stderr.
+
+use crate::error::{Error, Result};
+use crate::server::McpServer;
+use crate::session_manager::TransportKind;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+impl McpServer {
+    /// Serve over stdin/stdout until stdin is closed.
+    ///
+    /// Requests are handled concurrently. On EOF the server stops reading,
+    /// lets in-flight requests finish and flushes their responses before
+    /// returning. Configure logging to write to stderr, e.g.
+    /// `tracing_subscriber::fmt().with_writer(std::io::stderr).init()`.
+    pub async fn serve_stdio(self: Arc<Self>) -> Result<()> {
+        self.serve_io(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    /// Serve newline-delimited JSON-RPC over any reader/writer pair
+    pub async fn serve_io<R, W>(self: Arc<Self>, reader: R, writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outbound, rx) = mpsc::unbounded_channel::<Value>();
+        let writer_task = tokio::spawn(write_messages(rx, writer));
+
+        let lines =
+            futures::stream::unfold(BufReader::new(reader).lines(), |mut lines| async move {
+                match lines.next_line().await {
+                    Ok(Some(line)) => Some((Ok(line), lines)),
+                    Ok(None) => None,
+                    Err(e) => Some((
+                        Err(Error::ConnectionError(format!(
+                            "Failed to read stdin: {}",
+                            e
+                        ))),
+                        lines,
+                    )),
+                }
+            });
+        let served = super::serve_connection(self, lines, outbound, TransportKind::Stdio).await;
+
+        // Every sender is gone, so the writer ends once it has flushed
+        let written = writer_task
+            .await
+            .map_err(|e| Error::InternalError(e.to_string()))?;
+        served.and(written)
+    }
+}
+
+/// Write each message as one line until every sender is dropped
+async fn write_messages<W>(mut rx: mpsc::UnboundedReceiver<Value>, mut writer: W) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(message) = rx.recv().await {
+        let mut line = message.to_string();
+        line.push('\n');
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| Error::ConnectionError(format!("Failed to write stdout: {}", e)))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| Error::ConnectionError(format!("Failed to flush stdout: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::ServerConfig;
+    use serde_json::json;
+
+    async fn run(input: &str) -> Vec<Value> {
+        let server = Arc::new(McpServer::with_config(ServerConfig::default()));
+        let (writer, mut reader) = tokio::io::duplex(1 << 16);
+        server.serve_io(input.as_bytes(), writer).await.unwrap();
+
+        let mut output = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut reader, &mut output)
+            .await
+            .unwrap();
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    /// An `initialize` request with ID "1" offering `version`
+    fn initialize(version: &str) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "initialize",
+            "params": {
+                "protocolVersion": version,
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_serves_until_eof() {
+        let input = format!(
+            "{}\n{}\n{}\n",
+            initialize("2025-06-18"),
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":"2","method":"tools/list","params":{}}"#,
+        );
+        let mut responses = run(&input).await;
+        responses.sort_by_key(|r| r["id"].as_str().unwrap_or_default().to_string());
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], "1");
+        assert!(responses[0]["result"]["serverInfo"].is_object());
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+        assert_eq!(responses[1]["id"], "2");
+        assert!(responses[1]["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_batch_with_numeric_ids() {
+        let input = format!(
+            "{}\n{}\n",
+            initialize("2025-03-26"),
+            r#"[{"jsonrpc":"2.0","id":1,"method":"tools/list"},{"jsonrpc":"2.0","method":"notifications/initialized"}]"#,
+        );
+        let responses = run(&input).await;
+
+        // Only the request is answered, in an array since it came in a batch
+        assert_eq!(responses.len(), 2);
+        let batch = responses[1].as_array().unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0]["id"], 1);
+        assert!(batch[0]["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_parse_error_keeps_serving() {
+        let input = format!(
+            "{{not json\n{}\n{}\n{}\n",
+            initialize("2025-03-26"),
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":"2","method":"tools/list","params":{}}"#,
+        );
+        let responses = run(&input).await;
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["error"]["code"], -32700);
+        assert_eq!(responses[2]["id"], "2");
+        assert!(responses[2]["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_requests_before_initialize() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            "\n",
+        );
+        let mut responses = run(input).await;
+        responses.sort_by_key(|r| r["id"].as_i64());
+
+        assert!(responses[0]["result"].is_object());
+        assert_eq!(responses[1]["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn test_batches_rejected_after_2025_06_18() {
+        let input = format!(
+            "{}\n{}\n",
+            initialize("2025-06-18"),
+            r#"[{"jsonrpc":"2.0","id":2,"method":"ping"}]"#,
+        );
+        let responses = run(&input).await;
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[1]["error"]["code"], -32600);
+    }
+}
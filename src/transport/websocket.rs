@@ -0,0 +1,143 @@
+//! WebSocket transport for MCP servers.
+//!
+//! Each text frame carries one JSON-RPC message or batch, in both
+//! directions. Every connection is its own session, which ends when the
+//! connection closes.
+
+use crate::error::{Error, Result};
+use crate::server::McpServer;
+use crate::session_manager::TransportKind;
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+impl McpServer {
+    /// Serve over WebSocket on `addr` (e.g. `"127.0.0.1:3001"`), accepting
+    /// connections until the listener fails
+    pub async fn serve_websocket(self: Arc<Self>, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::ConnectionError(format!("Failed to bind {}: {}", addr, e)))?;
+
+        tracing::info!("MCP server listening on ws://{}", addr);
+
+        loop {
+            let (stream, peer) = listener.accept().await.map_err(|e| {
+                Error::ConnectionError(format!("Failed to accept connection: {}", e))
+            })?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                match tokio_tungstenite::accept_async(stream).await {
+                    Ok(socket) => {
+                        if let Err(e) = server.serve_websocket_connection(socket).await {
+                            tracing::debug!(%peer, "WebSocket connection ended: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::warn!(%peer, "WebSocket handshake failed: {}", e),
+                }
+            });
+        }
+    }
+
+    /// Serve one accepted WebSocket connection until the client closes it.
+    ///
+    /// Use it to mount MCP on a socket accepted elsewhere; in-flight
+    /// requests finish before it returns.
+    pub async fn serve_websocket_connection<S>(
+        self: Arc<Self>,
+        socket: WebSocketStream<S>,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (mut sink, stream) = socket.split();
+        let (outbound, mut rx) = mpsc::unbounded_channel::<Value>();
+        let writer_task = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                sink.send(Message::Text(message.to_string()))
+                    .await
+                    .map_err(|e| {
+                        Error::ConnectionError(format!("Failed to send WebSocket message: {}", e))
+                    })?;
+            }
+            let _ = sink.close().await;
+            Ok::<_, Error>(())
+        });
+
+        // Pings are answered by tungstenite and binary frames carry no
+        // JSON-RPC, so only text frames are passed on
+        let messages = stream.filter_map(|frame| async move {
+            match frame {
+                Ok(Message::Text(text)) => Some(Ok(text)),
+                Ok(_) => None,
+                Err(e) => Some(Err(Error::ConnectionError(format!(
+                    "WebSocket error: {}",
+                    e
+                )))),
+            }
+        });
+        let served =
+            super::serve_connection(self, messages, outbound, TransportKind::WebSocket).await;
+
+        let written = writer_task
+            .await
+            .map_err(|e| Error::InternalError(e.to_string()))?;
+        served.and(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::ServerConfig;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+
+    #[tokio::test]
+    async fn test_connection_is_a_session() {
+        let mut server = McpServer::with_config(ServerConfig::default());
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let connected = events_tx.clone();
+        server.on_connect(move |info| {
+            let _ = connected.send(("connect", info.transport, info.client.is_some()));
+        });
+        server.on_disconnect(move |info| {
+            let _ = events_tx.send(("disconnect", info.transport, info.client.is_some()));
+        });
+        let server = Arc::new(server);
+
+        let (client_io, server_io) = tokio::io::duplex(1 << 16);
+        let serving = tokio::spawn(server.clone().serve_websocket_connection(
+            WebSocketStream::from_raw_socket(server_io, Role::Server, None).await,
+        ));
+        let mut client = WebSocketStream::from_raw_socket(client_io, Role::Client, None).await;
+
+        let initialize = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#;
+        client
+            .send(Message::Text(initialize.to_string()))
+            .await
+            .unwrap();
+        let reply = match client.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("unexpected frame {:?}", other),
+        };
+        assert_eq!(reply["result"]["protocolVersion"], "2025-06-18");
+        assert_eq!(server.sessions().len(), 1);
+
+        client.close(None).await.unwrap();
+        serving.await.unwrap().unwrap();
+        assert!(server.sessions().is_empty());
+        assert_eq!(
+            events.recv().await,
+            Some(("connect", TransportKind::WebSocket, false))
+        );
+        assert_eq!(
+            events.recv().await,
+            Some(("disconnect", TransportKind::WebSocket, true))
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! RFC 6570 URI templates, used to route `resources/read` to resource templates.
+//!
+//! Templates are matched against concrete URIs to extract their variables.
+//! A simple expression (`{name}`) matches within a single path segment; use
+//! reserved expansion (`{+path}`) for values that may contain `/`, e.g.
+//! `file:///{+path}`. Query expressions (`{?limit,offset}`) are optional.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Expression operator, the first character inside the braces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    /// `{var}`
+    Simple,
+    /// `{+var}`
+    Reserved,
+    /// `{#var}`
+    Fragment,
+    /// `{.var}`
+    Label,
+    /// `{/var}`
+    Path,
+    /// `{;var}`
+    PathParam,
+    /// `{?var}`
+    Query,
+    /// `{&var}`
+    QueryContinuation,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Operator::Reserved),
+            '#' => Some(Operator::Fragment),
+            '.' => Some(Operator::Label),
+            '/' => Some(Operator::Path),
+            ';' => Some(Operator::PathParam),
+            '?' => Some(Operator::Query),
+            '&' => Some(Operator::QueryContinuation),
+            _ => None,
+        }
+    }
+
+    /// Character that introduces a non-empty expansion
+    fn prefix(self) -> Option<char> {
+        match self {
+            Operator::Simple | Operator::Reserved => None,
+            Operator::Fragment => Some('#'),
+            Operator::Label => Some('.'),
+            Operator::Path => Some('/'),
+            Operator::PathParam => Some(';'),
+            Operator::Query => Some('?'),
+            Operator::QueryContinuation => Some('&'),
+        }
+    }
+
+    /// Separator between the values of an expansion
+    fn separator(self) -> char {
+        match self {
+            Operator::Simple | Operator::Reserved | Operator::Fragment => ',',
+            Operator::Label => '.',
+            Operator::Path => '/',
+            Operator::PathParam => ';',
+            Operator::Query | Operator::QueryContinuation => '&',
+        }
+    }
+
+    /// Whether values are expanded as `name=value` pairs
+    fn named(self) -> bool {
+        matches!(
+            self,
+            Operator::PathParam | Operator::Query | Operator::QueryContinuation
+        )
+    }
+
+    /// Whether values may contain reserved characters such as `/`
+    fn allows_reserved(self) -> bool {
+        matches!(self, Operator::Reserved | Operator::Fragment)
+    }
+
+    /// Whether `c` can appear in an expansion of this operator
+    fn allows(self, c: char) -> bool {
+        match self {
+            Operator::Simple => !matches!(c, '/' | '?' | '#'),
+            Operator::Reserved | Operator::Fragment => true,
+            Operator::Label | Operator::PathParam => !matches!(c, '/' | '?' | '#'),
+            Operator::Path => !matches!(c, '?' | '#'),
+            Operator::Query | Operator::QueryContinuation => c != '#',
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VarSpec {
+    name: String,
+    explode: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Expression {
+        operator: Operator,
+        variables: Vec<VarSpec>,
+    },
+}
+
+/// A parsed RFC 6570 URI template
+#[derive(Debug, Clone)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+impl UriTemplate {
+    /// Parse a template such as `db://tables/{name}/schema`
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Error::InvalidParams(format!("Invalid URI template '{}': {}", template, reason))
+        };
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            match rest.find(['{', '}']) {
+                Some(start) if rest[start..].starts_with('}') => {
+                    return Err(invalid("unmatched '}'"))
+                }
+                Some(start) => {
+                    if start > 0 {
+                        parts.push(Part::Literal(rest[..start].to_string()));
+                    }
+                    let end = rest[start..]
+                        .find('}')
+                        .map(|end| start + end)
+                        .ok_or_else(|| invalid("unclosed expression"))?;
+                    parts.push(
+                        parse_expression(&rest[start + 1..end])
+                            .map_err(|reason| invalid(&reason))?,
+                    );
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    parts.push(Part::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    /// The template as written
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Names of the template's variables, in order of appearance
+    pub fn variables(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Literal(_) => [].iter(),
+                Part::Expression { variables, .. } => variables.iter(),
+            })
+            .map(|var| var.name.as_str())
+            .collect()
+    }
+
+    /// Names of the variables in simple expressions (`{name}`), which match
+    /// within a single path segment
+    pub fn segment_variables(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Expression {
+                    operator: Operator::Simple,
+                    variables,
+                } => variables.iter(),
+                _ => [].iter(),
+            })
+            .map(|var| var.name.as_str())
+            .collect()
+    }
+
+    /// Match a URI against the template, returning the percent-decoded
+    /// value of each variable present in the URI
+    pub fn matches(&self, uri: &str) -> Option<HashMap<String, String>> {
+        let mut vars = HashMap::new();
+        match_parts(&self.parts, uri, &mut vars).then_some(vars)
+    }
+}
+
+impl fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// Parse the text between `{` and `}`
+fn parse_expression(body: &str) -> std::result::Result<Part, String> {
+    let mut chars = body.chars();
+    let operator = match chars.next() {
+        None => return Err("empty expression".to_string()),
+        Some(c) if "=,!@|".contains(c) => return Err(format!("unsupported operator '{}'", c)),
+        Some(c) => Operator::from_char(c),
+    };
+    let list = match operator {
+        Some(_) => chars.as_str(),
+        None => body,
+    };
+
+    let variables = list
+        .split(',')
+        .map(|spec| {
+            let (spec, explode) = match spec.strip_suffix('*') {
+                Some(spec) => (spec, true),
+                None => (spec, false),
+            };
+            // A prefix modifier only affects expansion, so it is ignored when matching
+            let name = match spec.split_once(':') {
+                Some((name, len)) if !len.is_empty() && len.chars().all(|c| c.is_ascii_digit()) => {
+                    name
+                }
+                Some(_) => return Err(format!("invalid prefix modifier in '{}'", spec)),
+                None => spec,
+            };
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '%'))
+            {
+                return Err(format!("invalid variable name '{}'", name));
+            }
+            Ok(VarSpec {
+                name: name.to_string(),
+                explode,
+            })
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(Part::Expression {
+        operator: operator.unwrap_or(Operator::Simple),
+        variables,
+    })
+}
+
+fn match_parts(parts: &[Part], input: &str, vars: &mut HashMap<String, String>) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return input.is_empty();
+    };
+
+    match part {
+        Part::Literal(literal) => input
+            .strip_prefix(literal.as_str())
+            .is_some_and(|input| match_parts(rest, input, vars)),
+        Part::Expression {
+            operator,
+            variables,
+        } => {
+            // Try the shortest expansion first and backtrack until the rest matches
+            for end in expansion_ends(*operator, input) {
+                let mut captured = vars.clone();
+                if capture(*operator, variables, &input[..end], &mut captured)
+                    && match_parts(rest, &input[end..], &mut captured)
+                {
+                    *vars = captured;
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Candidate lengths of an expansion at the start of `input`
+fn expansion_ends(operator: Operator, input: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+
+    // Expressions with a prefix expand to nothing when their variables are undefined
+    let start = match operator.prefix() {
+        Some(prefix) => {
+            ends.push(0);
+            if !input.starts_with(prefix) {
+                return ends;
+            }
+            prefix.len_utf8()
+        }
+        None => 0,
+    };
+
+    for (i, c) in input[start..].char_indices() {
+        let end = start + i;
+        if end > 0 {
+            ends.push(end);
+        }
+        if !operator.allows(c) {
+            return ends;
+        }
+    }
+    if !input.is_empty() {
+        ends.push(input.len());
+    }
+    ends
+}
+
+/// Assign the values in an expansion to the expression's variables
+fn capture(
+    operator: Operator,
+    variables: &[VarSpec],
+    text: &str,
+    vars: &mut HashMap<String, String>,
+) -> bool {
+    let text = match operator.prefix() {
+        Some(_) if text.is_empty() => return true,
+        Some(prefix) => &text[prefix.len_utf8()..],
+        None => text,
+    };
+
+    if operator.allows_reserved() && variables.len() == 1 {
+        return insert(vars, &variables[0].name, text);
+    }
+
+    let separator = operator.separator();
+    if operator.named() {
+        return text.split(separator).all(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            variables.iter().any(|var| var.name == name) && insert(vars, name, value)
+        });
+    }
+
+    let mut values = text.split(separator);
+    for (i, var) in variables.iter().enumerate() {
+        if var.explode && i == variables.len() - 1 {
+            let rest: Vec<&str> = values.by_ref().collect();
+            return rest.is_empty() || insert(vars, &var.name, &rest.join(&separator.to_string()));
+        }
+        match values.next() {
+            Some(value) if !insert(vars, &var.name, value) => return false,
+            Some(_) => {}
+            None => break,
+        }
+    }
+    values.next().is_none()
+}
+
+/// Record a decoded value, rejecting conflicting values for a repeated variable
+fn insert(vars: &mut HashMap<String, String>, name: &str, raw: &str) -> bool {
+    let Ok(value) = urlencoding::decode(raw) else {
+        return false;
+    };
+    match vars.get(name) {
+        Some(existing) => *existing == value,
+        None => {
+            vars.insert(name.to_string(), value.into_owned());
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(template: &str, uri: &str) -> Option<HashMap<String, String>> {
+        UriTemplate::parse(template).unwrap().matches(uri)
+    }
+
+    #[test]
+    fn test_simple_expression() {
+        let vars = matches("db://tables/{name}/schema", "db://tables/users/schema").unwrap();
+        assert_eq!(vars["name"], "users");
+
+        let vars = matches("db://tables/{name}/schema", "db://tables/my%20table/schema").unwrap();
+        assert_eq!(vars["name"], "my table");
+
+        assert!(matches("db://tables/{name}/schema", "db://tables/users/rows").is_none());
+        assert!(matches("db://tables/{name}/schema", "db://tables//schema").is_none());
+    }
+
+    #[test]
+    fn test_reserved_expression_spans_segments() {
+        assert!(matches("file:///{path}", "file:///src/main.rs").is_none());
+        let template = UriTemplate::parse("file:///{path}{?rev}").unwrap();
+        assert_eq!(template.segment_variables(), ["path"]);
+        assert_eq!(
+            matches("file:///{path}", "file:///notes.txt").unwrap()["path"],
+            "notes.txt"
+        );
+        assert_eq!(
+            matches("file:///{+path}", "file:///src/main.rs").unwrap()["path"],
+            "src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_path_and_query_expressions() {
+        let vars = matches("repo://{owner}{/path*}", "repo://me/src/lib.rs").unwrap();
+        assert_eq!(vars["owner"], "me");
+        assert_eq!(vars["path"], "src/lib.rs");
+
+        let vars = matches("search://{term}{?limit,offset}", "search://rust?limit=10").unwrap();
+        assert_eq!(vars["term"], "rust");
+        assert_eq!(vars["limit"], "10");
+        assert!(!vars.contains_key("offset"));
+
+        let vars = matches("search://{term}{?limit,offset}", "search://rust").unwrap();
+        assert_eq!(vars.len(), 1);
+        assert!(matches("search://{term}{?limit}", "search://rust?page=2").is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(UriTemplate::parse("db://{name").is_err());
+        assert!(UriTemplate::parse("db://{}").is_err());
+        assert!(UriTemplate::parse("db://name}").is_err());
+        assert!(UriTemplate::parse("db://{=name}").is_err());
+        assert!(UriTemplate::parse("db://{na me}").is_err());
+
+        let template = UriTemplate::parse("db://{schema}/{table:3}{?limit}").unwrap();
+        assert_eq!(template.variables(), vec!["schema", "table", "limit"]);
+    }
+}